@@ -1,2 +1,5 @@
+#[cfg(feature = "png")]
+pub mod png;
+
 #[cfg(feature = "serde")]
 pub mod serde;
@@ -0,0 +1,123 @@
+// rust std imports
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+// external imports
+use png::{BitDepth, ColorType};
+
+// internal imports
+use crate::prelude::*;
+
+const ORIGIN_KEYWORD: &str = "TES3:Origin";
+const BASE_HEIGHT_KEYWORD: &str = "TES3:BaseHeight";
+
+impl TerrainImage<u16> {
+    /// Save as a 16-bit grayscale png, storing the image metadata in text chunks.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let bytes: Vec<u8> = self.pixels.iter().flat_map(|pixel| pixel.to_be_bytes()).collect();
+        save_png(self, path, ColorType::Grayscale, BitDepth::Sixteen, &bytes)
+    }
+
+    /// Load a 16-bit grayscale png, as saved by [`TerrainImage::save_png`].
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (mut image, bytes) = load_png(path, ColorType::Grayscale, BitDepth::Sixteen)?;
+        image.pixels = bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        Ok(image)
+    }
+}
+
+impl TerrainImage<[u8; 3]> {
+    /// Save as an 8-bit RGB png, storing the image metadata in text chunks.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        save_png(self, path, ColorType::Rgb, BitDepth::Eight, self.pixels.as_flattened())
+    }
+
+    /// Load an 8-bit RGB png, as saved by [`TerrainImage::save_png`].
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let (mut image, bytes) = load_png(path, ColorType::Rgb, BitDepth::Eight)?;
+        image.pixels = bytes.chunks_exact(3).map(|chunk| [chunk[0], chunk[1], chunk[2]]).collect();
+        Ok(image)
+    }
+}
+
+fn save_png<T>(
+    image: &TerrainImage<T>,
+    path: impl AsRef<Path>,
+    color_type: ColorType,
+    bit_depth: BitDepth,
+    bytes: &[u8],
+) -> io::Result<()> {
+    let file = std::fs::File::create(path)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+
+    let (x, y) = image.origin;
+    encoder.add_text_chunk(ORIGIN_KEYWORD.into(), format!("{x},{y}"))?;
+    encoder.add_text_chunk(BASE_HEIGHT_KEYWORD.into(), image.base_height.to_string())?;
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(bytes)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+fn load_png<T>(
+    path: impl AsRef<Path>,
+    color_type: ColorType,
+    bit_depth: BitDepth,
+) -> io::Result<(TerrainImage<T>, Vec<u8>)> {
+    let file = std::fs::File::open(path)?;
+
+    let mut reader = png::Decoder::new(BufReader::new(file)).read_info()?;
+
+    let info = reader.info();
+    if (info.color_type, info.bit_depth) != (color_type, bit_depth) {
+        Reader::error(format!(
+            "Unexpected png format: expected {color_type:?} {bit_depth:?}, found {:?} {:?}",
+            info.color_type, info.bit_depth
+        ))?;
+    }
+
+    let mut image = TerrainImage {
+        origin: (0, 0),
+        width: info.width,
+        height: info.height,
+        base_height: 0.0,
+        pixels: vec![],
+    };
+
+    for chunk in &info.uncompressed_latin1_text {
+        match chunk.keyword.as_str() {
+            ORIGIN_KEYWORD => {
+                let origin = chunk
+                    .text
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+                image.origin = origin.ok_or_else(|| invalid_metadata(chunk))?;
+            }
+            BASE_HEIGHT_KEYWORD => {
+                image.base_height = chunk.text.parse().map_err(|_| invalid_metadata(chunk))?;
+            }
+            _ => {}
+        }
+    }
+
+    let mut bytes = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut bytes)?;
+    bytes.truncate(frame.buffer_size());
+
+    Ok((image, bytes))
+}
+
+fn invalid_metadata(chunk: &png::text_metadata::TEXtChunk) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid png metadata: {} = {}", chunk.keyword, chunk.text),
+    )
+}
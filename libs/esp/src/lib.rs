@@ -6,6 +6,9 @@ pub use types::*;
 pub mod traits;
 pub use traits::*;
 
+pub mod scripting;
+pub use scripting::*;
+
 pub(crate) mod features;
 pub(crate) mod macros;
 
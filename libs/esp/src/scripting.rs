@@ -0,0 +1,22 @@
+mod ast;
+pub use ast::*;
+
+mod compiler;
+pub use compiler::*;
+
+mod decompiler;
+pub use decompiler::*;
+
+mod diagnostics;
+pub use diagnostics::*;
+
+mod functions;
+pub use functions::*;
+
+mod lexer;
+pub use lexer::*;
+
+mod lint;
+pub use lint::*;
+
+mod opcodes;
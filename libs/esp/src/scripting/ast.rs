@@ -0,0 +1,790 @@
+//! A syntax tree for script source, used for analysis rather than compilation.
+//!
+//! Parsing is independent of loaded content, except for the function table, which determines how
+//! many arguments a function call inside an expression takes. Unknown functions followed by
+//! argument-like tokens are parsed as calls, so that they can be reported by the linter.
+//!
+
+use crate::prelude::*;
+
+/// An identifier or id, as written in source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Identifier {
+    pub text: String,
+    pub span: Span,
+}
+
+impl From<&Token<'_>> for Identifier {
+    fn from(token: &Token<'_>) -> Self {
+        Self {
+            text: token.text.into(),
+            span: token.span,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BinaryOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOperator {
+    /// All operators, comparisons first.
+    const ALL: [Self; 10] = [
+        Self::Equal,
+        Self::NotEqual,
+        Self::Less,
+        Self::LessEqual,
+        Self::Greater,
+        Self::GreaterEqual,
+        Self::Add,
+        Self::Subtract,
+        Self::Multiply,
+        Self::Divide,
+    ];
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+        }
+    }
+
+    pub const fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Self::Equal | Self::NotEqual | Self::Less | Self::LessEqual | Self::Greater | Self::GreaterEqual
+        )
+    }
+
+    fn from_token(token: &Token<'_>) -> Option<Self> {
+        if token.kind != TokenKind::Operator {
+            return None;
+        }
+        Self::ALL.into_iter().find(|operator| operator.symbol() == token.text)
+    }
+}
+
+/// A function call, optionally on an explicit reference, as in `reference->Function arguments`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub reference: Option<Identifier>,
+    pub function: Identifier,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpressionKind {
+    /// A number, including its sign when used as a function argument.
+    Number(String),
+    /// A quoted string.
+    String(String),
+    /// A variable, a function without arguments, or an unquoted id.
+    Name(String),
+    /// A variable of the script attached to another object, as in `reference.name`.
+    Member {
+        reference: Identifier,
+        name: Identifier,
+    },
+    Call(Box<Call>),
+    Negate(Box<Expression>),
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// An expression that could not be parsed. A diagnostic has been reported for it.
+    Invalid,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    /// Visit this expression and all expressions within it, outermost first.
+    pub fn walk(&self, visit: &mut impl FnMut(&Self)) {
+        visit(self);
+        match &self.kind {
+            ExpressionKind::Call(call) => {
+                for argument in &call.arguments {
+                    argument.walk(visit);
+                }
+            }
+            ExpressionKind::Negate(operand) => operand.walk(visit),
+            ExpressionKind::Binary { left, right, .. } => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One branch of an `If` block: the `If` or an `ElseIf`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Branch {
+    pub condition: Expression,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementKind {
+    Declare {
+        kind: VariableKind,
+        name: Identifier,
+    },
+    Set {
+        target: Expression,
+        value: Expression,
+    },
+    If {
+        branches: Vec<Branch>,
+        otherwise: Option<Vec<Statement>>,
+    },
+    While {
+        condition: Expression,
+        body: Vec<Statement>,
+    },
+    Return,
+    Call(Call),
+}
+
+/// A statement. For blocks, the span covers the opening line only.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+/// The syntax tree of a script, or of a dialogue result which has no `Begin` and `End`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScriptAst {
+    /// The name given by the `Begin` statement.
+    pub name: Option<Identifier>,
+    pub body: Vec<Statement>,
+}
+
+impl ScriptAst {
+    /// Parse script source, returning the syntax tree and any syntax errors.
+    ///
+    /// Parsing continues after errors, so the tree is available even for invalid source.
+    ///
+    pub fn parse(source: &str, functions: &ScriptFunctions) -> (Self, Vec<Diagnostic>) {
+        let (tokens, diagnostics) = tokenize(source);
+
+        let mut parser = Parser {
+            functions,
+            ast: Self::default(),
+            blocks: vec![],
+            ended: false,
+            diagnostics,
+        };
+
+        for line in tokens.split(|token| token.kind == TokenKind::Newline) {
+            if !line.is_empty() {
+                parser.line(line);
+            }
+        }
+
+        parser.close_unfinished();
+
+        (parser.ast, parser.diagnostics)
+    }
+
+    /// Visit all statements, including those within blocks, in source order.
+    pub fn walk(&self, visit: &mut impl FnMut(&Statement)) {
+        walk_statements(&self.body, visit);
+    }
+}
+
+fn walk_statements(statements: &[Statement], visit: &mut impl FnMut(&Statement)) {
+    for statement in statements {
+        visit(statement);
+        match &statement.kind {
+            StatementKind::If { branches, otherwise } => {
+                for branch in branches {
+                    walk_statements(&branch.body, visit);
+                }
+                if let Some(otherwise) = otherwise {
+                    walk_statements(otherwise, visit);
+                }
+            }
+            StatementKind::While { body, .. } => walk_statements(body, visit),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+enum OpenKind {
+    If {
+        branches: Vec<Branch>,
+        /// The condition and span of the current branch, or `None` within `Else`.
+        current: Option<(Expression, Span)>,
+    },
+    While {
+        condition: Expression,
+    },
+}
+
+/// A block whose closing statement has not been parsed yet.
+#[derive(Debug)]
+struct OpenBlock {
+    kind: OpenKind,
+    span: Span,
+    statements: Vec<Statement>,
+}
+
+#[derive(Debug)]
+struct Parser<'a> {
+    functions: &'a ScriptFunctions,
+    ast: ScriptAst,
+    blocks: Vec<OpenBlock>,
+    ended: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Parser<'_> {
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::error(span, message));
+    }
+
+    fn push(&mut self, statement: Statement) {
+        match self.blocks.last_mut() {
+            Some(block) => block.statements.push(statement),
+            None => self.ast.body.push(statement),
+        }
+    }
+
+    fn line(&mut self, line: &[Token<'_>]) {
+        let first = &line[0];
+        let span = first.span.to(line[line.len() - 1].span);
+        let rest = &line[1..];
+
+        if self.ended {
+            self.error(span, "Unexpected statement after End");
+            return;
+        }
+
+        let keyword = if first.kind == TokenKind::Identifier {
+            first.text.to_ascii_lowercase()
+        } else {
+            String::new()
+        };
+
+        match keyword.as_str() {
+            "begin" => {
+                if self.ast.name.is_some() || !self.ast.body.is_empty() || !self.blocks.is_empty() {
+                    self.error(span, "Unexpected Begin");
+                } else if let [name] = rest {
+                    self.ast.name = Some(name.into());
+                } else {
+                    self.error(span, "Expected script name");
+                }
+            }
+            "end" => {
+                self.close_unfinished();
+                self.ended = true;
+            }
+            "short" | "long" | "float" => {
+                let kind = VariableKind::from_keyword(&keyword).unwrap_or(VariableKind::Short);
+                if let [name] = rest {
+                    self.push(Statement {
+                        kind: StatementKind::Declare { kind, name: name.into() },
+                        span,
+                    });
+                } else {
+                    self.error(span, "Expected variable name");
+                }
+            }
+            "set" => self.set(rest, span),
+            "if" | "while" => {
+                let condition = self.expression(rest, span);
+                let kind = if keyword == "if" {
+                    OpenKind::If {
+                        branches: vec![],
+                        current: Some((condition, span)),
+                    }
+                } else {
+                    OpenKind::While { condition }
+                };
+                self.blocks.push(OpenBlock {
+                    kind,
+                    span,
+                    statements: vec![],
+                });
+            }
+            "elseif" | "else" => self.branch(&keyword, rest, span),
+            "endif" | "endwhile" => self.end_block(&keyword, rest, span),
+            "return" => {
+                if !rest.is_empty() {
+                    self.error(span, "Unexpected tokens after Return");
+                }
+                self.push(Statement {
+                    kind: StatementKind::Return,
+                    span,
+                });
+            }
+            _ => {
+                if let Some(call) = self.statement_call(line, span) {
+                    self.push(Statement {
+                        kind: StatementKind::Call(call),
+                        span,
+                    });
+                }
+            }
+        }
+    }
+
+    fn set(&mut self, rest: &[Token<'_>], span: Span) {
+        let Some(to) = rest.iter().position(|token| token.is("to")) else {
+            self.error(span, "Expected: Set <variable> To <expression>");
+            return;
+        };
+        let target = self.expression(&rest[..to], span);
+        if !matches!(
+            target.kind,
+            ExpressionKind::Name(_) | ExpressionKind::Member { .. } | ExpressionKind::Invalid
+        ) {
+            self.error(target.span, "Expected a variable");
+        }
+        let value = self.expression(&rest[to + 1..], span);
+        self.push(Statement {
+            kind: StatementKind::Set { target, value },
+            span,
+        });
+    }
+
+    /// Start the next branch of the innermost `If` block, for `ElseIf` and `Else`.
+    fn branch(&mut self, keyword: &str, rest: &[Token<'_>], span: Span) {
+        let condition = (keyword == "elseif").then(|| self.expression(rest, span));
+        if condition.is_none() && !rest.is_empty() {
+            self.error(span, "Unexpected tokens after Else");
+        }
+
+        let Some(OpenBlock {
+            kind: OpenKind::If {
+                branches,
+                current: current @ Some(_),
+            },
+            statements,
+            ..
+        }) = self.blocks.last_mut()
+        else {
+            let message = if condition.is_some() {
+                "ElseIf without If"
+            } else {
+                "Else without If"
+            };
+            self.error(span, message);
+            return;
+        };
+
+        if let Some((previous, previous_span)) = current.take() {
+            branches.push(Branch {
+                condition: previous,
+                body: std::mem::take(statements),
+                span: previous_span,
+            });
+        }
+        *current = condition.map(|condition| (condition, span));
+    }
+
+    /// Close the innermost block, for `EndIf` and `EndWhile`.
+    fn end_block(&mut self, keyword: &str, rest: &[Token<'_>], span: Span) {
+        let expected = self.blocks.last().is_some_and(|block| match block.kind {
+            OpenKind::If { .. } => keyword == "endif",
+            OpenKind::While { .. } => keyword == "endwhile",
+        });
+        let name = if keyword == "endif" { "EndIf" } else { "EndWhile" };
+        if !expected {
+            let message = if keyword == "endif" {
+                "EndIf without If"
+            } else {
+                "EndWhile without While"
+            };
+            self.error(span, message);
+            return;
+        }
+        if !rest.is_empty() {
+            self.error(span, format!("Unexpected tokens after {name}"));
+        }
+        if let Some(block) = self.blocks.pop() {
+            self.close(block);
+        }
+    }
+
+    /// Turn a finished block into a statement.
+    fn close(&mut self, block: OpenBlock) {
+        let kind = match block.kind {
+            OpenKind::If { mut branches, current } => {
+                let otherwise = match current {
+                    Some((condition, span)) => {
+                        branches.push(Branch {
+                            condition,
+                            body: block.statements,
+                            span,
+                        });
+                        None
+                    }
+                    None => Some(block.statements),
+                };
+                StatementKind::If { branches, otherwise }
+            }
+            OpenKind::While { condition } => StatementKind::While {
+                condition,
+                body: block.statements,
+            },
+        };
+        self.push(Statement { kind, span: block.span });
+    }
+
+    /// Close all open blocks, reporting their missing closing statements.
+    fn close_unfinished(&mut self) {
+        while let Some(block) = self.blocks.pop() {
+            self.error(block.span, "Block is never closed");
+            self.close(block);
+        }
+    }
+
+    /// Parse a function call statement, as in `Function arguments` or `reference->Function arguments`.
+    fn statement_call(&mut self, line: &[Token<'_>], span: Span) -> Option<Call> {
+        let (reference, function, rest) = match line {
+            [reference, arrow, function, rest @ ..] if arrow.is("->") => (Some(reference.into()), function, rest),
+            [function, rest @ ..] => (None, function, rest),
+            [] => return None,
+        };
+
+        if function.kind != TokenKind::Identifier {
+            self.error(function.span, format!("Unexpected: {}", function.text));
+            return None;
+        }
+
+        let mut arguments = vec![];
+        let mut pos = 0;
+        while pos < rest.len() {
+            if let Some(argument) = argument(rest, &mut pos) {
+                arguments.push(argument);
+            } else {
+                self.error(rest[pos].span, format!("Unexpected: {}", rest[pos].text));
+                return None;
+            }
+        }
+
+        Some(Call {
+            reference,
+            function: function.into(),
+            arguments,
+            span,
+        })
+    }
+
+    /// Parse a whole expression, reporting unexpected trailing tokens.
+    fn expression(&mut self, tokens: &[Token<'_>], span: Span) -> Expression {
+        if tokens.is_empty() {
+            self.error(span, "Expected an expression");
+            return Expression {
+                kind: ExpressionKind::Invalid,
+                span,
+            };
+        }
+
+        let mut pos = 0;
+        let expression = self.comparison(tokens, &mut pos);
+        if expression.kind != ExpressionKind::Invalid && pos < tokens.len() {
+            if tokens[pos].is("=") {
+                self.error(tokens[pos].span, "Expected a comparison, did you mean '=='?");
+                return expression;
+            }
+            let span = tokens[pos].span.to(tokens[tokens.len() - 1].span);
+            self.error(span, format!("Unexpected: {}", tokens[pos].text));
+        }
+        expression
+    }
+
+    /// Parse a left-associative sequence of operands joined by any of `operators`.
+    fn binary(
+        &mut self,
+        tokens: &[Token<'_>],
+        pos: &mut usize,
+        operators: &[BinaryOperator],
+        operand: fn(&mut Self, &[Token<'_>], &mut usize) -> Expression,
+    ) -> Expression {
+        let mut left = operand(self, tokens, pos);
+        while let Some(operator) = tokens
+            .get(*pos)
+            .and_then(BinaryOperator::from_token)
+            .filter(|operator| operators.contains(operator))
+        {
+            *pos += 1;
+            let right = operand(self, tokens, pos);
+            left = Expression {
+                span: left.span.to(right.span),
+                kind: ExpressionKind::Binary {
+                    operator,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+            };
+        }
+        left
+    }
+
+    fn comparison(&mut self, tokens: &[Token<'_>], pos: &mut usize) -> Expression {
+        self.binary(tokens, pos, &BinaryOperator::ALL[..6], Self::sum)
+    }
+
+    fn sum(&mut self, tokens: &[Token<'_>], pos: &mut usize) -> Expression {
+        self.binary(tokens, pos, &[BinaryOperator::Add, BinaryOperator::Subtract], Self::product)
+    }
+
+    fn product(&mut self, tokens: &[Token<'_>], pos: &mut usize) -> Expression {
+        self.binary(tokens, pos, &[BinaryOperator::Multiply, BinaryOperator::Divide], Self::unary)
+    }
+
+    fn unary(&mut self, tokens: &[Token<'_>], pos: &mut usize) -> Expression {
+        if let Some(token) = tokens.get(*pos).filter(|token| token.is("-")) {
+            *pos += 1;
+            let operand = self.unary(tokens, pos);
+            return Expression {
+                span: token.span.to(operand.span),
+                kind: ExpressionKind::Negate(Box::new(operand)),
+            };
+        }
+        self.primary(tokens, pos)
+    }
+
+    fn primary(&mut self, tokens: &[Token<'_>], pos: &mut usize) -> Expression {
+        let Some(token) = tokens.get(*pos) else {
+            let span = tokens[tokens.len() - 1].span;
+            self.error(span, "Expected an operand");
+            return Expression {
+                kind: ExpressionKind::Invalid,
+                span,
+            };
+        };
+        *pos += 1;
+
+        let invalid = Expression {
+            kind: ExpressionKind::Invalid,
+            span: token.span,
+        };
+
+        match token.kind {
+            TokenKind::Number => {
+                return Expression {
+                    kind: ExpressionKind::Number(token.text.into()),
+                    span: token.span,
+                };
+            }
+            TokenKind::Operator if token.is("(") => {
+                let mut inner = self.comparison(tokens, pos);
+                match tokens.get(*pos) {
+                    Some(close) if close.is(")") => {
+                        *pos += 1;
+                        inner.span = token.span.to(close.span);
+                    }
+                    _ if inner.kind == ExpressionKind::Invalid => {}
+                    _ => {
+                        self.error(token.span, "Unclosed parenthesis");
+                        return invalid;
+                    }
+                }
+                return inner;
+            }
+            TokenKind::Operator | TokenKind::Newline => {
+                self.error(token.span, format!("Unexpected: {}", token.text));
+                return invalid;
+            }
+            TokenKind::Identifier | TokenKind::String => {}
+        }
+
+        let next = tokens.get(*pos);
+
+        if next.is_some_and(|next| next.is("->")) {
+            let Some(function) = tokens.get(*pos + 1).filter(|token| token.kind == TokenKind::Identifier) else {
+                self.error(token.span, "Expected a function");
+                return invalid;
+            };
+            *pos += 2;
+            return self.call(Some(token.into()), function, tokens, pos);
+        }
+
+        if next.is_some_and(|next| next.is(".")) {
+            let Some(name) = tokens.get(*pos + 1).filter(|token| token.kind == TokenKind::Identifier) else {
+                self.error(token.span, "Expected a variable");
+                return invalid;
+            };
+            *pos += 2;
+            return Expression {
+                kind: ExpressionKind::Member {
+                    reference: token.into(),
+                    name: name.into(),
+                },
+                span: token.span.to(name.span),
+            };
+        }
+
+        if token.kind == TokenKind::String {
+            return Expression {
+                kind: ExpressionKind::String(token.text.into()),
+                span: token.span,
+            };
+        }
+
+        let takes_arguments = self
+            .functions
+            .get(token.text)
+            .map_or_else(|| next.is_some_and(Token::is_id), |function| !function.arguments.is_empty());
+        if takes_arguments {
+            return self.call(None, token, tokens, pos);
+        }
+
+        Expression {
+            kind: ExpressionKind::Name(token.text.into()),
+            span: token.span,
+        }
+    }
+
+    /// Parse a function call within an expression, starting after the function name.
+    fn call(
+        &self,
+        reference: Option<Identifier>,
+        function: &Token<'_>,
+        tokens: &[Token<'_>],
+        pos: &mut usize,
+    ) -> Expression {
+        // Known functions take at most their declared number of arguments, unless variadic.
+        let limit = self
+            .functions
+            .get(function.text)
+            .and_then(ScriptFunction::max_arguments)
+            .unwrap_or(usize::MAX);
+
+        let mut arguments = vec![];
+        while arguments.len() < limit {
+            let mut next = *pos;
+            let Some(argument) = argument(tokens, &mut next) else {
+                break;
+            };
+            // Without a known signature, a minus is read as subtraction rather than a negative argument.
+            if tokens[*pos].is("-") && limit == usize::MAX {
+                break;
+            }
+            *pos = next;
+            arguments.push(argument);
+        }
+
+        let start = reference.as_ref().map_or(function.span, |reference| reference.span);
+        let end = arguments.last().map_or(function.span, |argument| argument.span);
+        let span = start.to(end);
+        Expression {
+            kind: ExpressionKind::Call(Box::new(Call {
+                reference,
+                function: function.into(),
+                arguments,
+                span,
+            })),
+            span,
+        }
+    }
+}
+
+/// Parse a single function argument at `tokens[*pos]`: an id, a string, or a signed number.
+fn argument(tokens: &[Token<'_>], pos: &mut usize) -> Option<Expression> {
+    let token = tokens.get(*pos)?;
+    if token.is("-") {
+        let number = tokens.get(*pos + 1).filter(|token| token.kind == TokenKind::Number)?;
+        *pos += 2;
+        return Some(Expression {
+            kind: ExpressionKind::Number(format!("-{}", number.text)),
+            span: token.span.to(number.span),
+        });
+    }
+    let kind = match token.kind {
+        TokenKind::Number => ExpressionKind::Number(token.text.into()),
+        TokenKind::String => ExpressionKind::String(token.text.into()),
+        TokenKind::Identifier => ExpressionKind::Name(token.text.into()),
+        TokenKind::Operator | TokenKind::Newline => return None,
+    };
+    *pos += 1;
+    Some(Expression { kind, span: token.span })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_blocks_and_expressions() {
+        let source = "begin test\nshort x\nif ( x == 1 )\n\tset x to -x * 2 + 1\nelseif x > 2\n\tplayer->AddItem Gold_001 10\nelse\n\treturn\nendif\nwhile x\nendwhile\nend";
+        let (ast, diagnostics) = ScriptAst::parse(source, &ScriptFunctions::default());
+
+        assert_eq!(diagnostics, []);
+        assert_eq!(ast.name.map(|name| name.text).as_deref(), Some("test"));
+        assert_eq!(ast.body.len(), 3);
+
+        let StatementKind::If { branches, otherwise } = &ast.body[1].kind else {
+            panic!("expected an If block");
+        };
+        assert_eq!(branches.len(), 2);
+        assert_eq!(otherwise.as_ref().map(|body| &body[0].kind), Some(&StatementKind::Return));
+        assert_eq!(branches[0].span.start.line, 3);
+        assert_eq!(branches[1].span.start.line, 5);
+
+        // `-x * 2 + 1` is `((-x) * 2) + 1`.
+        let StatementKind::Set { value, .. } = &branches[0].body[0].kind else {
+            panic!("expected a Set statement");
+        };
+        let ExpressionKind::Binary { operator, left, .. } = &value.kind else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(*operator, BinaryOperator::Add);
+        assert!(matches!(
+            left.kind,
+            ExpressionKind::Binary {
+                operator: BinaryOperator::Multiply,
+                ..
+            }
+        ));
+
+        let StatementKind::Call(call) = &branches[1].body[0].kind else {
+            panic!("expected a function call");
+        };
+        assert_eq!(
+            call.reference.as_ref().map(|reference| reference.text.as_str()),
+            Some("player")
+        );
+        assert_eq!(call.function.text, "AddItem");
+        assert_eq!(call.arguments.len(), 2);
+
+        let (_, diagnostics) = ScriptAst::parse("if x\nelse\nelseif y\n", &ScriptFunctions::default());
+        let messages = diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            ["3:1: error: ElseIf without If", "1:1: error: Block is never closed"]
+        );
+    }
+}
@@ -0,0 +1,917 @@
+//! Compilation of script source into `SCDT` bytecode.
+//!
+//! Bytecode is a sequence of statements, each starting with a `u16` opcode. `Begin` and variable
+//! declarations produce no bytecode, and `End` is [`opcodes::END`]. Control flow statements store
+//! a `u8` jump: for `If` and `ElseIf` the number of statements to skip to reach the next branch
+//! of the block, for `Else` the number to skip to reach `EndIf`, and for `While` the number to
+//! skip to leave the loop.
+//!
+//! Expressions are stored in infix order as space separated tokens, prefixed by a `u8` length.
+//! Numbers and operators are stored as text, local variables as their type prefix (`s`, `l` or
+//! `f`) followed by their `u16` index, and globals and function calls as described in
+//! [`opcodes`].
+//!
+
+use super::opcodes;
+use crate::prelude::*;
+
+const COMPARISONS: [&str; 6] = ["==", "!=", "<", "<=", ">", ">="];
+
+/// References which exist in every game without being defined by a plugin.
+const BUILTIN_REFERENCES: [&str; 1] = ["player"];
+
+/// The output of [`ScriptCompiler::compile`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Compilation {
+    /// The name given by the `Begin` statement.
+    pub name: String,
+    pub header: ScriptHeader,
+    pub variables: ScriptVariables,
+    pub bytecode: Vec<u8>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Compilation {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+/// Compiles script source into bytecode, resolving identifiers against a set of plugins.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptCompiler {
+    pub functions: ScriptFunctions,
+    /// Lowercase ids of global variables.
+    globals: HashSet<String>,
+    /// Lowercase ids of all objects.
+    objects: HashSet<String>,
+    /// Lowercase object ids mapped to the lowercase id of their script.
+    object_scripts: HashMap<String, String>,
+    /// Lowercase script ids mapped to their variables.
+    script_variables: HashMap<String, ScriptVariables>,
+    /// Lowercase dialogue ids mapped to their type.
+    dialogues: HashMap<String, DialogueType2>,
+}
+
+impl ScriptCompiler {
+    pub fn from_plugin(plugin: &Plugin) -> Self {
+        Self::from_plugins([plugin])
+    }
+
+    /// Create a compiler for the given plugins, which must be provided in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::default();
+
+        for object in plugins.into_iter().flat_map(|plugin| &plugin.objects) {
+            let id = object.editor_id_ascii_lowercase().into_owned();
+
+            if object.deleted() {
+                this.globals.remove(&id);
+                this.objects.remove(&id);
+                this.object_scripts.remove(&id);
+                this.dialogues.remove(&id);
+                continue;
+            }
+
+            match object {
+                TES3Object::GlobalVariable(_) => {
+                    this.globals.insert(id.clone());
+                }
+                TES3Object::Script(script) => {
+                    this.script_variables.insert(id.clone(), script.variables.clone());
+                }
+                TES3Object::Dialogue(dialogue) => {
+                    this.dialogues.insert(id.clone(), dialogue.dialogue_type);
+                }
+                _ => {}
+            }
+
+            if let Some(script) = object_script(object).filter(|script| !script.is_empty()) {
+                this.object_scripts.insert(id.clone(), script.to_ascii_lowercase());
+            }
+
+            this.objects.insert(id);
+        }
+
+        this
+    }
+
+    /// Check whether an object with the given id exists, ignoring case.
+    ///
+    /// Built-in references such as `player` always exist.
+    ///
+    pub fn has_object(&self, id: &str) -> bool {
+        let id = id.to_ascii_lowercase();
+        BUILTIN_REFERENCES.contains(&id.as_str()) || self.objects.contains(&id)
+    }
+
+    /// Check whether a global variable with the given id exists, ignoring case.
+    pub fn has_global(&self, id: &str) -> bool {
+        self.globals.contains(&id.to_ascii_lowercase())
+    }
+
+    /// The type of the dialogue with the given id, ignoring case.
+    pub fn dialogue_type(&self, id: &str) -> Option<DialogueType2> {
+        self.dialogues.get(&id.to_ascii_lowercase()).copied()
+    }
+
+    /// Find a variable of the script attached to the object `id`, returning its type and its
+    /// 1-based index among variables of that type.
+    pub fn object_variable(&self, id: &str, name: &str) -> Option<(VariableKind, u16)> {
+        let script = self.object_scripts.get(&id.to_ascii_lowercase())?;
+        self.script_variables.get(script)?.get(name)
+    }
+
+    /// Find the name of a variable of the script attached to the object `id`, given its type
+    /// and its 1-based index among variables of that type.
+    pub fn object_variable_name(&self, id: &str, kind: VariableKind, index: u16) -> Option<&str> {
+        let script = self.object_scripts.get(&id.to_ascii_lowercase())?;
+        self.script_variables.get(script)?.name(kind, index)
+    }
+
+    /// Compile script source.
+    pub fn compile(&self, source: &str) -> Compilation {
+        let (tokens, diagnostics) = tokenize(source);
+
+        let mut emitter = Emitter {
+            compiler: self,
+            name: String::new(),
+            locals: default(),
+            bytecode: vec![],
+            statements: 0,
+            blocks: vec![],
+            diagnostics,
+            state: State::BeforeBegin,
+        };
+
+        for line in tokens.split(|token| token.kind == TokenKind::Newline) {
+            if !line.is_empty() {
+                emitter.statement(line);
+            }
+        }
+
+        let end = tokens.last().map(|token| token.span).unwrap_or_default();
+        match emitter.state {
+            State::BeforeBegin => emitter.error(end, "Expected Begin"),
+            State::Body => emitter.error(end, "Expected End"),
+            State::AfterEnd => {}
+        }
+
+        let script = Script {
+            variables: emitter.locals,
+            bytecode: emitter.bytecode,
+            ..default()
+        };
+
+        Compilation {
+            name: emitter.name,
+            header: script.calculate_header(),
+            variables: script.variables,
+            bytecode: script.bytecode,
+            diagnostics: emitter.diagnostics,
+        }
+    }
+}
+
+impl Script {
+    /// Compile the script text, updating the header, variables and bytecode.
+    ///
+    /// The script is left unchanged when there are errors. All diagnostics are returned.
+    ///
+    pub fn compile(&mut self, compiler: &ScriptCompiler) -> Vec<Diagnostic> {
+        let compilation = compiler.compile(&self.text);
+        if !compilation.has_errors() {
+            self.variables = compilation.variables;
+            self.bytecode = compilation.bytecode;
+            self.recalculate_header();
+        }
+        compilation.diagnostics
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    BeforeBegin,
+    Body,
+    AfterEnd,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BlockKind {
+    If,
+    Else,
+    While,
+}
+
+/// A jump which has not been given its target yet.
+#[derive(Clone, Copy, Debug)]
+struct Jump {
+    statement: usize,
+    offset: usize,
+}
+
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    span: Span,
+    jump: Jump,
+}
+
+#[derive(Debug)]
+struct Emitter<'a> {
+    compiler: &'a ScriptCompiler,
+    name: String,
+    locals: ScriptVariables,
+    bytecode: Vec<u8>,
+    statements: usize,
+    blocks: Vec<Block>,
+    diagnostics: Vec<Diagnostic>,
+    state: State,
+}
+
+impl Emitter<'_> {
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::error(span, message));
+    }
+
+    fn statement(&mut self, line: &[Token<'_>]) {
+        let first = line[0];
+        let span = first.span.to(line[line.len() - 1].span);
+
+        if self.state == State::AfterEnd {
+            self.error(span, "Unexpected statement after End");
+            return;
+        }
+
+        if first.is("begin") {
+            if self.state == State::Body {
+                self.error(span, "Unexpected Begin");
+            } else if let Some(name) = line.get(1).filter(|token| token.is_id()) {
+                self.name = name.text.into();
+                self.state = State::Body;
+                self.expect_end_of_line(&line[2..]);
+            } else {
+                self.error(span, "Expected script name");
+            }
+            return;
+        }
+
+        if self.state == State::BeforeBegin {
+            self.error(span, "Expected Begin");
+            return;
+        }
+
+        if let Some(kind) = VariableKind::from_keyword(first.text).filter(|_| first.kind == TokenKind::Identifier) {
+            self.declare(kind, line);
+            return;
+        }
+
+        let keyword = match first.kind {
+            TokenKind::Identifier => first.text.to_ascii_lowercase(),
+            _ => String::new(),
+        };
+        match keyword.as_str() {
+            "end" => {
+                for block in std::mem::take(&mut self.blocks) {
+                    self.error(block.span, "Block is never closed");
+                }
+                self.opcode(opcodes::END);
+                self.state = State::AfterEnd;
+            }
+            "set" => self.set(line, span),
+            "if" | "elseif" | "while" => self.condition(line, span),
+            "else" => {
+                match self.blocks.pop() {
+                    Some(block) if block.kind == BlockKind::If => {
+                        let statement = self.opcode(opcodes::ELSE);
+                        let jump = self.jump(statement);
+                        self.patch(&block, statement + 1);
+                        self.blocks.push(Block {
+                            kind: BlockKind::Else,
+                            span,
+                            jump,
+                        });
+                    }
+                    block => {
+                        self.blocks.extend(block);
+                        self.error(span, "Else without If");
+                    }
+                }
+                self.expect_end_of_line(&line[1..]);
+            }
+            "endif" => {
+                match self.blocks.pop() {
+                    Some(block) if matches!(block.kind, BlockKind::If | BlockKind::Else) => {
+                        let statement = self.opcode(opcodes::ENDIF);
+                        self.patch(&block, statement);
+                    }
+                    block => {
+                        self.blocks.extend(block);
+                        self.error(span, "EndIf without If");
+                    }
+                }
+                self.expect_end_of_line(&line[1..]);
+            }
+            "endwhile" => {
+                match self.blocks.pop() {
+                    Some(block) if block.kind == BlockKind::While => {
+                        let statement = self.opcode(opcodes::ENDWHILE);
+                        self.patch(&block, statement + 1);
+                    }
+                    block => {
+                        self.blocks.extend(block);
+                        self.error(span, "EndWhile without While");
+                    }
+                }
+                self.expect_end_of_line(&line[1..]);
+            }
+            "return" => {
+                self.opcode(opcodes::RETURN);
+                self.expect_end_of_line(&line[1..]);
+            }
+            _ => self.function_statement(line, span),
+        }
+    }
+
+    fn declare(&mut self, kind: VariableKind, line: &[Token<'_>]) {
+        let Some(name) = line.get(1).filter(|token| token.kind == TokenKind::Identifier) else {
+            self.error(line[0].span, "Expected variable name");
+            return;
+        };
+        if let Err(error) = self.locals.add(kind, name.text) {
+            self.error(name.span, error.to_string());
+            return;
+        }
+        self.expect_end_of_line(&line[2..]);
+    }
+
+    fn set(&mut self, line: &[Token<'_>], span: Span) {
+        let Some(to) = line.iter().position(|token| token.is("to")) else {
+            self.error(span, "Expected: Set <variable> To <expression>");
+            return;
+        };
+
+        let Some(target) = self.variable(&line[1..to], span) else {
+            return;
+        };
+        let Some(expression) = self.expression(&line[to + 1..], span) else {
+            return;
+        };
+
+        self.opcode(opcodes::SET);
+        self.bytecode.extend(target);
+        self.bytecode.extend(expression);
+    }
+
+    fn condition(&mut self, line: &[Token<'_>], span: Span) {
+        let keyword = line[0];
+
+        let Some(expression) = self.expression(&line[1..], span) else {
+            // Keep the block structure intact, so that errors are not reported for its end.
+            if keyword.is("if") {
+                let statement = self.opcode(opcodes::IF);
+                let jump = self.jump(statement);
+                self.blocks.push(Block {
+                    kind: BlockKind::If,
+                    span,
+                    jump,
+                });
+            } else if keyword.is("while") {
+                let statement = self.opcode(opcodes::WHILE);
+                let jump = self.jump(statement);
+                self.blocks.push(Block {
+                    kind: BlockKind::While,
+                    span,
+                    jump,
+                });
+            }
+            return;
+        };
+
+        if keyword.is("elseif") {
+            match self.blocks.pop() {
+                Some(block) if block.kind == BlockKind::If => {
+                    let statement = self.opcode(opcodes::ELSEIF);
+                    self.patch(&block, statement);
+                    let jump = self.jump(statement);
+                    self.bytecode.extend(expression);
+                    self.blocks.push(Block {
+                        kind: BlockKind::If,
+                        span,
+                        jump,
+                    });
+                }
+                block => {
+                    self.blocks.extend(block);
+                    self.error(span, "ElseIf without If");
+                }
+            }
+            return;
+        }
+
+        let (opcode, kind) = if keyword.is("if") {
+            (opcodes::IF, BlockKind::If)
+        } else {
+            (opcodes::WHILE, BlockKind::While)
+        };
+        let statement = self.opcode(opcode);
+        let jump = self.jump(statement);
+        self.bytecode.extend(expression);
+        self.blocks.push(Block { kind, span, jump });
+    }
+
+    fn function_statement(&mut self, line: &[Token<'_>], span: Span) {
+        let (reference, rest) = match line {
+            [reference, arrow, rest @ ..] if arrow.is("->") => (Some(*reference), rest),
+            _ => (None, line),
+        };
+
+        let Some(name) = rest.first().filter(|token| token.kind == TokenKind::Identifier) else {
+            self.error(span, "Expected a function");
+            return;
+        };
+
+        let Some(function) = self.compiler.functions.get(name.text) else {
+            let message = if self.local(name.text).is_some() || self.compiler.has_global(name.text) {
+                format!("Expected a function, found variable: {}", name.text)
+            } else {
+                format!("Unknown function: {}", name.text)
+            };
+            self.error(name.span, message);
+            return;
+        };
+        let Some(opcode) = function.opcode else {
+            self.error(name.span, format!("The opcode of {} is not known", function.name));
+            return;
+        };
+
+        let mut pos = 1;
+        let Some(arguments) = self.arguments(function, rest, &mut pos) else {
+            return;
+        };
+        if !self.expect_end_of_line(&rest[pos..]) {
+            return;
+        }
+
+        let mut bytecode = vec![];
+        if let Some(reference) = reference {
+            let Some(id) = self.reference(reference) else {
+                return;
+            };
+            bytecode.extend(opcodes::REFERENCE.to_le_bytes());
+            bytecode.extend(id);
+        }
+        bytecode.extend(opcode.to_le_bytes());
+        bytecode.extend(arguments);
+
+        self.statements += 1;
+        self.bytecode.extend(bytecode);
+    }
+
+    /// Emit the opcode of a new statement, returning the index of the statement.
+    fn opcode(&mut self, opcode: u16) -> usize {
+        self.bytecode.extend(opcode.to_le_bytes());
+        self.statements += 1;
+        self.statements - 1
+    }
+
+    /// Emit a placeholder for a jump from the given statement.
+    fn jump(&mut self, statement: usize) -> Jump {
+        self.bytecode.push(0);
+        Jump {
+            statement,
+            offset: self.bytecode.len() - 1,
+        }
+    }
+
+    /// Resolve a jump to land on the given statement.
+    fn patch(&mut self, block: &Block, target: usize) {
+        if let Ok(value) = u8::try_from(target - block.jump.statement - 1) {
+            self.bytecode[block.jump.offset] = value;
+        } else {
+            self.error(block.span, "Block is too long, it must contain at most 255 statements");
+        }
+    }
+
+    /// Report any unexpected tokens at the end of a statement, returning whether there were none.
+    fn expect_end_of_line(&mut self, rest: &[Token<'_>]) -> bool {
+        match rest {
+            [] => true,
+            [first, ..] => {
+                let span = first.span.to(rest[rest.len() - 1].span);
+                self.error(span, format!("Unexpected: {}", first.text));
+                false
+            }
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<(VariableKind, u16)> {
+        self.locals.get(name)
+    }
+
+    /// Encode the target of a `Set` statement.
+    fn variable(&mut self, tokens: &[Token<'_>], span: Span) -> Option<Vec<u8>> {
+        match tokens {
+            [name] if name.kind == TokenKind::Identifier => {
+                if let Some((kind, index)) = self.local(name.text) {
+                    let mut bytes = vec![kind.prefix()];
+                    bytes.extend(index.to_le_bytes());
+                    Some(bytes)
+                } else if self.compiler.has_global(name.text) {
+                    let mut bytes = vec![opcodes::GLOBAL];
+                    bytes.extend(self.id(name)?);
+                    Some(bytes)
+                } else {
+                    self.error(name.span, format!("Unknown variable: {}", name.text));
+                    None
+                }
+            }
+            [reference, dot, name] if dot.is(".") && name.kind == TokenKind::Identifier => {
+                self.object_variable(*reference, *name)
+            }
+            _ => {
+                self.error(span, "Expected a variable");
+                None
+            }
+        }
+    }
+
+    /// Encode a variable of the script attached to another object, as in `reference.name`.
+    fn object_variable(&mut self, reference: Token<'_>, name: Token<'_>) -> Option<Vec<u8>> {
+        let id = self.reference(reference)?;
+        let Some((kind, index)) = self.compiler.object_variable(reference.text, name.text) else {
+            self.error(
+                reference.span.to(name.span),
+                format!("Unknown variable of {}: {}", reference.text, name.text),
+            );
+            return None;
+        };
+        let mut bytes = vec![opcodes::EXPLICIT_REFERENCE];
+        bytes.extend(id);
+        bytes.push(kind.prefix());
+        bytes.extend(index.to_le_bytes());
+        Some(bytes)
+    }
+
+    /// Encode the id of an explicit reference, checking that the object exists.
+    fn reference(&mut self, token: Token<'_>) -> Option<Vec<u8>> {
+        if !token.is_id() {
+            self.error(token.span, "Expected an object id");
+            return None;
+        }
+        if !self.compiler.has_object(token.text) {
+            self.error(token.span, format!("Unknown object: {}", token.text));
+            return None;
+        }
+        self.id(&token)
+    }
+
+    /// Encode an id or string, prefixed by its length.
+    fn id(&mut self, token: &Token<'_>) -> Option<Vec<u8>> {
+        let Ok(len) = u8::try_from(token.text.len()) else {
+            self.error(token.span, "Text is too long, it must be at most 255 characters");
+            return None;
+        };
+        let mut bytes = vec![len];
+        bytes.extend_from_slice(token.text.as_bytes());
+        Some(bytes)
+    }
+
+    /// Encode the arguments of a function, starting at `tokens[*pos]`.
+    fn arguments(&mut self, function: &ScriptFunction, tokens: &[Token<'_>], pos: &mut usize) -> Option<Vec<u8>> {
+        if function.is_variadic() {
+            // the encoding of variable arguments, as used by `MessageBox`, has not been verified
+            let span = tokens[*pos - 1].span;
+            self.error(span, format!("Variable arguments can not be compiled: {}", function.name));
+            return None;
+        }
+
+        let mut bytes = vec![];
+
+        for (i, &kind) in function.arguments.iter().enumerate() {
+            let negative = tokens.get(*pos).is_some_and(|token| token.is("-"))
+                && tokens.get(*pos + 1).is_some_and(|token| token.kind == TokenKind::Number);
+            let token = match tokens.get(*pos + usize::from(negative)) {
+                Some(token) if token.is_id() => *token,
+                _ if i >= function.required_arguments => {
+                    match kind {
+                        ArgumentKind::Id | ArgumentKind::String | ArgumentKind::Axis => bytes.push(0),
+                        ArgumentKind::Format | ArgumentKind::Buttons | ArgumentKind::Choices => {}
+                        ArgumentKind::Short => bytes.extend(0i16.to_le_bytes()),
+                        ArgumentKind::Long => bytes.extend(0i32.to_le_bytes()),
+                        ArgumentKind::Float => bytes.extend(0f32.to_le_bytes()),
+                    }
+                    continue;
+                }
+                _ => {
+                    let span = tokens.get(*pos).unwrap_or(&tokens[0]).span;
+                    self.error(span, format!("Missing argument {} of {}", i + 1, function.name));
+                    return None;
+                }
+            };
+            *pos += 1 + usize::from(negative);
+
+            let number = |text: &str| if negative { format!("-{text}") } else { text.into() };
+
+            let encoded = match kind {
+                ArgumentKind::Id | ArgumentKind::String => self.id(&token),
+                ArgumentKind::Short => number(token.text).parse::<i16>().ok().map(|v| v.to_le_bytes().to_vec()),
+                ArgumentKind::Long => number(token.text).parse::<i32>().ok().map(|v| v.to_le_bytes().to_vec()),
+                ArgumentKind::Float => number(token.text).parse::<f32>().ok().map(|v| v.to_le_bytes().to_vec()),
+                ArgumentKind::Axis => ["X", "Y", "Z"]
+                    .into_iter()
+                    .find(|axis| token.is(axis))
+                    .map(|axis| axis.as_bytes().to_vec()),
+                ArgumentKind::Format | ArgumentKind::Buttons | ArgumentKind::Choices => None,
+            };
+
+            let Some(encoded) = encoded else {
+                if !matches!(kind, ArgumentKind::Id | ArgumentKind::String) {
+                    self.error(
+                        token.span,
+                        format!("Invalid argument {} of {}: {}", i + 1, function.name, token.text),
+                    );
+                }
+                return None;
+            };
+            bytes.extend(encoded);
+        }
+
+        Some(bytes)
+    }
+
+    /// Encode an expression, prefixed by its length.
+    fn expression(&mut self, tokens: &[Token<'_>], span: Span) -> Option<Vec<u8>> {
+        if tokens.is_empty() {
+            self.error(span, "Expected an expression");
+            return None;
+        }
+
+        let mut output = vec![];
+        let mut pos = 0;
+        self.comparison(tokens, &mut pos, &mut output)?;
+        if !self.expect_end_of_line(&tokens[pos..]) {
+            return None;
+        }
+
+        let expression = output.join(&b' ');
+        let Ok(len) = u8::try_from(expression.len()) else {
+            self.error(span, "Expression is too long");
+            return None;
+        };
+
+        let mut bytes = vec![len];
+        bytes.extend(expression);
+        Some(bytes)
+    }
+
+    fn comparison(&mut self, tokens: &[Token<'_>], pos: &mut usize, output: &mut Vec<Vec<u8>>) -> Option<()> {
+        self.sum(tokens, pos, output)?;
+        if let Some(operator) = tokens.get(*pos).filter(|token| token.kind == TokenKind::Operator) {
+            if COMPARISONS.contains(&operator.text) {
+                output.push(operator.text.into());
+                *pos += 1;
+                self.sum(tokens, pos, output)?;
+            } else if operator.is("=") {
+                self.error(operator.span, "Expected a comparison, did you mean '=='?");
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn sum(&mut self, tokens: &[Token<'_>], pos: &mut usize, output: &mut Vec<Vec<u8>>) -> Option<()> {
+        self.product(tokens, pos, output)?;
+        while let Some(operator) = tokens.get(*pos).filter(|token| token.is("+") || token.is("-")) {
+            output.push(operator.text.into());
+            *pos += 1;
+            self.product(tokens, pos, output)?;
+        }
+        Some(())
+    }
+
+    fn product(&mut self, tokens: &[Token<'_>], pos: &mut usize, output: &mut Vec<Vec<u8>>) -> Option<()> {
+        self.unary(tokens, pos, output)?;
+        while let Some(operator) = tokens.get(*pos).filter(|token| token.is("*") || token.is("/")) {
+            output.push(operator.text.into());
+            *pos += 1;
+            self.unary(tokens, pos, output)?;
+        }
+        Some(())
+    }
+
+    fn unary(&mut self, tokens: &[Token<'_>], pos: &mut usize, output: &mut Vec<Vec<u8>>) -> Option<()> {
+        if tokens.get(*pos).is_some_and(|token| token.is("-")) {
+            output.push(b"-".into());
+            *pos += 1;
+            return self.unary(tokens, pos, output);
+        }
+        self.primary(tokens, pos, output)
+    }
+
+    fn primary(&mut self, tokens: &[Token<'_>], pos: &mut usize, output: &mut Vec<Vec<u8>>) -> Option<()> {
+        let Some(&token) = tokens.get(*pos) else {
+            self.error(tokens[tokens.len() - 1].span, "Expected an operand");
+            return None;
+        };
+        *pos += 1;
+
+        match token.kind {
+            TokenKind::Number => {
+                output.push(token.text.into());
+                return Some(());
+            }
+            TokenKind::Operator if token.is("(") => {
+                output.push(b"(".into());
+                self.comparison(tokens, pos, output)?;
+                if !tokens.get(*pos).is_some_and(|token| token.is(")")) {
+                    self.error(token.span, "Unclosed parenthesis");
+                    return None;
+                }
+                *pos += 1;
+                output.push(b")".into());
+                return Some(());
+            }
+            TokenKind::Operator | TokenKind::Newline => {
+                self.error(token.span, format!("Unexpected: {}", token.text));
+                return None;
+            }
+            TokenKind::Identifier | TokenKind::String => {}
+        }
+
+        let next = tokens.get(*pos);
+
+        if next.is_some_and(|next| next.is("->")) {
+            let reference = self.reference(token)?;
+            let Some(&name) = tokens.get(*pos + 1) else {
+                self.error(token.span, "Expected a function");
+                return None;
+            };
+            *pos += 2;
+            let mut bytes = vec![opcodes::EXPLICIT_REFERENCE];
+            bytes.extend(reference);
+            bytes.extend(self.function_call(name, tokens, pos)?);
+            output.push(bytes);
+            return Some(());
+        }
+
+        if next.is_some_and(|next| next.is(".")) {
+            let Some(&name) = tokens.get(*pos + 1) else {
+                self.error(token.span, "Expected a variable");
+                return None;
+            };
+            *pos += 2;
+            output.push(self.object_variable(token, name)?);
+            return Some(());
+        }
+
+        if token.kind == TokenKind::Identifier {
+            if let Some((kind, index)) = self.local(token.text) {
+                let mut bytes = vec![kind.prefix()];
+                bytes.extend(index.to_le_bytes());
+                output.push(bytes);
+                return Some(());
+            }
+            if self.compiler.has_global(token.text) {
+                let mut bytes = vec![opcodes::GLOBAL];
+                bytes.extend(self.id(&token)?);
+                output.push(bytes);
+                return Some(());
+            }
+        }
+
+        let bytes = self.function_call(token, tokens, pos)?;
+        output.push(bytes);
+        Some(())
+    }
+
+    /// Encode a function call within an expression.
+    fn function_call(&mut self, name: Token<'_>, tokens: &[Token<'_>], pos: &mut usize) -> Option<Vec<u8>> {
+        let Some(function) = self.compiler.functions.get(name.text) else {
+            self.error(name.span, format!("Unknown function or variable: {}", name.text));
+            return None;
+        };
+        if !function.returns_value {
+            self.error(name.span, format!("Function does not return a value: {}", function.name));
+            return None;
+        }
+        let Some(opcode) = function.opcode else {
+            self.error(name.span, format!("The opcode of {} is not known", function.name));
+            return None;
+        };
+        let mut bytes = vec![opcodes::FUNCTION];
+        bytes.extend(opcode.to_le_bytes());
+        bytes.extend(self.arguments(function, tokens, pos)?);
+        Some(bytes)
+    }
+}
+
+/// The script attached to an object, for object types that can have one.
+const fn object_script(object: &TES3Object) -> Option<&String> {
+    match object {
+        TES3Object::Activator(object) => Some(&object.script),
+        TES3Object::Alchemy(object) => Some(&object.script),
+        TES3Object::Apparatus(object) => Some(&object.script),
+        TES3Object::Armor(object) => Some(&object.script),
+        TES3Object::Book(object) => Some(&object.script),
+        TES3Object::Clothing(object) => Some(&object.script),
+        TES3Object::Container(object) => Some(&object.script),
+        TES3Object::Creature(object) => Some(&object.script),
+        TES3Object::Door(object) => Some(&object.script),
+        TES3Object::Ingredient(object) => Some(&object.script),
+        TES3Object::Light(object) => Some(&object.script),
+        TES3Object::Lockpick(object) => Some(&object.script),
+        TES3Object::MiscItem(object) => Some(&object.script),
+        TES3Object::Npc(object) => Some(&object.script),
+        TES3Object::Probe(object) => Some(&object.script),
+        TES3Object::RepairItem(object) => Some(&object.script),
+        TES3Object::Weapon(object) => Some(&object.script),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_verified_script() -> io::Result<()> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/all_types.esp");
+        let plugin = Plugin::from_path(path)?;
+        let script = plugin.objects_of_type::<Script>().next().unwrap();
+
+        let mut compiled = script.clone();
+        compiled.header = default();
+        compiled.variables = default();
+        compiled.bytecode.clear();
+
+        let diagnostics = compiled.compile(&ScriptCompiler::from_plugin(&plugin));
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(&compiled, script);
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_control_flow() {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            GlobalVariable {
+                id: "GameHour".into(),
+                ..default()
+            }
+            .into(),
+        );
+
+        let compiler = ScriptCompiler::from_plugin(&plugin);
+
+        let source = "Begin test\nshort x\nfloat y\nif ( x == 1 )\n  set y to x * -2\nelse\n  return\nendif\nwhile ( GameHour < 8.5 )\nendwhile\nEnd test";
+        let compilation = compiler.compile(source);
+        assert_eq!(compilation.diagnostics, []);
+        assert_eq!(compilation.name, "test");
+        assert_eq!(compilation.variables.to_bytes(), b"x\0y\0");
+        assert_eq!((compilation.header.num_shorts, compilation.header.num_floats), (1, 1));
+
+        let mut expected = vec![];
+        // if ( x == 1 ), skipping the set statement and landing after else
+        expected.extend(b"\x06\x01\x02\x0c( s\x01\x00 == 1 )");
+        // set y to x * -2
+        expected.extend(b"\x05\x01f\x01\x00\x09s\x01\x00 * - 2");
+        // else, skipping return
+        expected.extend(b"\x07\x01\x01");
+        expected.extend(b"\x24\x01\x09\x01");
+        // while ( GameHour < 8.5 ), skipping endwhile
+        expected.extend(b"\x0e\x01\x01\x14( G\x08GameHour < 8.5 )");
+        expected.extend(b"\x0f\x01\x01\x01");
+        assert_eq!(compilation.bytecode, expected);
+
+        let compilation = compiler.compile("Begin test\nPlayer->DontSaveObject\nEnd");
+        assert_eq!(compilation.diagnostics, []);
+        assert_eq!(compilation.bytecode, b"\x0c\x01\x06Player\x5f\x11\x01\x01");
+
+        let compilation = compiler.compile("Begin test\nif ( y = 1 )\nset x to UnknownFunction\nendwhile\nEnd");
+        let messages: Vec<_> = compilation.diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "2:6: error: Unknown function or variable: y",
+                "3:5: error: Unknown variable: x",
+                "4:1: error: EndWhile without While",
+                "2:1: error: Block is never closed",
+            ]
+        );
+
+        // vanilla functions whose opcodes have not been verified can not be compiled
+        let source = "Begin test\nfloat y\nset y to GetPos z\nMessageBox \"Hello\"\nEnd";
+        let compilation = compiler.compile(source);
+        let messages: Vec<_> = compilation.diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "3:10: error: The opcode of GetPos is not known",
+                "4:1: error: The opcode of MessageBox is not known",
+            ]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! Decompilation of `SCDT` bytecode back into script source.
+//!
+//! See the [`compiler`](super::compiler) module for a description of the bytecode format.
+//!
+
+use super::opcodes;
+use crate::prelude::*;
+
+const NEWLINE: &str = "\r\n";
+
+/// A difference between the text of a script and its compiled data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScriptMismatch {
+    /// The script has bytecode, but no text.
+    MissingText,
+    /// The text could not be compiled.
+    CompileErrors(Vec<Diagnostic>),
+    /// The compiled header does not match the stored header.
+    Header,
+    /// The compiled variable table does not match the stored variable table.
+    Variables,
+    /// The compiled bytecode first differs from the stored bytecode at the given offset.
+    Bytecode { offset: usize },
+}
+
+impl ScriptCompiler {
+    /// Decompile the bytecode of a script into source text.
+    ///
+    /// Fails if the bytecode is malformed, or uses functions or variables that are unknown to
+    /// this compiler.
+    ///
+    pub fn decompile(&self, script: &Script) -> io::Result<String> {
+        let mut decoder = Decoder {
+            compiler: self,
+            bytes: &script.bytecode,
+            pos: 0,
+            locals: &script.variables,
+        };
+
+        let mut text = format!("Begin {}{NEWLINE}", quote(&script.id));
+
+        for (name, kind) in decoder.locals.iter() {
+            text.push_str(kind.keyword());
+            text.push(' ');
+            text.push_str(name);
+            text.push_str(NEWLINE);
+        }
+        if !decoder.locals.is_empty() {
+            text.push_str(NEWLINE);
+        }
+
+        let mut depth = 0usize;
+        let mut ended = false;
+
+        while decoder.pos < decoder.bytes.len() {
+            if ended {
+                return Err(decoder.error("Unexpected statement after End"));
+            }
+
+            let opcode = decoder.u16()?;
+
+            let (indent, line) = match opcode {
+                opcodes::END => {
+                    ended = true;
+                    (0, "End".to_string())
+                }
+                opcodes::SET => {
+                    let target = decoder.variable()?;
+                    let expression = decoder.expression()?;
+                    (depth, format!("set {target} to {expression}"))
+                }
+                opcodes::IF | opcodes::WHILE => {
+                    decoder.u8()?; // jump
+                    let expression = decoder.expression()?;
+                    depth += 1;
+                    let keyword = if opcode == opcodes::IF { "if" } else { "while" };
+                    (depth - 1, format!("{keyword} {expression}"))
+                }
+                opcodes::ELSEIF => {
+                    decoder.u8()?; // jump
+                    let expression = decoder.expression()?;
+                    (depth.saturating_sub(1), format!("elseif {expression}"))
+                }
+                opcodes::ELSE => {
+                    decoder.u8()?; // jump
+                    (depth.saturating_sub(1), "else".to_string())
+                }
+                opcodes::ENDIF | opcodes::ENDWHILE => {
+                    depth = depth.saturating_sub(1);
+                    let keyword = if opcode == opcodes::ENDIF { "endif" } else { "endwhile" };
+                    (depth, keyword.to_string())
+                }
+                opcodes::RETURN => (depth, "return".to_string()),
+                opcodes::REFERENCE => {
+                    let reference = decoder.id()?;
+                    let opcode = decoder.u16()?;
+                    let call = decoder.function_call(opcode)?;
+                    (depth, format!("{}->{call}", quote(&reference)))
+                }
+                opcode => (depth, decoder.function_call(opcode)?),
+            };
+
+            for _ in 0..indent {
+                text.push('\t');
+            }
+            text.push_str(&line);
+            text.push_str(NEWLINE);
+        }
+
+        if !ended {
+            text.push_str("End");
+            text.push_str(NEWLINE);
+        }
+
+        Ok(text)
+    }
+
+    /// Compile the text of a script and compare the result against its stored compiled data.
+    ///
+    /// Returns an empty list when the text and compiled data agree.
+    ///
+    pub fn check(&self, script: &Script) -> Vec<ScriptMismatch> {
+        if script.text.trim().is_empty() {
+            if script.bytecode.is_empty() {
+                return vec![];
+            }
+            return vec![ScriptMismatch::MissingText];
+        }
+
+        let compilation = self.compile(&script.text);
+        if compilation.has_errors() {
+            return vec![ScriptMismatch::CompileErrors(compilation.diagnostics)];
+        }
+
+        let mut mismatches = vec![];
+
+        if compilation.header != script.header {
+            mismatches.push(ScriptMismatch::Header);
+        }
+        if compilation.variables != script.variables {
+            mismatches.push(ScriptMismatch::Variables);
+        }
+        if compilation.bytecode != script.bytecode {
+            let offset = compilation
+                .bytecode
+                .iter()
+                .zip(&script.bytecode)
+                .position(|(a, b)| a != b)
+                .unwrap_or_else(|| compilation.bytecode.len().min(script.bytecode.len()));
+            mismatches.push(ScriptMismatch::Bytecode { offset });
+        }
+
+        mismatches
+    }
+}
+
+#[derive(Debug)]
+struct Decoder<'a> {
+    compiler: &'a ScriptCompiler,
+    bytes: &'a [u8],
+    pos: usize,
+    locals: &'a ScriptVariables,
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{message} (at bytecode offset {})", self.pos),
+        )
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| self.error("Unexpected end of bytecode"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// Decode an id or string prefixed by its length.
+    fn id(&mut self) -> io::Result<String> {
+        let len = self.u8()?;
+        Ok(self.take(len.into())?.to_str_lossy().into_owned())
+    }
+
+    /// Decode a local variable, starting after its type prefix.
+    fn local(&mut self, kind: VariableKind) -> io::Result<String> {
+        let index = self.u16()?;
+        self.locals
+            .name(kind, index)
+            .map(String::from)
+            .ok_or_else(|| self.error(&format!("Unknown {} variable index: {index}", kind.keyword())))
+    }
+
+    /// Decode the target of a `Set` statement.
+    fn variable(&mut self) -> io::Result<String> {
+        let prefix = self.u8()?;
+        if let Some(kind) = VariableKind::from_prefix(prefix) {
+            return self.local(kind);
+        }
+        match prefix {
+            opcodes::GLOBAL => self.id(),
+            opcodes::EXPLICIT_REFERENCE => {
+                let reference = self.id()?;
+                self.object_variable(&reference)
+            }
+            _ => Err(self.error(&format!("Unexpected variable prefix: {prefix:#04x}"))),
+        }
+    }
+
+    /// Decode a variable of the script attached to another object, starting after its id.
+    fn object_variable(&mut self, reference: &str) -> io::Result<String> {
+        let prefix = self.u8()?;
+        let kind = VariableKind::from_prefix(prefix)
+            .ok_or_else(|| self.error(&format!("Unexpected variable prefix: {prefix:#04x}")))?;
+        let index = self.u16()?;
+        let name = self
+            .compiler
+            .object_variable_name(reference, kind, index)
+            .ok_or_else(|| self.error(&format!("Unknown variable of {reference}: {} {index}", kind.keyword())))?;
+        Ok(format!("{}.{name}", quote(reference)))
+    }
+
+    /// Decode an expression prefixed by its length.
+    fn expression(&mut self) -> io::Result<String> {
+        let len = usize::from(self.u8()?);
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err(self.error("Unexpected end of bytecode"));
+        }
+
+        let mut tokens = vec![];
+        while self.pos < end {
+            let prefix = self.bytes[self.pos];
+            let token = if let Some(kind) = VariableKind::from_prefix(prefix) {
+                self.pos += 1;
+                self.local(kind)?
+            } else {
+                match prefix {
+                    opcodes::GLOBAL => {
+                        self.pos += 1;
+                        self.id()?
+                    }
+                    opcodes::FUNCTION => {
+                        self.pos += 1;
+                        let opcode = self.u16()?;
+                        self.function_call(opcode)?
+                    }
+                    opcodes::EXPLICIT_REFERENCE => {
+                        self.pos += 1;
+                        let reference = self.id()?;
+                        if self.bytes.get(self.pos) == Some(&opcodes::FUNCTION) {
+                            self.pos += 1;
+                            let opcode = self.u16()?;
+                            format!("{}->{}", quote(&reference), self.function_call(opcode)?)
+                        } else {
+                            self.object_variable(&reference)?
+                        }
+                    }
+                    _ => {
+                        let len = self.bytes[self.pos..end]
+                            .iter()
+                            .position(|&b| b == b' ')
+                            .unwrap_or(end - self.pos);
+                        self.take(len)?.to_str_lossy().into_owned()
+                    }
+                }
+            };
+            tokens.push(token);
+
+            if self.pos < end {
+                if self.u8()? != b' ' {
+                    return Err(self.error("Expected a space between expression tokens"));
+                }
+            } else if self.pos > end {
+                return Err(self.error("Expression token exceeds the expression length"));
+            }
+        }
+
+        Ok(tokens.join(" "))
+    }
+
+    /// Decode a function call, starting after its opcode.
+    fn function_call(&mut self, opcode: u16) -> io::Result<String> {
+        let function = self
+            .compiler
+            .functions
+            .get_by_opcode(opcode)
+            .ok_or_else(|| self.error(&format!("Unknown opcode: {opcode:#06x}")))?;
+
+        let mut call = function.name.clone();
+        for kind in &function.arguments {
+            let argument = match kind {
+                ArgumentKind::Id | ArgumentKind::String => quote(&self.id()?),
+                ArgumentKind::Short => i16::from_le_bytes(self.array()?).to_string(),
+                ArgumentKind::Long => i32::from_le_bytes(self.array()?).to_string(),
+                ArgumentKind::Float => f32::from_le_bytes(self.array()?).to_string(),
+                ArgumentKind::Axis => char::from(self.u8()?).to_string(),
+                ArgumentKind::Format | ArgumentKind::Buttons | ArgumentKind::Choices => {
+                    return Err(self.error(&format!("Variable arguments can not be decompiled: {}", function.name)));
+                }
+            };
+            call.push(' ');
+            call.push_str(&argument);
+        }
+
+        Ok(call)
+    }
+}
+
+/// Quote an id if it can not be written as a single identifier.
+fn quote(id: &str) -> String {
+    let is_identifier = !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    if is_identifier {
+        id.into()
+    } else {
+        format!("\"{id}\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompile_verified_script() -> io::Result<()> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/all_types.esp");
+        let plugin = Plugin::from_path(path)?;
+        let script = plugin.objects_of_type::<Script>().next().unwrap();
+        let compiler = ScriptCompiler::from_plugin(&plugin);
+
+        let mut decompiled = script.clone();
+        decompiled.text = compiler.decompile(script)?;
+        assert!(decompiled.text.contains("DontSaveObject"));
+        assert_eq!(compiler.check(&decompiled), []);
+
+        decompiled.bytecode.clear();
+        assert_eq!(decompiled.compile(&compiler), []);
+        assert_eq!(decompiled.bytecode, script.bytecode);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decompile_and_check() {
+        let compiler = ScriptCompiler::default();
+
+        let source = "Begin test\r\nshort x\r\nfloat y\r\n\r\nif ( x == 1 )\r\n\tset y to x * - 2\r\nelseif ( x > y )\r\n\twhile ( y < 8.5 )\r\n\t\tset y to ( y + 1 )\r\n\tendwhile\r\nelse\r\n\treturn\r\nendif\r\nDontSaveObject\r\nEnd\r\n";
+
+        let mut script = Script {
+            id: "test".into(),
+            text: source.into(),
+            ..default()
+        };
+        assert_eq!(
+            compiler.check(&script),
+            [
+                ScriptMismatch::Header,
+                ScriptMismatch::Variables,
+                ScriptMismatch::Bytecode { offset: 0 }
+            ]
+        );
+
+        assert_eq!(script.compile(&compiler), []);
+        assert_eq!(compiler.check(&script), []);
+        assert_eq!(compiler.decompile(&script).unwrap(), source);
+
+        script.text = source.replace("- 2", "- 3");
+        assert_eq!(compiler.check(&script), [ScriptMismatch::Bytecode { offset: 30 }]);
+
+        script.text.clear();
+        assert_eq!(compiler.check(&script), [ScriptMismatch::MissingText]);
+
+        script.bytecode[0] = 0xFF;
+        assert!(compiler.decompile(&script).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use crate::prelude::*;
+
+/// The type of a script function argument, and how it is encoded in bytecode.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ArgumentKind {
+    /// An id or string, encoded as a `u8` length followed by the text.
+    Id,
+    /// Free text, such as a message, encoded like [`ArgumentKind::Id`] but not naming an object.
+    String,
+    /// An `i16` number.
+    Short,
+    /// An `i32` number.
+    Long,
+    /// An `f32` number.
+    Float,
+    /// One of the axes `X`, `Y` or `Z`, encoded as an uppercase ASCII byte.
+    Axis,
+    /// A message whose format specifiers, such as `%g` or `%.2f`, are each followed by a
+    /// variable argument, see [`format_arguments`].
+    Format,
+    /// Any number of button labels, ending the arguments.
+    Buttons,
+    /// Any number of pairs of a choice text and the number identifying it, ending the arguments.
+    Choices,
+}
+
+impl ArgumentKind {
+    /// Whether the argument is followed by a varying number of values.
+    pub const fn is_variadic(self) -> bool {
+        matches!(self, Self::Format | Self::Buttons | Self::Choices)
+    }
+}
+
+/// The definition of a script function, such as `AddItem` or `GetJournalIndex`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScriptFunction {
+    pub name: String,
+    /// The opcode of the function in bytecode, or `None` if it has not been verified against
+    /// compiled scripts. Functions without an opcode can be parsed and linted, but not compiled
+    /// or decompiled.
+    pub opcode: Option<u16>,
+    pub arguments: Vec<ArgumentKind>,
+    /// The number of leading arguments that must be provided. Omitted optional arguments are
+    /// encoded as zero, or as an empty id.
+    pub required_arguments: usize,
+    /// Whether the function can be used within expressions.
+    pub returns_value: bool,
+    /// Whether the function acts on a reference, so that it can be called as
+    /// `reference->Function`. Without an explicit reference, it acts on the object running the
+    /// script.
+    pub takes_reference: bool,
+}
+
+impl ScriptFunction {
+    /// A function with a known opcode, whose arguments are all required.
+    pub fn new(name: &str, opcode: u16, arguments: &[ArgumentKind], returns_value: bool) -> Self {
+        Self {
+            name: name.into(),
+            opcode: Some(opcode),
+            arguments: arguments.into(),
+            required_arguments: arguments.len(),
+            returns_value,
+            takes_reference: true,
+        }
+    }
+
+    /// Whether the number of arguments is not fixed, as for `MessageBox`.
+    pub fn is_variadic(&self) -> bool {
+        self.arguments.iter().any(|kind| kind.is_variadic())
+    }
+
+    /// The largest number of arguments the function takes, or `None` if it is variadic.
+    pub fn max_arguments(&self) -> Option<usize> {
+        (!self.is_variadic()).then_some(self.arguments.len())
+    }
+}
+
+/// The number of variables expected by a format string of `MessageBox` or `Say`.
+///
+/// Each specifier such as `%g`, `%s` or `%.2f` takes a variable, while `%%` is a literal `%`.
+///
+pub fn format_arguments(format: &str) -> usize {
+    let mut count = 0;
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some('%') | None => {}
+            Some(_) => count += 1,
+        }
+    }
+    count
+}
+
+/// A table of script functions, looked up by name ignoring case, or by opcode.
+///
+/// The default table contains the functions of the vanilla game with their arguments, but only
+/// the opcodes which have been verified against compiled scripts. Functions without an opcode
+/// can be parsed and linted, and must be registered again with [`ScriptFunctions::insert`] and
+/// their opcode before they can be compiled or decompiled.
+///
+#[derive(Clone, Debug)]
+pub struct ScriptFunctions {
+    functions: HashMap<String, ScriptFunction>,
+    opcodes: HashMap<u16, String>,
+}
+
+impl Default for ScriptFunctions {
+    fn default() -> Self {
+        let mut this = Self::empty();
+
+        for &(name, arguments, required_arguments, flags) in VANILLA_FUNCTIONS {
+            this.insert(vanilla_function(name, arguments, required_arguments, flags));
+        }
+
+        for (prefix, stats, argument) in STAT_FAMILIES {
+            for stat in *stats {
+                this.insert(vanilla_function(&format!("Get{prefix}{stat}"), &[], 0, VALUE | REFERENCE));
+                for verb in ["Set", "Mod"] {
+                    this.insert(vanilla_function(&format!("{verb}{prefix}{stat}"), &[*argument], 1, REFERENCE));
+                }
+            }
+        }
+        for stat in ["Health", "Magicka", "Fatigue"] {
+            this.insert(vanilla_function(&format!("ModCurrent{stat}"), &[Float], 1, REFERENCE));
+            this.insert(vanilla_function(&format!("Get{stat}GetRatio"), &[], 0, VALUE | REFERENCE));
+        }
+
+        for &(name, opcode) in VERIFIED_OPCODES {
+            if let Some(mut function) = this.get(name).cloned() {
+                function.opcode = Some(opcode);
+                this.insert(function);
+            }
+        }
+
+        this
+    }
+}
+
+impl ScriptFunctions {
+    pub fn empty() -> Self {
+        Self {
+            functions: HashMap::new(),
+            opcodes: HashMap::new(),
+        }
+    }
+
+    /// Register a function, replacing any function with the same name or opcode.
+    pub fn insert(&mut self, function: ScriptFunction) {
+        let key = function.name.to_ascii_lowercase();
+        if let Some(opcode) = self.functions.remove(&key).and_then(|previous| previous.opcode) {
+            self.opcodes.remove(&opcode);
+        }
+        if let Some(opcode) = function.opcode {
+            if let Some(previous) = self.opcodes.insert(opcode, key.clone()) {
+                self.functions.remove(&previous);
+            }
+        }
+        self.functions.insert(key, function);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ScriptFunction> {
+        self.functions.get(&name.to_ascii_lowercase())
+    }
+
+    pub fn get_by_opcode(&self, opcode: u16) -> Option<&ScriptFunction> {
+        self.functions.get(self.opcodes.get(&opcode)?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ScriptFunction> {
+        self.functions.values()
+    }
+}
+
+fn vanilla_function(name: &str, arguments: &[ArgumentKind], required_arguments: usize, flags: u8) -> ScriptFunction {
+    ScriptFunction {
+        name: name.into(),
+        opcode: None,
+        arguments: arguments.into(),
+        required_arguments,
+        returns_value: flags & VALUE != 0,
+        takes_reference: flags & REFERENCE != 0,
+    }
+}
+
+use ArgumentKind::{Axis, Buttons, Choices, Float, Format, Id, Long, Short, String as Text};
+
+/// The function returns a value.
+const VALUE: u8 = 1;
+/// The function acts on a reference.
+const REFERENCE: u8 = 2;
+
+/// Opcodes verified against scripts compiled by the Construction Set.
+const VERIFIED_OPCODES: &[(&str, u16)] = &[("DontSaveObject", 0x115F)];
+
+/// Functions that come in `Get`, `Set` and `Mod` variants, as in `GetStrength`, `SetStrength`
+/// and `ModStrength`, given as their prefix, the stats, and the argument of `Set` and `Mod`.
+#[rustfmt::skip]
+const STAT_FAMILIES: &[(&str, &[&str], ArgumentKind)] = &[
+    ("", &[
+        "Strength", "Intelligence", "Willpower", "Agility", "Speed", "Endurance", "Personality", "Luck",
+    ], Float),
+    ("", &[
+        "Block", "Armorer", "MediumArmor", "HeavyArmor", "BluntWeapon", "LongBlade", "Axe", "Spear",
+        "Athletics", "Enchant", "Destruction", "Alteration", "Illusion", "Conjuration", "Mysticism",
+        "Restoration", "Alchemy", "Unarmored", "Security", "Sneak", "Acrobatics", "LightArmor",
+        "ShortBlade", "Marksman", "Mercantile", "Speechcraft", "HandToHand",
+    ], Float),
+    ("", &["Health", "Magicka", "Fatigue"], Float),
+    ("", &["Hello", "Fight", "Flee", "Alarm"], Short),
+    ("", &["Disposition", "Reputation"], Long),
+    ("", &[
+        "ArmorBonus", "AttackBonus", "Blindness", "CastPenalty", "Chameleon", "DefendBonus",
+        "Invisible", "Paralysis", "Silence", "SuperJump", "SwimSpeed", "WaterBreathing",
+        "WaterWalking",
+    ], Float),
+    ("Resist", &[
+        "Blight", "Corprus", "Disease", "Fire", "Frost", "Magicka", "NormalWeapons", "Paralysis",
+        "Poison", "Shock",
+    ], Float),
+];
+
+/// The remaining vanilla functions, given as their name, arguments, number of required
+/// arguments, and flags.
+#[rustfmt::skip]
+const VANILLA_FUNCTIONS: &[(&str, &[ArgumentKind], usize, u8)] = &[
+    // objects and inventory
+    ("Activate", &[], 0, REFERENCE),
+    ("AddItem", &[Id, Long], 2, REFERENCE),
+    ("AddSoulGem", &[Id, Id], 2, REFERENCE),
+    ("Disable", &[], 0, REFERENCE),
+    ("DontSaveObject", &[], 0, REFERENCE),
+    ("Drop", &[Id, Long], 2, REFERENCE),
+    ("DropSoulGem", &[Id], 1, REFERENCE),
+    ("Enable", &[], 0, REFERENCE),
+    ("Equip", &[Id], 1, REFERENCE),
+    ("GetArmorType", &[Short], 1, VALUE | REFERENCE),
+    ("GetDisabled", &[], 0, VALUE | REFERENCE),
+    ("GetItemCount", &[Id], 1, VALUE | REFERENCE),
+    ("GetLocked", &[], 0, VALUE | REFERENCE),
+    ("GetWeaponType", &[], 0, VALUE | REFERENCE),
+    ("HasItemEquipped", &[Id], 1, VALUE | REFERENCE),
+    ("HasSoulGem", &[Id], 1, VALUE | REFERENCE),
+    ("Lock", &[Short], 0, REFERENCE),
+    ("PlaceAtMe", &[Id, Long, Float, Short], 4, REFERENCE),
+    ("PlaceAtPC", &[Id, Long, Float, Short], 4, 0),
+    ("PlaceItem", &[Id, Float, Float, Float, Float], 5, 0),
+    ("PlaceItemCell", &[Id, Id, Float, Float, Float, Float], 6, 0),
+    ("RemoveItem", &[Id, Long], 2, REFERENCE),
+    ("RemoveSoulGem", &[Id], 1, REFERENCE),
+    ("RepairedOnMe", &[Id], 1, VALUE | REFERENCE),
+    ("SetDelete", &[Short], 1, REFERENCE),
+    ("Unlock", &[], 0, REFERENCE),
+    // events
+    ("CellChanged", &[], 0, VALUE),
+    ("GetButtonPressed", &[], 0, VALUE),
+    ("MenuMode", &[], 0, VALUE),
+    ("OnActivate", &[], 0, VALUE | REFERENCE),
+    ("OnDeath", &[], 0, VALUE | REFERENCE),
+    ("OnKnockout", &[], 0, VALUE | REFERENCE),
+    ("OnMurder", &[], 0, VALUE | REFERENCE),
+    ("OnPCAdd", &[], 0, VALUE | REFERENCE),
+    ("OnPCDrop", &[], 0, VALUE | REFERENCE),
+    ("OnPCEquip", &[], 0, VALUE | REFERENCE),
+    ("OnPCHitMe", &[], 0, VALUE | REFERENCE),
+    ("OnPCSoulGemUse", &[], 0, VALUE | REFERENCE),
+    ("OnRepair", &[], 0, VALUE | REFERENCE),
+    // position and movement
+    ("Face", &[Float, Float], 2, REFERENCE),
+    ("GetAngle", &[Axis], 1, VALUE | REFERENCE),
+    ("GetDistance", &[Id], 1, VALUE | REFERENCE),
+    ("GetPos", &[Axis], 1, VALUE | REFERENCE),
+    ("GetScale", &[], 0, VALUE | REFERENCE),
+    ("GetStartingAngle", &[Axis], 1, VALUE | REFERENCE),
+    ("GetStartingPos", &[Axis], 1, VALUE | REFERENCE),
+    ("ModScale", &[Float], 1, REFERENCE),
+    ("Move", &[Axis, Float], 2, REFERENCE),
+    ("MoveWorld", &[Axis, Float], 2, REFERENCE),
+    ("Position", &[Float, Float, Float, Float], 4, REFERENCE),
+    ("PositionCell", &[Float, Float, Float, Float, Id], 5, REFERENCE),
+    ("Rotate", &[Axis, Float], 2, REFERENCE),
+    ("RotateWorld", &[Axis, Float], 2, REFERENCE),
+    ("SetAngle", &[Axis, Float], 2, REFERENCE),
+    ("SetAtStart", &[], 0, REFERENCE),
+    ("SetPos", &[Axis, Float], 2, REFERENCE),
+    ("SetScale", &[Float], 1, REFERENCE),
+    // collisions and detection
+    ("GetCollidingActor", &[], 0, VALUE | REFERENCE),
+    ("GetCollidingPC", &[], 0, VALUE | REFERENCE),
+    ("GetDetected", &[Id], 1, VALUE | REFERENCE),
+    ("GetLineOfSight", &[Id], 1, VALUE | REFERENCE),
+    ("GetLOS", &[Id], 1, VALUE | REFERENCE),
+    ("GetStandingActor", &[], 0, VALUE | REFERENCE),
+    ("GetStandingPC", &[], 0, VALUE | REFERENCE),
+    ("HitAttemptOnMe", &[Id], 1, VALUE | REFERENCE),
+    ("HitOnMe", &[Id], 1, VALUE | REFERENCE),
+    ("HurtCollidingActor", &[Float], 1, REFERENCE),
+    ("HurtStandingActor", &[Float], 1, REFERENCE),
+    // actors
+    ("ClearForceJump", &[], 0, REFERENCE),
+    ("ClearForceMoveJump", &[], 0, REFERENCE),
+    ("ClearForceRun", &[], 0, REFERENCE),
+    ("ClearForceSneak", &[], 0, REFERENCE),
+    ("ForceGreeting", &[], 0, REFERENCE),
+    ("ForceJump", &[], 0, REFERENCE),
+    ("ForceMoveJump", &[], 0, REFERENCE),
+    ("ForceRun", &[], 0, REFERENCE),
+    ("ForceSneak", &[], 0, REFERENCE),
+    ("GetAttacked", &[], 0, VALUE | REFERENCE),
+    ("GetBlightDisease", &[], 0, VALUE | REFERENCE),
+    ("GetCommonDisease", &[], 0, VALUE | REFERENCE),
+    ("GetDeadCount", &[Id], 1, VALUE),
+    ("GetForceJump", &[], 0, VALUE | REFERENCE),
+    ("GetForceMoveJump", &[], 0, VALUE | REFERENCE),
+    ("GetForceRun", &[], 0, VALUE | REFERENCE),
+    ("GetForceSneak", &[], 0, VALUE | REFERENCE),
+    ("GetLevel", &[], 0, VALUE | REFERENCE),
+    ("GetRace", &[Id], 1, VALUE | REFERENCE),
+    ("GetSpellReadied", &[], 0, VALUE | REFERENCE),
+    ("GetTarget", &[Id], 1, VALUE | REFERENCE),
+    ("GetWeaponDrawn", &[], 0, VALUE | REFERENCE),
+    ("LowerRank", &[], 0, REFERENCE),
+    ("RaiseRank", &[], 0, REFERENCE),
+    ("Resurrect", &[], 0, REFERENCE),
+    ("SameFaction", &[], 0, VALUE | REFERENCE),
+    ("SetLevel", &[Short], 1, REFERENCE),
+    ("StartCombat", &[Id], 1, REFERENCE),
+    ("StopCombat", &[], 0, REFERENCE),
+    // AI packages
+    ("AIActivate", &[Id, Short], 1, REFERENCE),
+    ("AIEscort", &[Id, Float, Float, Float, Float, Short], 5, REFERENCE),
+    ("AIEscortCell", &[Id, Id, Float, Float, Float, Float, Short], 6, REFERENCE),
+    ("AIFollow", &[Id, Float, Float, Float, Float, Short], 5, REFERENCE),
+    ("AIFollowCell", &[Id, Id, Float, Float, Float, Float, Short], 6, REFERENCE),
+    ("AITravel", &[Float, Float, Float, Short], 3, REFERENCE),
+    ("AIWander", &[
+        Float, Float, Float, Short, Short, Short, Short, Short, Short, Short, Short, Short,
+    ], 3, REFERENCE),
+    ("GetAIPackageDone", &[], 0, VALUE | REFERENCE),
+    ("GetCurrentAIPackage", &[], 0, VALUE | REFERENCE),
+    // magic
+    ("AddSpell", &[Id], 1, REFERENCE),
+    ("Cast", &[Id, Id], 2, REFERENCE),
+    ("ExplodeSpell", &[Id], 1, REFERENCE),
+    ("GetEffect", &[Text], 1, VALUE | REFERENCE),
+    ("GetSpell", &[Id], 1, VALUE | REFERENCE),
+    ("GetSpellEffects", &[Id], 1, VALUE | REFERENCE),
+    ("RemoveEffects", &[Text], 1, REFERENCE),
+    ("RemoveSpell", &[Id], 1, REFERENCE),
+    ("RemoveSpellEffects", &[Id], 1, REFERENCE),
+    // factions and crime
+    ("GetFactionReaction", &[Id, Id], 2, VALUE),
+    ("GetPCCrimeLevel", &[], 0, VALUE),
+    ("GetPCFacRep", &[Id], 0, VALUE | REFERENCE),
+    ("GetPCRank", &[Id], 0, VALUE | REFERENCE),
+    ("GoToJail", &[], 0, 0),
+    ("ModFactionReaction", &[Id, Id, Long], 3, 0),
+    ("ModPCCrimeLevel", &[Float], 1, 0),
+    ("ModPCFacRep", &[Long, Id], 1, REFERENCE),
+    ("PayFine", &[], 0, 0),
+    ("PayFineThief", &[], 0, 0),
+    ("PCClearExpelled", &[Id], 0, REFERENCE),
+    ("PCExpell", &[Id], 0, REFERENCE),
+    ("PCExpelled", &[Id], 0, VALUE | REFERENCE),
+    ("PCJoinFaction", &[Id], 0, REFERENCE),
+    ("PCLowerRank", &[Id], 0, REFERENCE),
+    ("PCRaiseRank", &[Id], 0, REFERENCE),
+    ("SetFactionReaction", &[Id, Id, Long], 3, 0),
+    ("SetPCCrimeLevel", &[Float], 1, 0),
+    ("SetPCFacRep", &[Long, Id], 1, REFERENCE),
+    // dialogue and journal
+    ("AddTopic", &[Id], 1, 0),
+    ("Choice", &[Choices], 0, 0),
+    ("ClearInfoActor", &[], 0, 0),
+    ("GetJournalIndex", &[Id], 1, VALUE),
+    ("Goodbye", &[], 0, 0),
+    ("Journal", &[Id, Long], 2, 0),
+    ("MessageBox", &[Format, Buttons], 1, 0),
+    ("Say", &[Text, Format], 2, REFERENCE),
+    ("SayDone", &[], 0, VALUE | REFERENCE),
+    ("SetJournalIndex", &[Id, Long], 2, 0),
+    // scripts
+    ("GetSecondsPassed", &[], 0, VALUE),
+    ("GetSquareRoot", &[Float], 1, VALUE),
+    ("Random", &[Short], 1, VALUE),
+    ("Random100", &[], 0, VALUE),
+    ("ScriptRunning", &[Id], 1, VALUE),
+    ("StartScript", &[Id], 1, 0),
+    ("StopScript", &[Id], 1, 0),
+    // player
+    ("BecomeWerewolf", &[], 0, REFERENCE),
+    ("DisableLevitation", &[], 0, 0),
+    ("DisablePlayerControls", &[], 0, 0),
+    ("DisablePlayerFighting", &[], 0, 0),
+    ("DisablePlayerJumping", &[], 0, 0),
+    ("DisablePlayerLooking", &[], 0, 0),
+    ("DisablePlayerMagic", &[], 0, 0),
+    ("DisablePlayerViewSwitch", &[], 0, 0),
+    ("DisableTeleporting", &[], 0, 0),
+    ("DisableVanityMode", &[], 0, 0),
+    ("EnableLevitation", &[], 0, 0),
+    ("EnablePlayerControls", &[], 0, 0),
+    ("EnablePlayerFighting", &[], 0, 0),
+    ("EnablePlayerJumping", &[], 0, 0),
+    ("EnablePlayerLooking", &[], 0, 0),
+    ("EnablePlayerMagic", &[], 0, 0),
+    ("EnablePlayerViewSwitch", &[], 0, 0),
+    ("EnableTeleporting", &[], 0, 0),
+    ("EnableVanityMode", &[], 0, 0),
+    ("GetPCCell", &[Id], 1, VALUE),
+    ("GetPCInJail", &[], 0, VALUE),
+    ("GetPCJumping", &[], 0, VALUE),
+    ("GetPCRunning", &[], 0, VALUE),
+    ("GetPCSleep", &[], 0, VALUE),
+    ("GetPCSneaking", &[], 0, VALUE),
+    ("GetPCTraveling", &[], 0, VALUE),
+    ("GetPlayerControlsDisabled", &[], 0, VALUE),
+    ("GetPlayerFightingDisabled", &[], 0, VALUE),
+    ("GetPlayerJumpingDisabled", &[], 0, VALUE),
+    ("GetPlayerLookingDisabled", &[], 0, VALUE),
+    ("GetPlayerMagicDisabled", &[], 0, VALUE),
+    ("GetPlayerViewSwitchDisabled", &[], 0, VALUE),
+    ("GetVanityModeDisabled", &[], 0, VALUE),
+    ("GetWerewolfKills", &[], 0, VALUE),
+    ("IsWerewolf", &[], 0, VALUE | REFERENCE),
+    ("PCForce1stPerson", &[], 0, 0),
+    ("PCForce3rdPerson", &[], 0, 0),
+    ("PCGet3rdPerson", &[], 0, VALUE),
+    ("SetWerewolfAcrobatics", &[], 0, REFERENCE),
+    ("UndoWerewolf", &[], 0, REFERENCE),
+    ("WakeUpPC", &[], 0, 0),
+    // menus
+    ("EnableBirthMenu", &[], 0, 0),
+    ("EnableClassMenu", &[], 0, 0),
+    ("EnableInventoryMenu", &[], 0, 0),
+    ("EnableMagicMenu", &[], 0, 0),
+    ("EnableMapMenu", &[], 0, 0),
+    ("EnableNameMenu", &[], 0, 0),
+    ("EnableRaceMenu", &[], 0, 0),
+    ("EnableRest", &[], 0, 0),
+    ("EnableStatReviewMenu", &[], 0, 0),
+    ("EnableStatsMenu", &[], 0, 0),
+    ("FillMap", &[], 0, 0),
+    ("ShowMap", &[Id], 1, 0),
+    ("ShowRestMenu", &[], 0, 0),
+    // world
+    ("AddToLevCreature", &[Id, Id, Short], 3, 0),
+    ("AddToLevItem", &[Id, Id, Short], 3, 0),
+    ("ChangeWeather", &[Id, Short], 2, 0),
+    ("GetCurrentWeather", &[], 0, VALUE),
+    ("GetInterior", &[], 0, VALUE | REFERENCE),
+    ("GetWaterLevel", &[], 0, VALUE),
+    ("GetWindSpeed", &[], 0, VALUE),
+    ("ModRegion", &[
+        Id, Short, Short, Short, Short, Short, Short, Short, Short, Short, Short,
+    ], 11, 0),
+    ("ModWaterLevel", &[Float], 1, 0),
+    ("RemoveFromLevCreature", &[Id, Id, Short], 3, 0),
+    ("RemoveFromLevItem", &[Id, Id, Short], 3, 0),
+    ("SetWaterLevel", &[Float], 1, 0),
+    // sound and animation
+    ("FadeIn", &[Float], 1, 0),
+    ("FadeOut", &[Float], 1, 0),
+    ("FadeTo", &[Float, Float], 2, 0),
+    ("GetSoundPlaying", &[Id], 1, VALUE | REFERENCE),
+    ("LoopGroup", &[Id, Short, Short], 2, REFERENCE),
+    ("PlayBink", &[Text, Short], 2, 0),
+    ("PlayGroup", &[Id, Short], 1, REFERENCE),
+    ("PlayLoopSound3D", &[Id], 1, REFERENCE),
+    ("PlayLoopSound3DVP", &[Id, Float, Float], 3, REFERENCE),
+    ("PlaySound", &[Id], 1, 0),
+    ("PlaySound3D", &[Id], 1, REFERENCE),
+    ("PlaySound3DVP", &[Id, Float, Float], 3, REFERENCE),
+    ("PlaySoundVP", &[Id, Float, Float], 3, 0),
+    ("SkipAnim", &[], 0, REFERENCE),
+    ("StopSound", &[Id], 1, REFERENCE),
+    ("StreamMusic", &[Text], 1, 0),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_functions() {
+        let functions = ScriptFunctions::default();
+
+        let add_item = functions.get("additem").unwrap();
+        assert_eq!(add_item.arguments, [Id, Long]);
+        assert!(add_item.takes_reference && !add_item.returns_value);
+        assert_eq!(add_item.opcode, None);
+
+        let get_strength = functions.get("GetStrength").unwrap();
+        assert!(get_strength.returns_value && get_strength.arguments.is_empty());
+        assert_eq!(functions.get("ModCurrentHealth").unwrap().arguments, [Float]);
+        assert_eq!(functions.get("SetResistFire").unwrap().arguments, [Float]);
+
+        let wander = functions.get("AIWander").unwrap();
+        assert_eq!((wander.required_arguments, wander.max_arguments()), (3, Some(12)));
+
+        let message_box = functions.get("MessageBox").unwrap();
+        assert!(message_box.is_variadic() && !message_box.takes_reference);
+        assert_eq!(message_box.max_arguments(), None);
+
+        // only verified opcodes are included
+        assert_eq!(functions.iter().filter(|function| function.opcode.is_some()).count(), 1);
+        assert_eq!(functions.get_by_opcode(0x115F).unwrap().name, "DontSaveObject");
+
+        assert_eq!(format_arguments("%g of %.2f, 100%% %s"), 3);
+    }
+}
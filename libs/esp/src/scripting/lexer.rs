@@ -0,0 +1,170 @@
+use crate::prelude::*;
+
+const OPERATORS: [&str; 15] = ["->", "==", "!=", "<=", ">=", "<", ">", "=", "+", "-", "*", "/", "(", ")", "."];
+
+/// A position in script source, using 1-based line and column numbers.
+///
+/// Columns count bytes, which matches characters for the ASCII text scripts are written in.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A range of script source, from `start` up to but excluding `end`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    #[must_use]
+    pub const fn to(self, other: Self) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    Identifier,
+    Number,
+    /// A quoted string, whose text excludes the quotes.
+    String,
+    Operator,
+    Newline,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl Token<'_> {
+    /// Whether this token is the given keyword or operator, ignoring case.
+    pub fn is(&self, text: &str) -> bool {
+        matches!(self.kind, TokenKind::Identifier | TokenKind::Operator) && self.text.eq_ignore_ascii_case(text)
+    }
+
+    /// Whether this token can be used as an id, such as a function argument.
+    pub const fn is_id(&self) -> bool {
+        matches!(self.kind, TokenKind::Identifier | TokenKind::String | TokenKind::Number)
+    }
+}
+
+/// Split script source into tokens.
+///
+/// Comments are skipped, and commas are treated as whitespace. Every line, including the last,
+/// ends with a [`TokenKind::Newline`] token. Unexpected characters are reported and skipped.
+///
+pub fn tokenize(source: &str) -> (Vec<Token<'_>>, Vec<Diagnostic>) {
+    let mut tokens = vec![];
+    let mut diagnostics = vec![];
+
+    for (line_index, line) in source.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let bytes = line.as_bytes();
+
+        #[allow(clippy::cast_possible_truncation)]
+        let span = |start: usize, end: usize| Span {
+            start: Position {
+                line: line_index as u32 + 1,
+                column: start as u32 + 1,
+            },
+            end: Position {
+                line: line_index as u32 + 1,
+                column: end as u32 + 1,
+            },
+        };
+
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            let start = i;
+
+            if c == b';' {
+                break;
+            }
+
+            if c.is_ascii_whitespace() || c == b',' {
+                i += 1;
+                continue;
+            }
+
+            if c == b'"' {
+                let end = line[i + 1..].find('"').map(|j| i + 1 + j);
+                let text_end = end.unwrap_or(bytes.len());
+                if end.is_none() {
+                    diagnostics.push(Diagnostic::error(span(start, text_end), "Unterminated string"));
+                }
+                i = end.map_or(text_end, |end| end + 1);
+                tokens.push(Token {
+                    kind: TokenKind::String,
+                    text: &line[start + 1..text_end],
+                    span: span(start, i),
+                });
+                continue;
+            }
+
+            if is_word_byte(c) {
+                let mut kind = TokenKind::Identifier;
+                if c.is_ascii_digit() {
+                    kind = TokenKind::Number;
+                    while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                        i += 1;
+                    }
+                }
+                if i < bytes.len() && is_word_byte(bytes[i]) {
+                    kind = TokenKind::Identifier;
+                }
+                if kind == TokenKind::Identifier {
+                    while i < bytes.len() && is_word_byte(bytes[i]) {
+                        i += 1;
+                    }
+                }
+                tokens.push(Token {
+                    kind,
+                    text: &line[start..i],
+                    span: span(start, i),
+                });
+                continue;
+            }
+
+            if let Some(operator) = OPERATORS.iter().find(|operator| line[i..].starts_with(*operator)) {
+                i += operator.len();
+                tokens.push(Token {
+                    kind: TokenKind::Operator,
+                    text: operator,
+                    span: span(start, i),
+                });
+                continue;
+            }
+
+            let len = line[i..].chars().next().map_or(1, char::len_utf8);
+            i += len;
+            diagnostics.push(Diagnostic::error(
+                span(start, i),
+                format!("Unexpected character: {:?}", &line[start..i]),
+            ));
+        }
+
+        tokens.push(Token {
+            kind: TokenKind::Newline,
+            text: "",
+            span: span(bytes.len(), bytes.len()),
+        });
+    }
+
+    (tokens, diagnostics)
+}
+
+const fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
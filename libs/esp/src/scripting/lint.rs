@@ -0,0 +1,492 @@
+//! Static analysis of scripts and dialogue results.
+
+use crate::prelude::*;
+
+/// Functions whose first argument must be a journal topic.
+const JOURNAL_FUNCTIONS: [&str; 3] = ["GetJournalIndex", "Journal", "SetJournalIndex"];
+
+/// Ids longer than this are truncated in fixed size fields, such as the name of a script header.
+const MAX_ID_LENGTH: usize = 31;
+
+/// Where linted source comes from, which determines how names are resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LintContext<'a> {
+    /// A global or object script, which starts with `Begin` and declares its own variables.
+    Script,
+    /// A dialogue result, which can use the variables of the speaker's script. Local variables
+    /// are not checked when the speaker is unknown.
+    Dialogue { speaker_id: Option<&'a str> },
+}
+
+impl ScriptCompiler {
+    /// Check a parsed script for likely mistakes, resolving names against the loaded content.
+    pub fn lint(&self, ast: &ScriptAst, context: LintContext<'_>) -> Vec<Diagnostic> {
+        let mut linter = Linter {
+            compiler: self,
+            context,
+            locals: default(),
+            declared: HashSet::new(),
+            diagnostics: vec![],
+        };
+
+        ast.walk(&mut |statement| {
+            if let StatementKind::Declare { kind, name } = &statement.kind {
+                // Duplicates are reported while linting, keeping the first declaration here.
+                linter.locals.add(*kind, &name.text).ok();
+            }
+        });
+
+        match (&ast.name, context) {
+            (None, LintContext::Script) => {
+                let start = ast.body.first().map(|statement| statement.span).unwrap_or_default();
+                linter.error(start, "Expected Begin");
+            }
+            (Some(name), LintContext::Script) if name.text.len() > MAX_ID_LENGTH => {
+                linter.warning(
+                    name.span,
+                    format!("Script name is longer than {MAX_ID_LENGTH} characters and will be truncated"),
+                );
+            }
+            (Some(name), LintContext::Dialogue { .. }) => {
+                linter.error(name.span, "Begin can not be used in dialogue results");
+            }
+            _ => {}
+        }
+
+        linter.statements(&ast.body, 0);
+        linter.diagnostics
+    }
+}
+
+impl Script {
+    /// Parse and lint the script text, returning syntax errors and lint findings.
+    pub fn lint(&self, compiler: &ScriptCompiler) -> Vec<Diagnostic> {
+        let (ast, mut diagnostics) = ScriptAst::parse(&self.text, &compiler.functions);
+        diagnostics.extend(compiler.lint(&ast, LintContext::Script));
+        if let Some(name) = ast.name.filter(|name| !name.text.eq_ignore_ascii_case(&self.id)) {
+            diagnostics.push(Diagnostic::warning(
+                name.span,
+                format!("Script name does not match its id: {}", self.id),
+            ));
+        }
+        diagnostics
+    }
+}
+
+impl DialogueInfo {
+    /// Parse and lint the result script, returning syntax errors and lint findings.
+    pub fn lint(&self, compiler: &ScriptCompiler) -> Vec<Diagnostic> {
+        let (ast, mut diagnostics) = ScriptAst::parse(&self.script_text, &compiler.functions);
+        let speaker_id = Some(self.speaker_id.as_str()).filter(|id| !id.is_empty());
+        diagnostics.extend(compiler.lint(&ast, LintContext::Dialogue { speaker_id }));
+        diagnostics
+    }
+}
+
+#[derive(Debug)]
+struct Linter<'a> {
+    compiler: &'a ScriptCompiler,
+    context: LintContext<'a>,
+    /// All declared variables, wherever they are declared.
+    locals: ScriptVariables,
+    /// Lowercase names of the variables declared so far.
+    declared: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::error(span, message));
+    }
+
+    fn warning(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::warning(span, message));
+    }
+
+    fn local(&self, name: &str) -> Option<VariableKind> {
+        self.locals.get(name).map(|(kind, _)| kind)
+    }
+
+    /// Whether a name is a variable, or can not be checked in this context.
+    fn is_variable(&self, name: &str) -> bool {
+        if self.local(name).is_some() || self.compiler.has_global(name) {
+            return true;
+        }
+        match self.context {
+            LintContext::Script => false,
+            LintContext::Dialogue { speaker_id: None } => true,
+            LintContext::Dialogue {
+                speaker_id: Some(speaker_id),
+            } => self.compiler.object_variable(speaker_id, name).is_some(),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement], depth: usize) {
+        let mut returned = false;
+
+        for statement in statements {
+            if returned && !matches!(statement.kind, StatementKind::Declare { .. }) {
+                self.warning(statement.span, "Unreachable code after Return");
+                returned = false;
+            }
+
+            match &statement.kind {
+                StatementKind::Declare { name, .. } => self.declare(name, depth),
+                StatementKind::Set { target, value } => {
+                    match &target.kind {
+                        ExpressionKind::Name(name) if !self.is_variable(name) => {
+                            self.error(target.span, format!("Undeclared variable: {name}"));
+                        }
+                        ExpressionKind::Member { reference, name } => self.member(reference, name),
+                        _ => {}
+                    }
+                    self.expression(value);
+                }
+                StatementKind::If { branches, otherwise } => {
+                    for branch in branches {
+                        self.expression(&branch.condition);
+                        self.statements(&branch.body, depth + 1);
+                    }
+                    if let Some(otherwise) = otherwise {
+                        self.statements(otherwise, depth + 1);
+                    }
+                }
+                StatementKind::While { condition, body } => {
+                    self.expression(condition);
+                    self.statements(body, depth + 1);
+                }
+                StatementKind::Return => returned = true,
+                StatementKind::Call(call) => self.call(call, true),
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &Identifier, depth: usize) {
+        if let LintContext::Dialogue { .. } = self.context {
+            self.error(name.span, "Variables can not be declared in dialogue results");
+            return;
+        }
+
+        let text = &name.text;
+        if !self.declared.insert(text.to_ascii_lowercase()) {
+            self.error(name.span, format!("Variable is already declared: {text}"));
+            return;
+        }
+        if depth > 0 {
+            self.warning(
+                name.span,
+                format!("Variable is declared inside a block, but applies to the whole script: {text}"),
+            );
+        }
+        if text.starts_with(|c: char| c.is_ascii_digit()) {
+            self.warning(
+                name.span,
+                format!("Variable names starting with a digit are misread by the engine: {text}"),
+            );
+        }
+        if self.compiler.functions.get(text).is_some() {
+            self.warning(name.span, format!("Variable shadows a function: {text}"));
+        } else if self.compiler.has_global(text) {
+            self.warning(name.span, format!("Variable shadows a global: {text}"));
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Name(name) => {
+                if self.is_variable(name) {
+                    return;
+                }
+                match self.compiler.functions.get(name) {
+                    Some(function) if !function.returns_value => {
+                        self.error(
+                            expression.span,
+                            format!("Function does not return a value: {}", function.name),
+                        );
+                    }
+                    Some(_) => {}
+                    None => self.error(expression.span, format!("Undeclared variable: {name}")),
+                }
+            }
+            ExpressionKind::Member { reference, name } => self.member(reference, name),
+            ExpressionKind::Call(call) => self.call(call, false),
+            ExpressionKind::Negate(operand) => self.expression(operand),
+            ExpressionKind::Binary { operator, left, right } => {
+                let equality = matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual);
+                if equality && (self.is_float(left) || self.is_float(right)) {
+                    self.warning(
+                        expression.span,
+                        "Floats are rarely exactly equal, compare against a range instead",
+                    );
+                }
+                self.expression(left);
+                self.expression(right);
+            }
+            ExpressionKind::Number(_) | ExpressionKind::String(_) | ExpressionKind::Invalid => {}
+        }
+    }
+
+    fn is_float(&self, expression: &Expression) -> bool {
+        match &expression.kind {
+            ExpressionKind::Number(number) => number.contains('.'),
+            ExpressionKind::Name(name) => self.local(name) == Some(VariableKind::Float),
+            _ => false,
+        }
+    }
+
+    fn member(&mut self, reference: &Identifier, name: &Identifier) {
+        if !self.id(reference) {
+            return;
+        }
+        if self.compiler.object_variable(&reference.text, &name.text).is_none() {
+            self.error(
+                reference.span.to(name.span),
+                format!("Unknown variable of {}: {}", reference.text, name.text),
+            );
+        }
+    }
+
+    /// Check that an id exists, returning whether it does.
+    fn id(&mut self, id: &Identifier) -> bool {
+        let exists = self.compiler.has_object(&id.text);
+        if !exists {
+            self.warning(id.span, format!("Unknown id: {}", id.text));
+        }
+        exists
+    }
+
+    fn call(&mut self, call: &Call, statement: bool) {
+        if let Some(reference) = &call.reference {
+            self.id(reference);
+        }
+
+        let name = &call.function.text;
+        let journal = JOURNAL_FUNCTIONS.iter().any(|function| function.eq_ignore_ascii_case(name));
+        if journal {
+            if let Some(topic) = call.arguments.first() {
+                self.journal_topic(name, topic);
+            }
+        }
+
+        let Some(function) = self.compiler.functions.get(name) else {
+            self.warning(call.function.span, format!("Unknown function: {name}"));
+            return;
+        };
+
+        if !statement && !function.returns_value {
+            self.error(call.span, format!("Function does not return a value: {}", function.name));
+        }
+        if call.arguments.len() < function.required_arguments {
+            self.error(call.span, format!("Missing arguments for {}", function.name));
+        }
+        if let Some(max) = function.max_arguments().filter(|max| call.arguments.len() > *max) {
+            self.warning(call.arguments[max].span, format!("Too many arguments for {}", function.name));
+        }
+        if let Some(reference) = call.reference.as_ref().filter(|_| !function.takes_reference) {
+            self.warning(reference.span, format!("{} does not act on a reference", function.name));
+        }
+
+        let skip = usize::from(journal);
+        for (i, (argument, kind)) in call.arguments.iter().zip(&function.arguments).enumerate().skip(skip) {
+            if *kind == ArgumentKind::Format {
+                // the format is followed by a variable for each of its specifiers
+                if let ExpressionKind::String(format) = &argument.kind {
+                    if call.arguments.len() - i - 1 < format_arguments(format) {
+                        self.warning(
+                            argument.span,
+                            format!("Missing variables for the format of {}", function.name),
+                        );
+                    }
+                }
+            }
+            if kind.is_variadic() {
+                break;
+            }
+            if *kind == ArgumentKind::Id {
+                if let ExpressionKind::Name(text) | ExpressionKind::String(text) = &argument.kind {
+                    self.id(&Identifier {
+                        text: text.clone(),
+                        span: argument.span,
+                    });
+                }
+            }
+        }
+    }
+
+    fn journal_topic(&mut self, function: &str, topic: &Expression) {
+        let (ExpressionKind::Name(id) | ExpressionKind::String(id)) = &topic.kind else {
+            return;
+        };
+        match self.compiler.dialogue_type(id) {
+            Some(DialogueType2::Journal) => {}
+            Some(_) => self.warning(topic.span, format!("{function} expects a journal topic: {id}")),
+            None => self.warning(topic.span, format!("Unknown journal topic: {id}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_script_and_dialogue() {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            GlobalVariable {
+                id: "GameHour".into(),
+                ..default()
+            }
+            .into(),
+        );
+        plugin.objects.push(
+            Script {
+                id: "guard_script".into(),
+                variables: ScriptVariables {
+                    shorts: vec!["alarmed".into()],
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        plugin.objects.push(
+            Npc {
+                id: "guard".into(),
+                script: "guard_script".into(),
+                ..default()
+            }
+            .into(),
+        );
+        for (id, dialogue_type) in [("A1_quest", DialogueType2::Journal), ("rumors", DialogueType2::Topic)] {
+            plugin.objects.push(
+                Dialogue {
+                    id: id.into(),
+                    dialogue_type,
+                    ..default()
+                }
+                .into(),
+            );
+        }
+
+        let compiler = ScriptCompiler::from_plugin(&plugin);
+
+        let script = Script {
+            id: "test".into(),
+            text: [
+                "Begin test",
+                "float timer",
+                "if ( GetJournalIndex rumors >= 10 )",
+                "\tshort done",
+                "\tset guard.alarmed to count",
+                "\treturn",
+                "\tMessageBox \"Not shown\"",
+                "elseif ( timer == 1.5 )",
+                "\tplayer->AddItem Gold_001 10",
+                "\tguard->Resurect",
+                "endif",
+                "End",
+            ]
+            .join("\r\n"),
+            ..default()
+        };
+        let messages: Vec<_> = script.lint(&compiler).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "3:22: warning: GetJournalIndex expects a journal topic: rumors",
+                "4:8: warning: Variable is declared inside a block, but applies to the whole script: done",
+                "5:23: error: Undeclared variable: count",
+                "7:2: warning: Unreachable code after Return",
+                "8:8: warning: Floats are rarely exactly equal, compare against a range instead",
+                "9:18: warning: Unknown id: Gold_001",
+                "10:9: warning: Unknown function: Resurect",
+            ]
+        );
+        assert!(!messages.iter().any(|message| message.contains("player")));
+
+        let info = DialogueInfo {
+            speaker_id: "guard".into(),
+            script_text: "short x\r\nset alarmed to 1\r\nset missing to GetJournalIndex A1_quest".into(),
+            ..default()
+        };
+        let messages: Vec<_> = info.lint(&compiler).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "1:7: error: Variables can not be declared in dialogue results",
+                "3:5: error: Undeclared variable: missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_vanilla_functions() {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            Dialogue {
+                id: "A1_quest".into(),
+                dialogue_type: DialogueType2::Journal,
+                ..default()
+            }
+            .into(),
+        );
+        let compiler = ScriptCompiler::from_plugin(&plugin);
+
+        // written in the style of the scripts of the vanilla game
+        let script = Script {
+            id: "test".into(),
+            text: [
+                "Begin test",
+                "short state",
+                "short button",
+                "float timer",
+                "if ( MenuMode == 1 )",
+                "\treturn",
+                "endif",
+                "if ( GetJournalIndex A1_quest < 10 )",
+                "\treturn",
+                "endif",
+                "set timer to ( timer + GetSecondsPassed )",
+                "if ( state == 0 )",
+                "\tif ( GetDistance player < 512 )",
+                "\t\tMessageBox \"You waited %.0f seconds, 100%% sure?\" timer \"Yes\" \"No\"",
+                "\t\tset state to 1",
+                "\tendif",
+                "elseif ( state == 1 )",
+                "\tset button to GetButtonPressed",
+                "\tif ( button == 0 )",
+                "\t\tJournal A1_quest 20",
+                "\t\tModDisposition 10",
+                "\t\tModCurrentFatigue -20",
+                "\t\tAIWander 512 5 0 60 20 10 10 0 0 0 0 0",
+                "\telseif ( button == 1 )",
+                "\t\tif ( GetHealthGetRatio < 0.5 )",
+                "\t\t\tStartCombat player",
+                "\t\tendif",
+                "\tendif",
+                "\tset state to 2",
+                "endif",
+                "if ( OnDeath == 1 )",
+                "\tplayer->ModReputation 1",
+                "\tplayer->MessageBox \"%g\"",
+                "endif",
+                "End",
+            ]
+            .join("\r\n"),
+            ..default()
+        };
+        let messages: Vec<_> = script.lint(&compiler).iter().map(ToString::to_string).collect();
+        assert!(
+            !messages.iter().any(|message| message.contains("Unknown function")),
+            "{messages:?}"
+        );
+        assert_eq!(
+            messages,
+            [
+                "33:2: warning: MessageBox does not act on a reference",
+                "33:21: warning: Missing variables for the format of MessageBox",
+            ]
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! Statement opcodes and expression prefixes of compiled script bytecode.
+
+pub const END: u16 = 0x0101;
+pub const SET: u16 = 0x0105;
+pub const IF: u16 = 0x0106;
+pub const ELSE: u16 = 0x0107;
+pub const ELSEIF: u16 = 0x0108;
+pub const ENDIF: u16 = 0x0109;
+pub const REFERENCE: u16 = 0x010C;
+pub const WHILE: u16 = 0x010E;
+pub const ENDWHILE: u16 = 0x010F;
+pub const RETURN: u16 = 0x0124;
+
+/// Prefix of a global variable, followed by its name.
+pub const GLOBAL: u8 = b'G';
+/// Prefix of an explicit reference, followed by its id.
+pub const EXPLICIT_REFERENCE: u8 = b'r';
+/// Prefix of a function call within an expression, followed by its opcode.
+pub const FUNCTION: u8 = b'X';
@@ -1,6 +1,12 @@
 mod editor_id;
 pub use editor_id::*;
 
+mod cell_references;
+
+mod landscape_textures;
+
+mod filter_notation;
+
 mod sort_objects;
 
 mod type_info;
@@ -8,3 +14,48 @@ pub use type_info::*;
 
 mod object_info;
 pub use object_info::*;
+
+mod terrain;
+pub use terrain::*;
+
+mod world_map;
+pub use world_map::*;
+
+mod topic;
+pub use topic::*;
+
+mod dialogue_merge;
+pub use dialogue_merge::*;
+
+mod dialogue_filters;
+pub use dialogue_filters::*;
+
+mod quest;
+pub use quest::*;
+
+mod autocalc;
+pub use autocalc::*;
+
+mod magic_cost;
+pub use magic_cost::*;
+
+mod leveled_lists;
+pub use leveled_lists::*;
+
+mod leveled_merge;
+pub use leveled_merge::*;
+
+mod merge;
+pub use merge::*;
+
+mod game_settings;
+pub use game_settings::*;
+
+mod load_order;
+pub use load_order::*;
+
+mod travel;
+pub use travel::*;
+
+mod spatial_index;
+pub use spatial_index::*;
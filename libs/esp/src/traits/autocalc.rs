@@ -0,0 +1,644 @@
+use crate::prelude::*;
+
+/// The stats and spells the engine calculates for an NPC with the auto calculate flag set.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AutoCalcStats {
+    pub stats: NpcStats,
+    /// Ids of the spells given to the NPC in addition to its own spell list.
+    pub spells: Vec<String>,
+}
+
+/// Calculates NPC stats the same way the engine does for NPCs with the auto calculate flag set.
+///
+/// Records and game settings are taken from a load ordered set of plugins. Game settings which
+/// are not defined by any plugin use their vanilla values.
+///
+#[derive(Clone, Debug, Default)]
+pub struct NpcAutoCalc {
+    races: HashMap<String, Race>,
+    classes: HashMap<String, Class>,
+    skills: HashMap<SkillId, SkillData>,
+    magic_effects: HashMap<EffectId, MagicEffectData>,
+    /// Spells in the order they were first defined. Spell selection depends on this order.
+    spells: Vec<Spell>,
+    game_settings: GameSettings,
+    /// Spell costs are calculated, as the engine ignores the stored cost of autocalculated spells.
+    costs: MagicCostCalculator,
+}
+
+impl NpcAutoCalc {
+    /// Collect the records used by autocalculation from plugins, which must be provided in
+    /// load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let plugins: Vec<_> = plugins.into_iter().collect();
+
+        let mut this = Self {
+            costs: MagicCostCalculator::from_plugins(plugins.iter().copied()),
+            ..default()
+        };
+        let mut spell_positions: HashMap<String, usize> = HashMap::new();
+
+        for object in plugins.into_iter().flat_map(|plugin| &plugin.objects) {
+            let id = object.editor_id_ascii_lowercase().into_owned();
+            let deleted = object.deleted();
+
+            match object {
+                TES3Object::Race(race) => {
+                    this.races.remove(&id);
+                    if !deleted {
+                        this.races.insert(id, race.clone());
+                    }
+                }
+                TES3Object::Class(class) => {
+                    this.classes.remove(&id);
+                    if !deleted {
+                        this.classes.insert(id, class.clone());
+                    }
+                }
+                TES3Object::Skill(skill) => {
+                    this.skills.insert(skill.skill_id, skill.data.clone());
+                }
+                TES3Object::MagicEffect(effect) => {
+                    this.magic_effects.insert(effect.effect_id, effect.data.clone());
+                }
+                TES3Object::GameSetting(setting) => {
+                    this.game_settings.insert(setting);
+                }
+                TES3Object::Spell(spell) => {
+                    if deleted {
+                        if let Some(position) = spell_positions.remove(&id) {
+                            this.spells.remove(position);
+                            spell_positions.values_mut().filter(|i| **i > position).for_each(|i| *i -= 1);
+                        }
+                    } else if let Some(&position) = spell_positions.get(&id) {
+                        this.spells[position] = spell.clone();
+                    } else {
+                        spell_positions.insert(id, this.spells.len());
+                        this.spells.push(spell.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        this
+    }
+
+    /// The value of a numeric game setting, falling back to its vanilla value.
+    fn game_setting(&self, id: &str) -> f32 {
+        self.game_settings.get_f32(id).unwrap_or_default()
+    }
+
+    fn spell(&self, id: &str) -> Option<&Spell> {
+        self.spells.iter().find(|spell| spell.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Calculate the stats and spells of an NPC from its race, class and level.
+    ///
+    /// Fails if the race, the class, or any skill record can not be found.
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn calculate(&self, npc: &Npc) -> io::Result<AutoCalcStats> {
+        let race = self
+            .races
+            .get(&npc.race.to_ascii_lowercase())
+            .ok_or_else(|| not_found("Race", &npc.race))?;
+        let class = self
+            .classes
+            .get(&npc.class.to_ascii_lowercase())
+            .ok_or_else(|| not_found("Class", &npc.class))?;
+
+        let mut skill_data = vec![];
+        for index in 0..27 {
+            let skill = SkillId::try_from(index).unwrap_or_default();
+            let data = self.skills.get(&skill).ok_or_else(|| not_found("Skill", skill.display()))?;
+            skill_data.push(data);
+        }
+
+        let level = f32::from(npc.data.level) - 1.0;
+        let female = npc.npc_flags.contains(NpcFlags::FEMALE);
+        let majors = major_skills(&class.data).map(|skill| skill as i32);
+        let minors = minor_skills(&class.data).map(|skill| skill as i32);
+
+        // Attributes start from the race, with a bonus for the class's favored attributes, and
+        // grow with level depending on how many of their governed skills the class favors.
+        let mut attributes = race_attributes(&race.data, female);
+        for (index, attribute) in attributes.iter_mut().enumerate() {
+            let favored = [class.data.attribute1, class.data.attribute2];
+            if favored.iter().any(|favored| *favored as i32 == index as i32) {
+                *attribute += 10;
+            }
+            let mut modifier = 0.0;
+            for (skill, data) in skill_data.iter().enumerate() {
+                if data.governing_attribute != index as i32 {
+                    continue;
+                }
+                modifier += if majors.contains(&(skill as i32)) {
+                    1.0
+                } else if minors.contains(&(skill as i32)) {
+                    0.5
+                } else {
+                    0.2
+                };
+            }
+            *attribute = ((*attribute as f32 + level * modifier).round_ties_even() as i32).min(100);
+        }
+
+        // Skills start from their class and race bonuses, and grow faster when favored by the
+        // class or matching its specialization.
+        let race_bonuses = race_skill_bonuses(&race.data.skill_bonuses);
+        let mut skills = [0i32; 27];
+        for (index, skill) in skills.iter_mut().enumerate() {
+            let index_i32 = index as i32;
+            let mut base = 5;
+            let mut multiplier = 0.1;
+            if majors.contains(&index_i32) {
+                base += 25;
+                multiplier = 1.0;
+            } else if minors.contains(&index_i32) {
+                base += 10;
+                multiplier = 1.0;
+            }
+            if let Some((_, bonus)) = race_bonuses.iter().find(|(skill, _)| *skill as i32 == index_i32) {
+                base += bonus;
+            }
+            if skill_data[index].specialization == class.data.specialization as i32 {
+                base += 5;
+                multiplier += 0.5;
+            }
+            *skill = ((base as f32 + level * multiplier).round_ties_even() as i32).min(100);
+        }
+
+        let [strength, intelligence, willpower, agility, _, endurance, _, _] = attributes;
+
+        let mut health_per_level = 3;
+        match class.data.specialization {
+            Specialization::Combat => health_per_level += 2,
+            Specialization::Stealth => health_per_level += 1,
+            _ => {}
+        }
+        if [class.data.attribute1, class.data.attribute2].contains(&AttributeId::Endurance) {
+            health_per_level += 1;
+        }
+        #[allow(clippy::manual_midpoint)] // i32::midpoint requires Rust 1.87
+        let health = (strength + endurance) / 2 + health_per_level * (i32::from(npc.data.level) - 1);
+
+        let magicka_multiplier = self
+            .race_magicka_bonus(race)
+            .mul_add(0.1, self.game_setting("fNPCbaseMagickaMult"));
+        let magicka = intelligence as f32 * magicka_multiplier;
+
+        let fatigue = strength + willpower + agility + endurance;
+
+        let stats = NpcStats {
+            attributes: attributes.map(|value| value.clamp(0, 255) as u8),
+            skills: skills.map(|value| value.clamp(0, 255) as u8),
+            health: health.clamp(0, 65535) as u16,
+            magicka: magicka.clamp(0.0, 65535.0) as u16,
+            fatigue: fatigue.clamp(0, 65535) as u16,
+        };
+
+        Ok(AutoCalcStats {
+            spells: self.select_spells(race, &attributes, &skills),
+            stats,
+        })
+    }
+
+    /// The total magnitude of Fortify Maximum Magicka abilities granted by a race.
+    #[allow(clippy::cast_precision_loss)]
+    fn race_magicka_bonus(&self, race: &Race) -> f32 {
+        race.spells
+            .iter()
+            .filter_map(|id| self.spell(id))
+            .filter(|spell| spell.data.spell_type == SpellType::Ability)
+            .flat_map(|spell| &spell.effects)
+            .filter(|effect| effect.magic_effect == EffectId2::FortifyMagickaMultiplier)
+            .map(|effect| (effect.min_magnitude + effect.max_magnitude) as f32 / 2.0)
+            .sum()
+    }
+
+    /// Select the autocalculated spells an NPC with the given attributes and skills knows.
+    ///
+    /// Spells are considered in load order, using their calculated cost. Each school has a limit,
+    /// and once it is reached a new spell replaces the cheapest selected spell. As in the engine,
+    /// that cheapest spell is not necessarily of the same school.
+    ///
+    #[allow(clippy::cast_precision_loss)]
+    fn select_spells(&self, race: &Race, attributes: &[i32; 8], skills: &[i32; 27]) -> Vec<String> {
+        #[derive(Clone, Debug)]
+        struct SchoolCap {
+            count: i32,
+            limit: i32,
+            min_cost: u32,
+            weakest: Option<String>,
+        }
+
+        let schools = [
+            "Alteration",
+            "Conjuration",
+            "Destruction",
+            "Illusion",
+            "Mysticism",
+            "Restoration",
+        ];
+        let mut caps = schools.map(|school| {
+            #[allow(clippy::cast_possible_truncation)]
+            let limit = self.game_setting(&format!("iAutoSpell{school}Max")) as i32;
+            SchoolCap {
+                count: 0,
+                limit,
+                min_cost: u32::MAX,
+                weakest: None,
+            }
+        });
+
+        let base_magicka = self.game_setting("fNPCbaseMagickaMult") * attributes[1] as f32;
+        let times_can_cast = self.game_setting("iAutoSpellTimesCanCast");
+        let chance_threshold = self.game_setting("fAutoSpellChance");
+
+        let mut selected: Vec<(&Spell, u32)> = vec![];
+
+        for spell in &self.spells {
+            if spell.data.spell_type != SpellType::Spell || !spell.data.flags.contains(SpellFlags::AUTO_CALCULATE) {
+                continue;
+            }
+            let Some(cost) = self.costs.spell_cost(spell) else {
+                continue;
+            };
+            if base_magicka < times_can_cast * cost as f32 {
+                continue;
+            }
+            if race.spells.iter().any(|id| id.eq_ignore_ascii_case(&spell.id)) {
+                continue;
+            }
+            if !self.meets_minimums(spell, attributes, skills) {
+                continue;
+            }
+            let Some((school, skill_term)) = self.weakest_school(spell, skills) else {
+                continue;
+            };
+
+            let cap = &mut caps[school as usize];
+            let reached_limit = cap.count >= cap.limit;
+            if reached_limit && cost <= cap.min_cost {
+                continue;
+            }
+
+            let chance = if spell.data.flags.contains(SpellFlags::ALWAYS_SUCCEEDS) {
+                100.0
+            } else {
+                let willpower = attributes[2] as f32;
+                let luck = attributes[7] as f32;
+                luck.mul_add(0.1, willpower.mul_add(0.2, skill_term - cost as f32))
+            };
+            if chance < chance_threshold {
+                continue;
+            }
+
+            selected.push((spell, cost));
+
+            if reached_limit {
+                if let Some(weakest) = &cap.weakest {
+                    if let Some(position) = selected.iter().position(|(spell, _)| &spell.id == weakest) {
+                        selected.remove(position);
+                    }
+                }
+                cap.min_cost = u32::MAX;
+                cap.weakest = None;
+                for &(spell, cost) in &selected {
+                    if cost < cap.min_cost {
+                        cap.min_cost = cost;
+                        cap.weakest = Some(spell.id.clone());
+                    }
+                }
+            } else {
+                cap.count += 1;
+                if cost < cap.min_cost {
+                    cap.min_cost = cost;
+                    cap.weakest = Some(spell.id.clone());
+                }
+            }
+        }
+
+        selected.into_iter().map(|(spell, _)| spell.id.clone()).collect()
+    }
+
+    /// Whether the attributes and skills targeted by a spell's effects meet the minimum value.
+    fn meets_minimums(&self, spell: &Spell, attributes: &[i32; 8], skills: &[i32; 27]) -> bool {
+        #[allow(clippy::cast_possible_truncation)]
+        let minimum = self.game_setting("iAutoSpellAttSkillMin") as i32;
+        spell.effects.iter().all(|effect| {
+            let Some(data) = self.effect_data(effect) else {
+                return true;
+            };
+            let skill = usize::try_from(effect.skill as i8).ok().and_then(|i| skills.get(i));
+            let attribute = usize::try_from(effect.attribute as i8).ok().and_then(|i| attributes.get(i));
+            !(data.flags.contains(MagicEffectFlags::TARGET_SKILL) && skill.is_some_and(|v| *v < minimum)
+                || data.flags.contains(MagicEffectFlags::TARGET_ATTRIBUTE) && attribute.is_some_and(|v| *v < minimum))
+        })
+    }
+
+    fn effect_data(&self, effect: &Effect) -> Option<&MagicEffectData> {
+        let id = EffectId::try_from(effect.magic_effect as i32).ok()?;
+        self.magic_effects.get(&id)
+    }
+
+    /// Find the school whose skill gives the lowest margin over the cost of any of a spell's
+    /// effects, returning it with twice the NPC's skill in that school.
+    ///
+    /// This uses a cost formula slightly different from the one used for spell costs, matching
+    /// the engine.
+    ///
+    #[allow(clippy::cast_precision_loss)]
+    fn weakest_school(&self, spell: &Spell, skills: &[i32; 27]) -> Option<(EffectSchool, f32)> {
+        let cost_mult = self.game_setting("fEffectCostMult");
+        let mut weakest: Option<(EffectSchool, f32, f32)> = None;
+
+        for effect in &spell.effects {
+            let data = self.effect_data(effect)?;
+
+            let (min, max) = if data.flags.contains(MagicEffectFlags::NO_MAGNITUDE) {
+                (1, 1)
+            } else {
+                (effect.min_magnitude.max(1), effect.max_magnitude.max(1))
+            };
+            let mut duration = if data.flags.contains(MagicEffectFlags::NO_DURATION) {
+                0
+            } else {
+                effect.duration
+            };
+            if !data.flags.contains(MagicEffectFlags::APPLIED_ONCE) {
+                duration = duration.max(1);
+            }
+
+            let mut cost = 0.5 * (min + max) as f32;
+            cost *= 0.1 * data.base_cost;
+            cost *= 1.0 + duration as f32;
+            cost += 0.05 * effect.area.max(1) as f32 * data.base_cost;
+            cost *= cost_mult;
+            if effect.range == EffectRange::OnTarget {
+                cost *= 1.5;
+            }
+
+            let skill_term = 2.0 * skills[school_skill(data.school) as usize] as f32;
+            #[allow(clippy::unnecessary_map_or)] // Option::is_none_or requires Rust 1.82
+            let is_weaker = weakest.map_or(true, |(_, _, margin)| skill_term - cost < margin);
+            if is_weaker {
+                weakest = Some((data.school, skill_term, skill_term - cost));
+            }
+        }
+
+        weakest.map(|(school, skill_term, _)| (school, skill_term))
+    }
+}
+
+fn not_found(kind: &str, id: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{kind} not found: {id}"))
+}
+
+/// The skill used to cast spells of a school.
+const fn school_skill(school: EffectSchool) -> SkillId {
+    match school {
+        EffectSchool::Alteration => SkillId::Alteration,
+        EffectSchool::Conjuration => SkillId::Conjuration,
+        EffectSchool::Destruction => SkillId::Destruction,
+        EffectSchool::Illusion => SkillId::Illusion,
+        EffectSchool::Mysticism => SkillId::Mysticism,
+        EffectSchool::Restoration => SkillId::Restoration,
+    }
+}
+
+const fn major_skills(data: &ClassData) -> [SkillId; 5] {
+    [data.major1, data.major2, data.major3, data.major4, data.major5]
+}
+
+const fn minor_skills(data: &ClassData) -> [SkillId; 5] {
+    [data.minor1, data.minor2, data.minor3, data.minor4, data.minor5]
+}
+
+/// The base attributes of a race, in attribute index order.
+fn race_attributes(data: &RaceData, female: bool) -> [i32; 8] {
+    [
+        data.strength,
+        data.intelligence,
+        data.willpower,
+        data.agility,
+        data.speed,
+        data.endurance,
+        data.personality,
+        data.luck,
+    ]
+    .map(|values| values[usize::from(female)])
+}
+
+const fn race_skill_bonuses(bonuses: &SkillBonuses) -> [(SkillId, i32); 7] {
+    [
+        (bonuses.skill_0, bonuses.bonus_0),
+        (bonuses.skill_1, bonuses.bonus_1),
+        (bonuses.skill_2, bonuses.bonus_2),
+        (bonuses.skill_3, bonuses.bonus_3),
+        (bonuses.skill_4, bonuses.bonus_4),
+        (bonuses.skill_5, bonuses.bonus_5),
+        (bonuses.skill_6, bonuses.bonus_6),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The governing attribute and specialization of each skill, as in the base game.
+    const SKILLS: [(i32, i32); 27] = [
+        (3, 0),
+        (0, 0),
+        (5, 0),
+        (5, 0),
+        (0, 0),
+        (0, 0),
+        (0, 0),
+        (5, 0),
+        (4, 0),
+        (1, 1),
+        (2, 1),
+        (2, 1),
+        (6, 1),
+        (1, 1),
+        (2, 1),
+        (2, 1),
+        (1, 1),
+        (4, 1),
+        (1, 2),
+        (3, 2),
+        (0, 2),
+        (3, 2),
+        (4, 2),
+        (3, 2),
+        (6, 2),
+        (6, 2),
+        (4, 2),
+    ];
+
+    fn plugin() -> Plugin {
+        let mut plugin = Plugin::new();
+        for (index, (governing_attribute, specialization)) in (0..).zip(SKILLS) {
+            plugin.objects.push(
+                Skill {
+                    skill_id: SkillId::try_from(index).unwrap(),
+                    data: SkillData {
+                        governing_attribute,
+                        specialization,
+                        ..default()
+                    },
+                    ..default()
+                }
+                .into(),
+            );
+        }
+        plugin.objects.push(
+            Race {
+                id: "Breton".into(),
+                spells: vec!["magicka_bonus".into()],
+                data: RaceData {
+                    skill_bonuses: SkillBonuses {
+                        skill_0: SkillId::Conjuration,
+                        bonus_0: 10,
+                        ..default()
+                    },
+                    strength: [40, 30],
+                    intelligence: [50, 50],
+                    willpower: [50, 50],
+                    agility: [30, 30],
+                    speed: [30, 40],
+                    endurance: [30, 30],
+                    personality: [40, 40],
+                    luck: [40, 40],
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        plugin.objects.push(
+            Class {
+                id: "Mage".into(),
+                data: ClassData {
+                    attribute1: AttributeId::Intelligence,
+                    attribute2: AttributeId::Willpower,
+                    specialization: Specialization::Magic,
+                    major1: SkillId::Conjuration,
+                    major2: SkillId::Destruction,
+                    major3: SkillId::Alteration,
+                    major4: SkillId::Mysticism,
+                    major5: SkillId::Restoration,
+                    minor1: SkillId::Illusion,
+                    minor2: SkillId::Enchant,
+                    minor3: SkillId::Alchemy,
+                    minor4: SkillId::Unarmored,
+                    minor5: SkillId::ShortBlade,
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        add_spells(&mut plugin);
+        plugin
+    }
+
+    fn add_spells(plugin: &mut Plugin) {
+        plugin.objects.push(
+            MagicEffect {
+                effect_id: EffectId::FireDamage,
+                data: MagicEffectData {
+                    school: EffectSchool::Destruction,
+                    base_cost: 5.0,
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        let fire = |magnitude, duration| Effect {
+            magic_effect: EffectId2::FireDamage,
+            range: EffectRange::OnTarget,
+            min_magnitude: magnitude,
+            max_magnitude: magnitude,
+            duration,
+            ..default()
+        };
+        for (id, spell_type, cost, effects) in [
+            (
+                "magicka_bonus",
+                SpellType::Ability,
+                0,
+                vec![Effect {
+                    magic_effect: EffectId2::FortifyMagickaMultiplier,
+                    min_magnitude: 5,
+                    max_magnitude: 5,
+                    ..default()
+                }],
+            ),
+            ("fire_bite", SpellType::Spell, 8, vec![fire(7, 3)]),
+            ("fireball", SpellType::Spell, 80, vec![fire(71, 3)]),
+            // the stored cost is wrong, and ignored in favor of the calculated cost of 80
+            ("cheap_fireball", SpellType::Spell, 1, vec![fire(71, 3)]),
+        ] {
+            plugin.objects.push(
+                Spell {
+                    id: id.into(),
+                    effects,
+                    data: SpellData {
+                        spell_type,
+                        cost,
+                        flags: SpellFlags::AUTO_CALCULATE,
+                    },
+                    ..default()
+                }
+                .into(),
+            );
+        }
+    }
+
+    #[test]
+    fn calculate_npc_stats() -> io::Result<()> {
+        let plugin = plugin();
+        let autocalc = NpcAutoCalc::from_plugins([&plugin]);
+
+        let mut npc = Npc {
+            race: "breton".into(),
+            class: "mage".into(),
+            npc_flags: NpcFlags::FEMALE | NpcFlags::AUTO_CALCULATE,
+            ..default()
+        };
+        npc.data.level = 1;
+
+        let AutoCalcStats { stats, spells } = autocalc.calculate(&npc)?;
+        assert_eq!(stats.attributes, [30, 60, 60, 30, 40, 30, 40, 40]);
+        // Conjuration: 5 base, 25 major, 10 racial, 5 specialization.
+        assert_eq!(stats.skills[SkillId::Conjuration as usize], 45);
+        // Short Blade: 5 base, 10 minor, no specialization.
+        assert_eq!(stats.skills[SkillId::ShortBlade as usize], 15);
+        assert_eq!(stats.skills[SkillId::Block as usize], 5);
+        assert_eq!((stats.health, stats.magicka, stats.fatigue), (30, 150, 150));
+        // Fire Bite's cast chance is 70 - 8 + 12 + 4, below 80.
+        assert!(spells.is_empty());
+
+        npc.data.level = 20;
+        let AutoCalcStats { stats, spells } = autocalc.calculate(&npc)?;
+        // Willpower governs Destruction, Alteration, Mysticism and Restoration (majors), so it
+        // grows by 4 per level, on top of the class bonus.
+        assert_eq!(stats.attributes[2], 100);
+        // Destruction: 35 + 19 * 1.5.
+        assert_eq!(stats.skills[SkillId::Destruction as usize], 64);
+        // Health: 3 per level for a magic class.
+        assert_eq!(stats.health, u16::from(stats.attributes[0] + stats.attributes[5]) / 2 + 57);
+        // Fireball costs more than a third of the base magicka of 200.
+        assert_eq!(spells, ["fire_bite"]);
+
+        npc.race = "missing".into();
+        assert!(autocalc.calculate(&npc).is_err());
+
+        Ok(())
+    }
+}
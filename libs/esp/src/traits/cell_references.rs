@@ -0,0 +1,241 @@
+use crate::prelude::*;
+
+/// The largest object index that fits in the packed `FRMR` indices.
+const MAX_REFR_INDEX: u32 = 0xFFFFFF;
+
+impl Plugin {
+    pub fn get_cell(&self, id: &CellId) -> Option<&Cell> {
+        self.objects_of_type::<Cell>().find(|cell| cell.matches_id(id))
+    }
+
+    pub fn get_cell_mut(&mut self, id: &CellId) -> Option<&mut Cell> {
+        self.objects_of_type_mut::<Cell>().find(|cell| cell.matches_id(id))
+    }
+
+    /// Returns an unused object index for a new plugin-defined reference.
+    ///
+    /// Object indices of plugin-defined references must be unique across all cells.
+    ///
+    pub fn next_refr_index(&self) -> io::Result<u32> {
+        let last_index = self
+            .objects_of_type::<Cell>()
+            .flat_map(|cell| cell.references.keys())
+            .filter_map(|&(mast_index, refr_index)| (mast_index == 0).then_some(refr_index))
+            .max()
+            .unwrap_or(0);
+
+        if last_index >= MAX_REFR_INDEX {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "too many references"));
+        }
+
+        Ok(last_index + 1)
+    }
+
+    /// Move a reference from the cell `from` into the cell `to`.
+    ///
+    /// The `from` cell is the cell whose record contains the reference. Both cells must exist in
+    /// the plugin. The translation of the reference is left unchanged.
+    ///
+    /// - Plugin-defined references are simply relocated to the new cell.
+    /// - Master-defined references moved between exterior cells stay in their original cell and
+    ///   are marked with the new cell, which is saved as `MVRF`/`CNDT`.
+    /// - Master-defined references moved to or from an interior cell can not be marked as moved.
+    ///   The original is replaced by a deleted stub and a new plugin-defined copy is created.
+    ///
+    /// Returns the indices of the reference in its new location.
+    ///
+    pub fn move_reference(&mut self, from: &CellId, indices: (u32, u32), to: &CellId) -> io::Result<(u32, u32)> {
+        self.expect_cell(to)?;
+
+        let source = self.expect_cell_mut(from)?;
+        let source_coords = source.exterior_coords();
+        let is_same_cell = source.matches_id(to);
+
+        let Some(reference) = source.references.get_mut(&indices) else {
+            return Err(reference_not_found(from, indices));
+        };
+
+        if reference.mast_index == 0 {
+            let Some(mut reference) = source.references.remove(&indices) else {
+                unreachable!()
+            };
+            reference.moved_cell = None;
+            self.expect_cell_mut(to)?.references.insert(indices, reference);
+            return Ok(indices);
+        }
+
+        if let &CellId::Exterior(x, y) = to {
+            if source_coords.is_some() {
+                let is_original_cell = source_coords == Some((x, y));
+                reference.moved_cell = if is_original_cell { None } else { Some((x, y)) };
+                return Ok(indices);
+            }
+        } else if is_same_cell {
+            return Ok(indices);
+        }
+
+        let mut reference = reference.clone();
+        reference.moved_cell = None;
+        self.delete_reference(from, indices)?;
+        self.insert_new_reference(to, reference)
+    }
+
+    /// Duplicate a reference of the cell `from` into the cell `to`.
+    ///
+    /// The copy is always a plugin-defined reference with a newly allocated object index.
+    ///
+    /// Returns the indices of the new reference.
+    ///
+    pub fn duplicate_reference(&mut self, from: &CellId, indices: (u32, u32), to: &CellId) -> io::Result<(u32, u32)> {
+        self.expect_cell(to)?;
+
+        let Some(reference) = self.expect_cell(from)?.references.get(&indices) else {
+            return Err(reference_not_found(from, indices));
+        };
+
+        let mut reference = reference.clone();
+        reference.moved_cell = None;
+        reference.deleted = None;
+
+        self.insert_new_reference(to, reference)
+    }
+
+    /// Delete a reference of the cell `from`.
+    ///
+    /// Plugin-defined references are removed outright. Master-defined references are replaced by
+    /// a deleted stub, which is required for the deletion to take effect in-game.
+    ///
+    /// Returns the reference as it was before deletion.
+    ///
+    pub fn delete_reference(&mut self, from: &CellId, indices: (u32, u32)) -> io::Result<Reference> {
+        let cell = self.expect_cell_mut(from)?;
+
+        let Some(reference) = cell.references.get_mut(&indices) else {
+            return Err(reference_not_found(from, indices));
+        };
+
+        if reference.mast_index == 0 {
+            let Some(reference) = cell.references.remove(&indices) else {
+                unreachable!()
+            };
+            return Ok(reference);
+        }
+
+        let stub = Reference {
+            mast_index: reference.mast_index,
+            refr_index: reference.refr_index,
+            id: reference.id.clone(),
+            temporary: reference.temporary,
+            deleted: Some(true),
+            ..default()
+        };
+
+        Ok(std::mem::replace(reference, stub))
+    }
+
+    fn insert_new_reference(&mut self, to: &CellId, mut reference: Reference) -> io::Result<(u32, u32)> {
+        let indices = (0, self.next_refr_index()?);
+
+        reference.mast_index = indices.0;
+        reference.refr_index = indices.1;
+
+        self.expect_cell_mut(to)?.references.insert(indices, reference);
+
+        Ok(indices)
+    }
+
+    fn expect_cell(&self, id: &CellId) -> io::Result<&Cell> {
+        self.get_cell(id).ok_or_else(|| cell_not_found(id))
+    }
+
+    fn expect_cell_mut(&mut self, id: &CellId) -> io::Result<&mut Cell> {
+        self.get_cell_mut(id).ok_or_else(|| cell_not_found(id))
+    }
+}
+
+fn cell_not_found(id: &CellId) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("Unable to find cell {id:?}"))
+}
+
+fn reference_not_found(id: &CellId, indices: (u32, u32)) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Unable to find reference {indices:?} in cell {id:?}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exterior(grid: (i32, i32), references: &[(u32, u32)]) -> TES3Object {
+        let references = references
+            .iter()
+            .map(|&(mast_index, refr_index)| {
+                let reference = Reference {
+                    mast_index,
+                    refr_index,
+                    id: "furn_de_table_01".into(),
+                    temporary: true,
+                    ..default()
+                };
+                ((mast_index, refr_index), reference)
+            })
+            .collect();
+        Cell {
+            data: CellData { grid, ..default() },
+            references,
+            ..default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn move_master_reference() -> io::Result<()> {
+        let mut plugin = Plugin {
+            objects: vec![exterior((0, 0), &[(1, 7)]), exterior((1, 0), &[])],
+        };
+        let (a, b) = (CellId::Exterior(0, 0), CellId::Exterior(1, 0));
+
+        assert_eq!(plugin.move_reference(&a, (1, 7), &b)?, (1, 7));
+        let reference = &plugin.get_cell(&a).unwrap().references[&(1, 7)];
+        assert_eq!(reference.moved_cell, Some((1, 0)));
+        assert!(reference.persistent());
+
+        // moving into a cell without a record is an error
+        assert!(plugin.move_reference(&a, (1, 7), &CellId::Exterior(5, 5)).is_err());
+
+        // moving back restores the original state
+        plugin.move_reference(&a, (1, 7), &a)?;
+        assert_eq!(plugin.get_cell(&a).unwrap().references[&(1, 7)].moved_cell, None);
+
+        // interior cell names are compared ignoring case
+        let mut interior: Cell = exterior((0, 0), &[(1, 8)]).try_into().unwrap();
+        interior.name = "Balmora".into();
+        interior.data.flags = CellFlags::IS_INTERIOR;
+        plugin.objects.push(interior.into());
+        let (from, to) = (CellId::Interior("Balmora".into()), CellId::Interior("balmora".into()));
+        assert_eq!(plugin.move_reference(&from, (1, 8), &to)?, (1, 8));
+        assert!(!plugin.get_cell(&to).unwrap().references[&(1, 8)].deleted());
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_and_delete() -> io::Result<()> {
+        let mut plugin = Plugin {
+            objects: vec![exterior((0, 0), &[(1, 7), (0, 3)])],
+        };
+        let a = CellId::Exterior(0, 0);
+
+        assert_eq!(plugin.duplicate_reference(&a, (1, 7), &a)?, (0, 4));
+        assert_eq!(plugin.next_refr_index()?, 5);
+
+        plugin.delete_reference(&a, (0, 4))?;
+        plugin.delete_reference(&a, (1, 7))?;
+
+        let references = &plugin.get_cell(&a).unwrap().references;
+        assert!(!references.contains_key(&(0, 4)));
+        assert!(references[&(1, 7)].deleted());
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+use crate::prelude::*;
+
+/// Provides the game state that dialogue conditions and filters are evaluated against.
+///
+/// The "speaker" is the actor the player is talking to. Ids are compared ignoring case.
+///
+pub trait GameState {
+    fn speaker_id(&self) -> &str;
+    fn speaker_race(&self) -> &str;
+    fn speaker_class(&self) -> &str;
+    fn speaker_sex(&self) -> Sex;
+    fn speaker_cell(&self) -> &str;
+    fn speaker_disposition(&self) -> i32;
+
+    /// The faction of the speaker and their rank in it, or `None` if they are not in a faction.
+    fn speaker_faction(&self) -> Option<(&str, i8)>;
+
+    /// The rank of the player in the given faction, or `None` if they are not a member.
+    fn player_rank(&self, faction: &str) -> Option<i8>;
+
+    /// The value of a [`FilterType::Function`] filter function.
+    ///
+    /// Boolean functions such as [`FilterFunction::SameRace`] return `1.0` or `0.0`.
+    ///
+    fn function(&self, function: FilterFunction) -> f32;
+
+    /// The value of a global variable, or `None` if it does not exist.
+    fn global(&self, id: &str) -> Option<f32>;
+
+    /// The value of a local variable of the speaker's script, or `None` if it does not exist.
+    fn local(&self, id: &str) -> Option<f32>;
+
+    fn journal_index(&self, quest_id: &str) -> i32;
+
+    /// The number of items with the given id carried by the player.
+    fn item_count(&self, id: &str) -> i32;
+
+    /// The number of dead actors with the given id.
+    fn dead_count(&self, id: &str) -> i32;
+}
+
+impl FilterComparison {
+    pub fn compare(self, a: f32, b: f32) -> bool {
+        #[allow(clippy::float_cmp)]
+        match self {
+            Self::Equal => a == b,
+            Self::NotEqual => a != b,
+            Self::Greater => a > b,
+            Self::GreaterEqual => a >= b,
+            Self::Less => a < b,
+            Self::LessEqual => a <= b,
+        }
+    }
+}
+
+impl FilterValue {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f32(self) -> f32 {
+        match self {
+            Self::Float(value) => value,
+            Self::Integer(value) => value as f32,
+        }
+    }
+}
+
+impl Filter {
+    /// Evaluate this filter against the given game state.
+    ///
+    /// As in the engine, the `Not*` filter types are satisfied by the speaker not matching the
+    /// filter id, and ignore their comparison and value. Comparisons against variables which do
+    /// not exist always fail.
+    ///
+    #[allow(clippy::cast_precision_loss)]
+    pub fn evaluate(&self, state: &impl GameState) -> bool {
+        let id = self.id.as_str();
+
+        let value = match self.filter_type {
+            FilterType::None => return true,
+            FilterType::Function => state.function(self.function),
+            FilterType::Global => match state.global(id) {
+                Some(value) => value,
+                None => return false,
+            },
+            FilterType::Local => match state.local(id) {
+                Some(value) => value,
+                None => return false,
+            },
+            FilterType::Journal => state.journal_index(id) as f32,
+            FilterType::Item => state.item_count(id) as f32,
+            FilterType::Dead => state.dead_count(id) as f32,
+            FilterType::NotId => return !state.speaker_id().eq_ignore_ascii_case(id),
+            FilterType::NotFaction => {
+                return !state
+                    .speaker_faction()
+                    .is_some_and(|(faction, _)| faction.eq_ignore_ascii_case(id));
+            }
+            FilterType::NotClass => return !state.speaker_class().eq_ignore_ascii_case(id),
+            FilterType::NotRace => return !state.speaker_race().eq_ignore_ascii_case(id),
+            FilterType::NotCell => return !starts_with_ignore_case(state.speaker_cell(), id),
+            FilterType::NotLocal => return state.local(id).is_none(),
+        };
+
+        self.comparison.compare(value, self.value.as_f32())
+    }
+}
+
+impl DialogueInfo {
+    /// Check whether this info's speaker conditions and filters are satisfied by the game state.
+    ///
+    /// A speaker faction of "FFFF" requires the speaker to not be in any faction, and a speaker
+    /// cell matches any cell whose name starts with it. Disposition is only checked for infos of
+    /// [`DialogueType::Topic`], [`DialogueType::Greeting`] and [`DialogueType::Persuasion`].
+    ///
+    pub fn evaluate(&self, state: &impl GameState) -> bool {
+        self.evaluate_speaker(state) && self.evaluate_player(state) && self.filters.iter().all(|f| f.evaluate(state))
+    }
+
+    fn evaluate_speaker(&self, state: &impl GameState) -> bool {
+        let matches = |condition: &str, value: &str| condition.is_empty() || condition.eq_ignore_ascii_case(value);
+
+        if !(matches(&self.speaker_id, state.speaker_id())
+            && matches(&self.speaker_race, state.speaker_race())
+            && matches(&self.speaker_class, state.speaker_class()))
+        {
+            return false;
+        }
+
+        if self.data.speaker_sex != Sex::Any && self.data.speaker_sex != state.speaker_sex() {
+            return false;
+        }
+
+        if !starts_with_ignore_case(state.speaker_cell(), &self.speaker_cell) {
+            return false;
+        }
+
+        let speaker_faction = state.speaker_faction();
+
+        if self.speaker_faction.eq_ignore_ascii_case("FFFF") {
+            if speaker_faction.is_some() {
+                return false;
+            }
+        } else if !self.speaker_faction.is_empty()
+            && !speaker_faction.is_some_and(|(faction, _)| faction.eq_ignore_ascii_case(&self.speaker_faction))
+        {
+            return false;
+        }
+
+        if self.data.speaker_rank != -1 {
+            match speaker_faction {
+                Some((_, rank)) if rank >= self.data.speaker_rank => {}
+                _ => return false,
+            }
+        }
+
+        let uses_disposition = matches!(
+            self.data.dialogue_type,
+            DialogueType::Topic | DialogueType::Greeting | DialogueType::Persuasion
+        );
+        !uses_disposition || state.speaker_disposition() >= self.data.disposition
+    }
+
+    fn evaluate_player(&self, state: &impl GameState) -> bool {
+        // Without an explicit player faction, the player rank refers to the speaker's faction.
+        let faction = if self.player_faction.is_empty() {
+            if self.data.player_rank == -1 {
+                return true;
+            }
+            match state.speaker_faction() {
+                Some((faction, _)) => faction,
+                None => return false,
+            }
+        } else {
+            &self.player_faction
+        };
+
+        state
+            .player_rank(faction)
+            .is_some_and(|rank| self.data.player_rank == -1 || rank >= self.data.player_rank)
+    }
+}
+
+impl Topic {
+    /// Find the first info of this topic whose conditions are satisfied by the game state.
+    ///
+    /// The topic is expected to contain all of its infos in their effective order, such as a
+    /// topic from [`DialogueMerge`].
+    ///
+    pub fn select_info(&self, state: &impl GameState) -> Option<&DialogueInfo> {
+        self.infos
+            .iter()
+            .filter(|info| !info.deleted())
+            .find(|info| info.evaluate(state))
+    }
+}
+
+fn starts_with_ignore_case(value: &str, prefix: &str) -> bool {
+    value
+        .as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct State {
+        faction: Option<(&'static str, i8)>,
+        globals: HashMap<&'static str, f32>,
+        journal: HashMap<&'static str, i32>,
+        items: HashMap<&'static str, i32>,
+        level: f32,
+    }
+
+    impl GameState for State {
+        fn speaker_id(&self) -> &'static str {
+            "fargoth"
+        }
+        fn speaker_race(&self) -> &'static str {
+            "Wood Elf"
+        }
+        fn speaker_class(&self) -> &'static str {
+            "Commoner"
+        }
+        fn speaker_sex(&self) -> Sex {
+            Sex::Male
+        }
+        fn speaker_cell(&self) -> &'static str {
+            "Seyda Neen, Arrille's Tradehouse"
+        }
+        fn speaker_disposition(&self) -> i32 {
+            50
+        }
+        fn speaker_faction(&self) -> Option<(&str, i8)> {
+            self.faction
+        }
+        fn player_rank(&self, _: &str) -> Option<i8> {
+            None
+        }
+        fn function(&self, function: FilterFunction) -> f32 {
+            match function {
+                FilterFunction::PcLevel => self.level,
+                _ => 0.0,
+            }
+        }
+        fn global(&self, id: &str) -> Option<f32> {
+            self.globals.get(id).copied()
+        }
+        fn local(&self, _: &str) -> Option<f32> {
+            None
+        }
+        fn journal_index(&self, quest_id: &str) -> i32 {
+            self.journal.get(quest_id).copied().unwrap_or(0)
+        }
+        fn item_count(&self, id: &str) -> i32 {
+            self.items.get(id).copied().unwrap_or(0)
+        }
+        fn dead_count(&self, _: &str) -> i32 {
+            0
+        }
+    }
+
+    fn filter(filter_type: FilterType, comparison: FilterComparison, id: &str, value: i32) -> Filter {
+        Filter {
+            filter_type,
+            function: FilterFunction::PcLevel,
+            comparison,
+            id: id.into(),
+            value: FilterValue::Integer(value),
+            ..default()
+        }
+    }
+
+    fn info(id: &str, speaker_cell: &str, filters: Vec<Filter>) -> DialogueInfo {
+        DialogueInfo {
+            id: id.into(),
+            speaker_cell: speaker_cell.into(),
+            data: DialogueData {
+                speaker_rank: -1,
+                player_rank: -1,
+                ..default()
+            },
+            filters,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn select_info() {
+        let mut topic = Topic::default();
+        topic.infos.push(info(
+            "journal",
+            "",
+            vec![filter(FilterType::Journal, FilterComparison::GreaterEqual, "A1_1", 10)],
+        ));
+        topic.infos.push(info(
+            "gold",
+            "Balmora",
+            vec![filter(FilterType::Item, FilterComparison::Greater, "Gold_001", 100)],
+        ));
+        topic.infos.push(info(
+            "level",
+            "seyda neen",
+            vec![
+                filter(FilterType::Function, FilterComparison::GreaterEqual, "", 5),
+                filter(FilterType::NotLocal, FilterComparison::Equal, "nolore", 0),
+            ],
+        ));
+        topic.infos.push(info("fallback", "", vec![]));
+
+        let mut state = State { level: 5.0, ..default() };
+        assert_eq!(topic.select_info(&state).unwrap().id, "level");
+
+        state.items.insert("Gold_001", 500);
+        state.level = 1.0;
+        assert_eq!(topic.select_info(&state).unwrap().id, "fallback");
+
+        state.journal.insert("A1_1", 10);
+        assert_eq!(topic.select_info(&state).unwrap().id, "journal");
+
+        let mut faction_info = info("faction", "", vec![]);
+        faction_info.speaker_faction = "FFFF".into();
+        assert!(faction_info.evaluate(&state));
+        state.faction = Some(("Hlaalu", 2));
+        assert!(!faction_info.evaluate(&state));
+
+        let global = filter(FilterType::Global, FilterComparison::Equal, "missing", 0);
+        assert!(!global.evaluate(&state));
+        state.globals.insert("missing", 0.0);
+        assert!(global.evaluate(&state));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::prelude::*;
+
+/// An info whose `prev_id` and `next_id` anchors could not be found in the merged topic.
+///
+/// Such infos are placed at the end of the topic, matching the behavior of the engine.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingAnchor {
+    pub topic_id: String,
+    pub info_id: String,
+    pub prev_id: String,
+    pub next_id: String,
+    /// The index of the plugin which defined the info, in load order.
+    pub plugin_index: usize,
+}
+
+/// A topic in its effective order after loading all plugins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergedTopic {
+    pub topic: Topic,
+    /// The indices of the plugins which modified this topic, in load order.
+    pub plugin_indices: Vec<usize>,
+}
+
+/// The effective dialogue of a load ordered set of plugins.
+///
+/// Infos are spliced into their topics the same way the engine does: a new info is inserted
+/// after its `prev_id`, or before its `next_id` when the previous info can not be found. An info
+/// that overrides an existing info keeps its place unless its `prev_id` changed, in which case it
+/// is moved. Deleted infos and topics are removed.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DialogueMerge {
+    pub topics: Vec<MergedTopic>,
+    pub missing_anchors: Vec<MissingAnchor>,
+}
+
+impl DialogueMerge {
+    /// Merge the dialogue of the given plugins, which must be provided in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::default();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for (plugin_index, plugin) in plugins.into_iter().enumerate() {
+            for topic in plugin.topics() {
+                let key = topic.id().to_ascii_lowercase();
+
+                if topic.dialogue.deleted() {
+                    if let Some(position) = positions.remove(&key) {
+                        this.topics.remove(position);
+                        positions.values_mut().filter(|i| **i > position).for_each(|i| *i -= 1);
+                    }
+                    continue;
+                }
+
+                let position = *positions.entry(key).or_insert_with(|| {
+                    this.topics.push(default());
+                    this.topics.len() - 1
+                });
+
+                let merged = &mut this.topics[position];
+                merged.topic.dialogue = topic.dialogue;
+                merged.plugin_indices.push(plugin_index);
+
+                for info in topic.infos {
+                    if let Some(missing) = merged.splice(info, plugin_index) {
+                        this.missing_anchors.push(missing);
+                    }
+                }
+            }
+        }
+
+        for merged in &mut this.topics {
+            merged.topic.relink();
+        }
+
+        this
+    }
+
+    /// Get the merged topic with the given id, ignoring case.
+    pub fn get_topic(&self, id: &str) -> Option<&Topic> {
+        self.topics
+            .iter()
+            .map(|merged| &merged.topic)
+            .find(|topic| topic.id().eq_ignore_ascii_case(id))
+    }
+
+    /// Create a patch plugin containing every topic that was modified by more than one plugin.
+    ///
+    /// The infos of each topic are linked in their effective order, so that loading the patch
+    /// after all merged plugins preserves the insertion order intended by each of them. Master
+    /// files are not filled in and should be set by the caller.
+    ///
+    pub fn to_patch(&self) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(Header::default().into());
+
+        for merged in &self.topics {
+            if merged.plugin_indices.len() > 1 {
+                plugin.objects.push(merged.topic.dialogue.clone().into());
+                plugin.objects.extend(merged.topic.infos.iter().cloned().map(Into::into));
+            }
+        }
+
+        plugin
+    }
+}
+
+impl MergedTopic {
+    /// Splice a loaded info into the topic, returning the info if its anchors are missing.
+    fn splice(&mut self, info: DialogueInfo, plugin_index: usize) -> Option<MissingAnchor> {
+        let infos = &mut self.topic.infos;
+
+        if let Some(i) = infos.iter().position(|other| other.id == info.id) {
+            if info.deleted() {
+                infos.remove(i);
+                return None;
+            }
+            if infos[i].prev_id == info.prev_id {
+                infos[i] = info;
+                return None;
+            }
+            infos.remove(i);
+        } else if info.deleted() {
+            return None;
+        }
+
+        let position = |id: &str| {
+            if id.is_empty() {
+                None
+            } else {
+                infos.iter().position(|other| other.id == id)
+            }
+        };
+
+        let (index, missing) = if let Some(i) = position(&info.prev_id) {
+            (i + 1, false)
+        } else if let Some(i) = position(&info.next_id) {
+            (i, false)
+        } else if info.prev_id.is_empty() {
+            (0, false)
+        } else {
+            (infos.len(), true)
+        };
+
+        let missing = missing.then(|| MissingAnchor {
+            topic_id: self.topic.dialogue.id.clone(),
+            info_id: info.id.clone(),
+            prev_id: info.prev_id.clone(),
+            next_id: info.next_id.clone(),
+            plugin_index,
+        });
+
+        infos.insert(index, info);
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(topic_id: &str, infos: &[(&str, &str, &str)]) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            Dialogue {
+                id: topic_id.into(),
+                ..default()
+            }
+            .into(),
+        );
+        for &(prev_id, id, next_id) in infos {
+            plugin.objects.push(
+                DialogueInfo {
+                    id: id.into(),
+                    prev_id: prev_id.into(),
+                    next_id: next_id.into(),
+                    ..default()
+                }
+                .into(),
+            );
+        }
+        plugin
+    }
+
+    fn order(merge: &DialogueMerge) -> Vec<&str> {
+        let topic = merge.get_topic("background").unwrap();
+        topic.infos.iter().map(|info| info.id.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_insertion_order() {
+        let master = plugin("Background", &[("", "a", "b"), ("a", "b", "c"), ("b", "c", "")]);
+        let mod_1 = plugin("Background", &[("a", "x", "b")]);
+        let mod_2 = plugin("background", &[("", "y", "x"), ("y", "z", "missing")]);
+        let mod_3 = plugin("background", &[("unknown", "w", "unknown_2"), ("b", "c", "")]);
+
+        let merge = DialogueMerge::from_plugins([&master, &mod_1]);
+        assert_eq!(order(&merge), ["a", "x", "b", "c"]);
+
+        let merge = DialogueMerge::from_plugins([&master, &mod_1, &mod_2, &mod_3]);
+        assert_eq!(order(&merge), ["a", "y", "z", "x", "b", "c", "w"]);
+        assert_eq!(merge.topics[0].plugin_indices, [0, 1, 2, 3]);
+        assert_eq!(merge.missing_anchors.len(), 1);
+        assert_eq!(merge.missing_anchors[0].info_id, "w");
+
+        // loading the patch last must reproduce the merged order
+        let patch = merge.to_patch();
+        let remerge = DialogueMerge::from_plugins([&master, &mod_3, &mod_1, &patch]);
+        assert_eq!(order(&remerge), order(&merge));
+    }
+}
@@ -0,0 +1,335 @@
+// rust std imports
+use std::fmt;
+use std::str::FromStr;
+
+// internal imports
+use crate::prelude::*;
+
+/// The filter types that have a display name, in the order used by the Construction Set.
+const FILTER_TYPES: [FilterType; 12] = [
+    FilterType::Function,
+    FilterType::Global,
+    FilterType::Local,
+    FilterType::Journal,
+    FilterType::Item,
+    FilterType::Dead,
+    FilterType::NotId,
+    FilterType::NotFaction,
+    FilterType::NotClass,
+    FilterType::NotRace,
+    FilterType::NotCell,
+    FilterType::NotLocal,
+];
+
+/// Functions which evaluate to either `0` or `1`.
+const BOOLEAN_FUNCTIONS: [FilterFunction; 17] = [
+    FilterFunction::PcSex,
+    FilterFunction::PcExpelled,
+    FilterFunction::PcCommonDisease,
+    FilterFunction::PcBlightDisease,
+    FilterFunction::SameSex,
+    FilterFunction::SameRace,
+    FilterFunction::SameFaction,
+    FilterFunction::Detected,
+    FilterFunction::Alarmed,
+    FilterFunction::PcCorprus,
+    FilterFunction::PcVampire,
+    FilterFunction::Attacked,
+    FilterFunction::TalkedToPc,
+    FilterFunction::CreatureTarget,
+    FilterFunction::FriendHit,
+    FilterFunction::ShouldAttack,
+    FilterFunction::Werewolf,
+];
+
+impl FilterType {
+    /// The name of this filter type, as shown by the Construction Set.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Function => "Function",
+            Self::Global => "Global",
+            Self::Local => "Local",
+            Self::Journal => "Journal",
+            Self::Item => "Item",
+            Self::Dead => "Dead",
+            Self::NotId => "Not ID",
+            Self::NotFaction => "Not Faction",
+            Self::NotClass => "Not Class",
+            Self::NotRace => "Not Race",
+            Self::NotCell => "Not Cell",
+            Self::NotLocal => "Not Local",
+        }
+    }
+
+    /// Whether this filter type only checks that the speaker does not match the filter id.
+    pub const fn is_inverted(self) -> bool {
+        matches!(
+            self,
+            Self::NotId | Self::NotFaction | Self::NotClass | Self::NotRace | Self::NotCell | Self::NotLocal
+        )
+    }
+}
+
+impl FilterFunction {
+    /// All functions usable by [`FilterType::Function`] filters.
+    pub fn functions() -> impl Iterator<Item = Self> {
+        (0..=73u8).filter_map(|i| u16::from_le_bytes([b'0' + i / 10, b'0' + i % 10]).try_into().ok())
+    }
+
+    /// Whether this function is usable by [`FilterType::Function`] filters.
+    pub const fn is_function(self) -> bool {
+        let [a, b] = (self as u16).to_le_bytes();
+        a.is_ascii_digit() && b.is_ascii_digit()
+    }
+
+    /// The name of this function, as shown by the Construction Set (e.g. "PC Level").
+    pub fn name(self) -> String {
+        let mut name = String::new();
+        for (i, c) in self.display().char_indices() {
+            if i > 0 && c.is_ascii_uppercase() {
+                name.push(' ');
+            }
+            name.push(c);
+        }
+        name.split(' ')
+            .map(|word| if word == "Pc" { "PC" } else { word })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl FilterComparison {
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+        }
+    }
+}
+
+impl fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Float(value) => write!(f, "{value:?}"),
+            Self::Integer(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl FromStr for FilterValue {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(value) = s.parse() {
+            return Ok(Self::Integer(value));
+        }
+        s.parse()
+            .map(Self::Float)
+            .map_err(|_| invalid_filter(format!("Invalid filter value: {s}")))
+    }
+}
+
+impl fmt::Display for Filter {
+    /// Format the filter in Construction Set notation, such as `Function PC Level >= 5` or
+    /// `Item Gold_001 > 100`. The filter index is not included.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.filter_type.name())?;
+        if self.filter_type == FilterType::Function {
+            write!(f, "{} ", self.function.name())?;
+        } else {
+            write!(f, "{} ", self.id)?;
+        }
+        write!(f, "{} {}", self.comparison.symbol(), self.value)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = io::Error;
+
+    /// Parse a filter in Construction Set notation, as produced by [`Filter`]'s `Display`.
+    ///
+    /// The filter index is left at zero. Global and local variable filters are assumed to refer
+    /// to float variables when given a float value, and long variables otherwise.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (filter_type, rest) = FILTER_TYPES
+            .into_iter()
+            .find_map(|filter_type| Some((filter_type, strip_word(s, filter_type.name())?)))
+            .ok_or_else(|| invalid_filter(format!("Unknown filter type: {s}")))?;
+
+        let mut parts = rest.rsplitn(3, ' ');
+        let (Some(value), Some(symbol), Some(name)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid_filter(format!("Incomplete filter: {s}")));
+        };
+
+        let comparison = [
+            FilterComparison::Equal,
+            FilterComparison::NotEqual,
+            FilterComparison::Greater,
+            FilterComparison::GreaterEqual,
+            FilterComparison::Less,
+            FilterComparison::LessEqual,
+        ]
+        .into_iter()
+        .find(|comparison| comparison.symbol() == symbol)
+        .ok_or_else(|| invalid_filter(format!("Unknown comparison: {symbol}")))?;
+
+        let value: FilterValue = value.parse()?;
+        let name = name.trim();
+
+        let (function, id) = match filter_type {
+            FilterType::Function => {
+                let function = FilterFunction::functions()
+                    .find(|function| function.name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| invalid_filter(format!("Unknown filter function: {name}")))?;
+                (function, String::new())
+            }
+            FilterType::Global | FilterType::Local | FilterType::NotLocal => match value {
+                FilterValue::Float(_) => (FilterFunction::Global, name.into()),
+                FilterValue::Integer(_) => (FilterFunction::PcGold, name.into()),
+            },
+            _ => (expected_function(filter_type).unwrap_or_default(), name.into()),
+        };
+
+        let filter = Self {
+            index: 0,
+            filter_type,
+            function,
+            comparison,
+            id,
+            value,
+        };
+        filter.validate()?;
+
+        Ok(filter)
+    }
+}
+
+impl Filter {
+    /// Check that the function, comparison and value of this filter are valid for its type.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.index > 5 {
+            return Err(invalid_filter(format!("Invalid filter index: {}", self.index)));
+        }
+
+        let valid_function = match self.filter_type {
+            FilterType::None => true,
+            FilterType::Function => self.function.is_function(),
+            FilterType::Global | FilterType::Local | FilterType::NotLocal => matches!(
+                self.function,
+                FilterFunction::Global | FilterFunction::PcGold | FilterFunction::VariableCompare
+            ),
+            filter_type => expected_function(filter_type) == Some(self.function),
+        };
+        if !valid_function {
+            return Err(invalid_filter(format!(
+                "Invalid function for {} filter: {}",
+                self.filter_type.name(),
+                self.function.display()
+            )));
+        }
+
+        let requires_id = !matches!(self.filter_type, FilterType::None | FilterType::Function);
+        if requires_id == self.id.is_empty() {
+            return Err(invalid_filter(format!(
+                "Invalid id for {} filter: {:?}",
+                self.filter_type.name(),
+                self.id
+            )));
+        }
+
+        let accepts_float = matches!(self.filter_type, FilterType::Global | FilterType::Local);
+        if !accepts_float && matches!(self.value, FilterValue::Float(_)) {
+            return Err(invalid_filter(format!(
+                "{} filters require an integer value: {self}",
+                self.filter_type.name()
+            )));
+        }
+
+        let boolean = self.filter_type.is_inverted()
+            || (self.filter_type == FilterType::Function && BOOLEAN_FUNCTIONS.contains(&self.function));
+        if boolean {
+            let valid_comparison = if self.filter_type.is_inverted() {
+                self.comparison == FilterComparison::Equal
+            } else {
+                matches!(self.comparison, FilterComparison::Equal | FilterComparison::NotEqual)
+            };
+            if !valid_comparison || !matches!(self.value, FilterValue::Integer(0 | 1)) {
+                return Err(invalid_filter(format!("Invalid comparison for boolean filter: {self}")));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The function used by filter types other than functions and variables.
+const fn expected_function(filter_type: FilterType) -> Option<FilterFunction> {
+    match filter_type {
+        FilterType::Journal => Some(FilterFunction::JournalType),
+        FilterType::Item => Some(FilterFunction::ItemType),
+        FilterType::Dead => Some(FilterFunction::DeadType),
+        FilterType::NotId => Some(FilterFunction::NotIdType),
+        FilterType::NotFaction => Some(FilterFunction::NotFaction),
+        FilterType::NotClass => Some(FilterFunction::NotClass),
+        FilterType::NotRace => Some(FilterFunction::NotRace),
+        FilterType::NotCell => Some(FilterFunction::NotCell),
+        _ => None,
+    }
+}
+
+/// Strip a leading word from `s`, ignoring case, returning the remainder after a space.
+fn strip_word<'a>(s: &'a str, word: &str) -> Option<&'a str> {
+    let rest = s.get(word.len()..)?;
+    (s[..word.len()].eq_ignore_ascii_case(word) && rest.starts_with(' ')).then(|| rest.trim_start())
+}
+
+fn invalid_filter(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        for s in [
+            "Function PC Level >= 5",
+            "Function Talked To PC = 0",
+            "Item Gold_001 > 100",
+            "Journal A1_1 = 10",
+            "Global Random100 < 50.5",
+            "Not Cell Seyda Neen = 0",
+        ] {
+            let filter: Filter = s.parse().unwrap();
+            assert_eq!(filter.to_string(), s);
+        }
+
+        let filter: Filter = "item gold_001 != 0".parse().unwrap();
+        assert_eq!(filter.filter_type, FilterType::Item);
+        assert_eq!(filter.function, FilterFunction::ItemType);
+        assert_eq!(filter.comparison, FilterComparison::NotEqual);
+
+        let filter: Filter = "Function PC Hand To Hand < 30".parse().unwrap();
+        assert_eq!(filter.function, FilterFunction::PcHandToHand);
+
+        for s in [
+            "Function Unknown = 1",
+            "Function Same Race > 0",
+            "Journal A1_1 = 1.5",
+            "Not ID fargoth != 0",
+            "Item Gold_001 100",
+            "Spell Fireball = 1",
+        ] {
+            assert!(s.parse::<Filter>().is_err(), "{s}");
+        }
+    }
+}
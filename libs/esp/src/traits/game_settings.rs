@@ -0,0 +1,673 @@
+// rust std imports
+use std::sync::LazyLock;
+
+// internal imports
+use crate::prelude::*;
+
+/// Vanilla values of the float game settings.
+#[rustfmt::skip]
+const FLOATS: &[(&str, f32)] = &[
+    ("fAIFleeFleeMult", 0.3),
+    ("fAIFleeHealthMult", 7.0),
+    ("fAIMagicSpellMult", 15.0),
+    ("fAIMeleeArmorMult", 1.0),
+    ("fAIMeleeSummWeaponMult", 1.0),
+    ("fAIMeleeWeaponMult", 2.0),
+    ("fAIRangeMagicSpellMult", 25.0),
+    ("fAIRangeMeleeWeaponMult", 25.0),
+    ("fAlarmRadius", 2000.0),
+    ("fAthleticsRunBonus", 1.0),
+    ("fAudioDefaultMaxDistance", 4000.0),
+    ("fAudioDefaultMinDistance", 100.0),
+    ("fAudioMaxDistanceMult", 20.0),
+    ("fAudioMinDistanceMult", 5.0),
+    ("fAudioVoiceDefaultMaxDistance", 2500.0),
+    ("fAudioVoiceDefaultMinDistance", 750.0),
+    ("fAutoPCSpellChance", 80.0),
+    ("fAutoSpellChance", 80.0),
+    ("fBargainOfferBase", 50.0),
+    ("fBargainOfferMulti", -4.0),
+    ("fBarterGoldResetDelay", 24.0),
+    ("fBaseRunMultiplier", 1.75),
+    ("fBlockStillBonus", 1.25),
+    ("fBribe1000Mod", 35.0),
+    ("fBribe100Mod", 15.0),
+    ("fBribe10Mod", 5.0),
+    ("fCombatAngleXY", 0.5),
+    ("fCombatAngleZ", 0.5),
+    ("fCombatArmorMinMult", 0.25),
+    ("fCombatBlockLeftAngle", -90.0),
+    ("fCombatBlockRightAngle", 30.0),
+    ("fCombatCriticalStrikeMult", 4.0),
+    ("fCombatDelayCreature", 0.0),
+    ("fCombatDelayNPC", 0.0),
+    ("fCombatDistance", 128.0),
+    ("fCombatDistanceWerewolfMod", 0.3),
+    ("fCombatForceSideAngle", 30.0),
+    ("fCombatInvisoMult", 0.2),
+    ("fCombatKODamageMult", 1.5),
+    ("fCombatTorsoSideAngle", 45.0),
+    ("fCombatTorsoStartPercent", 0.8),
+    ("fCombatTorsoStopPercent", 0.8),
+    ("fConstantEffectMult", 0.5),
+    ("fCorpseClearDelay", 72.0),
+    ("fCorpseRespawnDelay", 72.0),
+    ("fCrimeGoldDiscountMult", 0.5),
+    ("fCrimeGoldTurnInMult", 0.9),
+    ("fCrimeStealing", 1.0),
+    ("fDamageStrengthBase", 0.5),
+    ("fDamageStrengthMult", 0.1),
+    ("fDifficultyMult", 5.0),
+    ("fDiseaseXferChance", 0.1),
+    ("fDispAttacking", -50.0),
+    ("fDispBargainFailMod", -1.0),
+    ("fDispBargainSuccessMod", 1.0),
+    ("fDispCrimeMod", 0.0),
+    ("fDispDiseaseMod", -10.0),
+    ("fDispFactionMod", 3.0),
+    ("fDispFactionRankBase", 1.0),
+    ("fDispFactionRankMult", 0.5),
+    ("fDispositionMod", 1.0),
+    ("fDispPersonalityBase", 50.0),
+    ("fDispPersonalityMult", 0.5),
+    ("fDispPickPocketMod", -25.0),
+    ("fDispRaceMod", 5.0),
+    ("fDispStealing", -0.5),
+    ("fDispWeaponDrawn", -5.0),
+    ("fEffectCostMult", 0.5),
+    ("fElementalShieldMult", 0.1),
+    ("fEnchantmentChanceMult", 3.0),
+    ("fEnchantmentConstantChanceMult", 0.5),
+    ("fEnchantmentConstantDurationMult", 100.0),
+    ("fEnchantmentMult", 0.1),
+    ("fEnchantmentValueMult", 1000.0),
+    ("fEncumberedMoveEffect", 0.3),
+    ("fEncumbranceStrMult", 5.0),
+    ("fEndFatigueMult", 0.04),
+    ("fFallAcroBase", 0.5),
+    ("fFallAcroMult", 0.1),
+    ("fFallDamageDistanceMin", 400.0),
+    ("fFallDistanceBase", 0.0),
+    ("fFallDistanceMult", 0.07),
+    ("fFatigueAttackBase", 2.0),
+    ("fFatigueAttackMult", 0.0),
+    ("fFatigueBase", 1.25),
+    ("fFatigueBlockBase", 4.0),
+    ("fFatigueBlockMult", 0.0),
+    ("fFatigueJumpBase", 5.0),
+    ("fFatigueJumpMult", 0.0),
+    ("fFatigueMult", 0.5),
+    ("fFatigueReturnBase", 2.5),
+    ("fFatigueReturnMult", 0.02),
+    ("fFatigueRunBase", 5.0),
+    ("fFatigueRunMult", 2.0),
+    ("fFatigueSneakBase", 1.5),
+    ("fFatigueSneakMult", 1.5),
+    ("fFatigueSpellBase", 0.0),
+    ("fFatigueSpellCostMult", 0.0),
+    ("fFatigueSpellMult", 0.0),
+    ("fFatigueSwimRunBase", 7.0),
+    ("fFatigueSwimRunMult", 0.0),
+    ("fFatigueSwimWalkBase", 5.0),
+    ("fFatigueSwimWalkMult", 0.0),
+    ("fFightDispMult", 0.2),
+    ("fFightDistanceMultiplier", 0.005),
+    ("fFightStealing", 50.0),
+    ("fFleeDistance", 3000.0),
+    ("fGreetDistanceReset", 512.0),
+    ("fHandtoHandHealthPer", 0.1),
+    ("fHandToHandReach", 1.0),
+    ("fHoldBreathTime", 20.0),
+    ("fIdleChanceMultiplier", 0.75),
+    ("fIngredientMult", 2.0),
+    ("fInteriorHeadTrackMult", 0.5),
+    ("fJumpAcrobaticsBase", 128.0),
+    ("fJumpAcroMultiplier", 4.0),
+    ("fJumpEncumbranceBase", 0.5),
+    ("fJumpEncumbranceMultiplier", 1.0),
+    ("fJumpMoveBase", 0.5),
+    ("fJumpMoveMult", 0.005),
+    ("fJumpRunMultiplier", 1.0),
+    ("fKnockDownMult", 0.5),
+    ("fLevelMod", 5.0),
+    ("fLevelUpHealthEndMult", 0.1),
+    ("fLightMaxMod", 0.6),
+    ("fLuckMod", 10.0),
+    ("fMagesGuildTravel", 10.0),
+    ("fMagicCreatureCastDelay", 1.5),
+    ("fMagicDetectRefreshRate", 1.0),
+    ("fMagicItemConstantMult", 0.1),
+    ("fMagicItemCostMult", 0.1),
+    ("fMagicItemOnceMult", 0.5),
+    ("fMagicItemPriceMult", 1.0),
+    ("fMagicItemRechargePerSecond", 0.05),
+    ("fMagicItemStrikeMult", 0.5),
+    ("fMagicItemUsedMult", 0.7),
+    ("fMagicStartIconBlink", 3.0),
+    ("fMagicSunBlockedMult", 0.5),
+    ("fMajorSkillBonus", 0.75),
+    ("fMaxFlyingHeight", 1200.0),
+    ("fMaxHandToHandMult", 0.1),
+    ("fMaxHeadTrackDistance", 400.0),
+    ("fMaxWalkSpeed", 100.0),
+    ("fMaxWalkSpeedCreature", 300.0),
+    ("fMedMaxMod", 0.9),
+    ("fMessageTimePerChar", 0.1),
+    ("fMinFlyingHeight", 5.0),
+    ("fMinHandToHandMult", 0.1),
+    ("fMinorSkillBonus", 1.0),
+    ("fMinWalkSpeed", 100.0),
+    ("fMinWalkSpeedCreature", 5.0),
+    ("fMiscSkillBonus", 1.25),
+    ("fNPCbaseMagickaMult", 2.0),
+    ("fNPCHealthBarFade", 1.0),
+    ("fNPCHealthBarTime", 5.0),
+    ("fPCbaseMagickaMult", 1.0),
+    ("fPerDieRollMult", 0.3),
+    ("fPersonalityMod", 5.0),
+    ("fPerTempMult", 5.0),
+    ("fPickLockMult", -1.0),
+    ("fPickPocketMod", 0.3),
+    ("fPotionMinUsefulDuration", 20.0),
+    ("fPotionStrengthMult", 0.5),
+    ("fPotionT1DurMult", 0.5),
+    ("fPotionT1MagMult", 1.5),
+    ("fPotionT4BaseStrengthMult", 20.0),
+    ("fPotionT4EquipStrengthMult", 2.0),
+    ("fProjectileMaxSpeed", 3000.0),
+    ("fProjectileMinSpeed", 400.0),
+    ("fProjectileThrownStoreChance", 25.0),
+    ("fRepairAmountMult", 3.0),
+    ("fRepairMult", 1.0),
+    ("fReputationMod", 1.0),
+    ("fRestMagicMult", 0.15),
+    ("fSeriousWoundMult", 0.0),
+    ("fSleepRandMod", 0.25),
+    ("fSleepRestMod", 0.3),
+    ("fSneakBootMult", -1.0),
+    ("fSneakDistanceBase", 64.0),
+    ("fSneakDistanceMultiplier", 0.5),
+    ("fSneakNoViewMult", 1.5),
+    ("fSneakSkillMult", 1.0),
+    ("fSneakSpeedMultiplier", 0.75),
+    ("fSneakUseDelay", 1.0),
+    ("fSneakUseDist", 500.0),
+    ("fSneakViewMult", 1.5),
+    ("fSoulGemMult", 3.0),
+    ("fSpecialSkillBonus", 0.8),
+    ("fSpellMakingValueMult", 7.0),
+    ("fSpellPriceMult", 2.0),
+    ("fSpellValueMult", 10.0),
+    ("fStromWalkMult", 0.25),
+    ("fStromWindSpeed", 0.7),
+    ("fSwimHeightScale", 0.9),
+    ("fSwimRunAthleticsMult", 0.1),
+    ("fSwimRunBase", 0.5),
+    ("fSwimWalkAthleticsMult", 0.02),
+    ("fSwimWalkBase", 0.5),
+    ("fSwingBlockBase", 1.0),
+    ("fSwingBlockMult", 1.0),
+    ("fTargetSpellMaxSpeed", 1000.0),
+    ("fThrownWeaponMaxSpeed", 1000.0),
+    ("fThrownWeaponMinSpeed", 300.0),
+    ("fTrapCostMult", 0.0),
+    ("fTravelMult", 4000.0),
+    ("fTravelTimeMult", 16000.0),
+    ("fUnarmoredBase1", 0.1),
+    ("fUnarmoredBase2", 0.065),
+    ("fVanityDelay", 30.0),
+    ("fVoiceIdleOdds", 0.0),
+    ("fWaterReflectUpdateAlways", 0.0),
+    ("fWaterReflectUpdateSeldom", 10.0),
+    ("fWeaponDamageMult", 0.1),
+    ("fWeaponFatigueBlockMult", 1.0),
+    ("fWeaponFatigueMult", 0.25),
+    ("fWereWolfAcrobatics", 80.0),
+    ("fWereWolfAgility", 150.0),
+    ("fWereWolfAlchemy", 1.0),
+    ("fWereWolfAlteration", 1.0),
+    ("fWereWolfArmorer", 1.0),
+    ("fWereWolfAthletics", 150.0),
+    ("fWereWolfAxe", 1.0),
+    ("fWereWolfBlock", 1.0),
+    ("fWereWolfBluntWeapon", 1.0),
+    ("fWereWolfConjuration", 1.0),
+    ("fWereWolfDestruction", 1.0),
+    ("fWereWolfEnchant", 1.0),
+    ("fWereWolfEndurance", 150.0),
+    ("fWereWolfFatigue", 400.0),
+    ("fWereWolfHandtoHand", 100.0),
+    ("fWereWolfHealth", 2.0),
+    ("fWereWolfHeavyArmor", 1.0),
+    ("fWereWolfIllusion", 1.0),
+    ("fWereWolfIntellegence", 1.0),
+    ("fWereWolfLightArmor", 1.0),
+    ("fWereWolfLongBlade", 1.0),
+    ("fWereWolfLuck", 1.0),
+    ("fWereWolfMagicka", 100.0),
+    ("fWereWolfMarksman", 1.0),
+    ("fWereWolfMediumArmor", 1.0),
+    ("fWereWolfMerchantile", 1.0),
+    ("fWereWolfMysticism", 1.0),
+    ("fWereWolfPersonality", 1.0),
+    ("fWereWolfRestoration", 1.0),
+    ("fWereWolfRunMult", 1.3),
+    ("fWereWolfSecurity", 1.0),
+    ("fWereWolfShortBlade", 1.0),
+    ("fWereWolfSilverWeaponDamageMult", 2.0),
+    ("fWereWolfSneak", 1.0),
+    ("fWereWolfSpear", 1.0),
+    ("fWereWolfSpeechcraft", 1.0),
+    ("fWereWolfSpeed", 150.0),
+    ("fWereWolfStrength", 150.0),
+    ("fWereWolfUnarmored", 100.0),
+    ("fWereWolfWillPower", 1.0),
+    ("fWortChanceValue", 15.0),
+];
+
+/// Vanilla values of the integer game settings.
+#[rustfmt::skip]
+const INTEGERS: &[(&str, i32)] = &[
+    ("iAlarmAttack", 50),
+    ("iAlarmKilling", 90),
+    ("iAlarmPickPocket", 20),
+    ("iAlarmStealing", 1),
+    ("iAlarmTresspass", 5),
+    ("iAlchemyMod", 2),
+    ("iAutoPCSpellMax", 100),
+    ("iAutoRepFacMod", 2),
+    ("iAutoRepLevMod", 0),
+    ("iAutoSpellAlterationMax", 2),
+    ("iAutoSpellAttSkillMin", 70),
+    ("iAutoSpellConjurationMax", 3),
+    ("iAutoSpellDestructionMax", 2),
+    ("iAutoSpellIllusionMax", 2),
+    ("iAutoSpellMysticismMax", 3),
+    ("iAutoSpellRestorationMax", 2),
+    ("iAutoSpellTimesCanCast", 3),
+    ("iBarterFailDisposition", -1),
+    ("iBarterSuccessDisposition", 1),
+    ("iBaseArmorSkill", 30),
+    ("iBlockMaxChance", 50),
+    ("iBlockMinChance", 10),
+    ("iBootsWeight", 20),
+    ("iCrimeAttack", 1000),
+    ("iCrimeKilling", 1000),
+    ("iCrimePickPocket", 25),
+    ("iCrimeThreshold", 1000),
+    ("iCrimeThresholdMultiplier", 10),
+    ("iCrimeTresspass", 5),
+    ("iCuirassWeight", 30),
+    ("iDaysinPrisonMod", 100),
+    ("iDispAttackMod", -15),
+    ("iDispKilling", -50),
+    ("iDispTresspass", -20),
+    ("iFightAlarmMult", 100),
+    ("iFightAttack", 100),
+    ("iFightAttacking", 50),
+    ("iFightDistanceBase", 20),
+    ("iFightKilling", 50),
+    ("iFightPickpocket", 25),
+    ("iFightTrespass", 25),
+    ("iFlee", 0),
+    ("iGauntletWeight", 5),
+    ("iGreavesWeight", 15),
+    ("iGreetDistanceMultiplier", 6),
+    ("iGreetDuration", 4),
+    ("iHelmWeight", 5),
+    ("iKnockDownOddsBase", 50),
+    ("iKnockDownOddsMult", 50),
+    ("iLevelUp01Mult", 2),
+    ("iLevelUp02Mult", 2),
+    ("iLevelUp03Mult", 2),
+    ("iLevelUp04Mult", 2),
+    ("iLevelUp05Mult", 3),
+    ("iLevelUp06Mult", 3),
+    ("iLevelUp07Mult", 3),
+    ("iLevelUp08Mult", 4),
+    ("iLevelUp09Mult", 4),
+    ("iLevelUp10Mult", 5),
+    ("iLevelupMajorMult", 1),
+    ("iLevelupMajorMultAttribute", 1),
+    ("iLevelupMinorMult", 1),
+    ("iLevelupMinorMultAttribute", 1),
+    ("iLevelupMiscMultAttriubte", 1),
+    ("iLevelupSpecialization", 1),
+    ("iLevelupTotal", 10),
+    ("iMagicItemChargeConst", 10),
+    ("iMagicItemChargeOnce", 1),
+    ("iMagicItemChargeStrike", 10),
+    ("iMagicItemChargeUse", 5),
+    ("iMaxActivateDist", 192),
+    ("iMaxInfoDist", 192),
+    ("iMonthsToRespawn", 1),
+    ("iNumberCreatures", 1),
+    ("iPauldronWeight", 10),
+    ("iPerMinChance", 5),
+    ("iPerMinChange", 10),
+    ("iPickMaxChance", 75),
+    ("iPickMinChance", 5),
+    ("iShieldWeight", 15),
+    ("iSoulAmountForConstantEffect", 400),
+    ("iTrainingMod", 10),
+    ("iVoiceAttackOdds", 10),
+    ("iVoiceHitOdds", 30),
+    ("iWereWolfBounty", 1000),
+    ("iWereWolfFightMod", 100),
+    ("iWereWolfFleeMod", 100),
+    ("iWereWolfLevelToAttack", 20),
+];
+
+/// Vanilla values of the string game settings.
+///
+/// This covers the attribute, skill, specialization and school names and common interface
+/// buttons; other string settings have no built-in values.
+///
+#[rustfmt::skip]
+const STRINGS: &[(&str, &str)] = &[
+    ("sAttributeAgility", "Agility"),
+    ("sAttributeEndurance", "Endurance"),
+    ("sAttributeIntelligence", "Intelligence"),
+    ("sAttributeLuck", "Luck"),
+    ("sAttributePersonality", "Personality"),
+    ("sAttributeSpeed", "Speed"),
+    ("sAttributeStrength", "Strength"),
+    ("sAttributeWillpower", "Willpower"),
+    ("sBack", "Back"),
+    ("sCancel", "Cancel"),
+    ("sClose", "Close"),
+    ("sDefaultCellname", "Wilderness"),
+    ("sDone", "Done"),
+    ("sNext", "Next"),
+    ("sNo", "No"),
+    ("sOK", "OK"),
+    ("sSchoolAlteration", "Alteration"),
+    ("sSchoolConjuration", "Conjuration"),
+    ("sSchoolDestruction", "Destruction"),
+    ("sSchoolIllusion", "Illusion"),
+    ("sSchoolMysticism", "Mysticism"),
+    ("sSchoolRestoration", "Restoration"),
+    ("sSkillAcrobatics", "Acrobatics"),
+    ("sSkillAlchemy", "Alchemy"),
+    ("sSkillAlteration", "Alteration"),
+    ("sSkillArmorer", "Armorer"),
+    ("sSkillAthletics", "Athletics"),
+    ("sSkillAxe", "Axe"),
+    ("sSkillBlock", "Block"),
+    ("sSkillBluntweapon", "Blunt Weapon"),
+    ("sSkillConjuration", "Conjuration"),
+    ("sSkillDestruction", "Destruction"),
+    ("sSkillEnchant", "Enchant"),
+    ("sSkillHandtohand", "Hand-to-hand"),
+    ("sSkillHeavyarmor", "Heavy Armor"),
+    ("sSkillIllusion", "Illusion"),
+    ("sSkillLightarmor", "Light Armor"),
+    ("sSkillLongblade", "Long Blade"),
+    ("sSkillMarksman", "Marksman"),
+    ("sSkillMediumarmor", "Medium Armor"),
+    ("sSkillMercantile", "Mercantile"),
+    ("sSkillMysticism", "Mysticism"),
+    ("sSkillRestoration", "Restoration"),
+    ("sSkillSecurity", "Security"),
+    ("sSkillShortblade", "Short Blade"),
+    ("sSkillSneak", "Sneak"),
+    ("sSkillSpear", "Spear"),
+    ("sSkillSpeechcraft", "Speechcraft"),
+    ("sSkillUnarmored", "Unarmored"),
+    ("sSpecializationCombat", "Combat"),
+    ("sSpecializationMagic", "Magic"),
+    ("sSpecializationStealth", "Stealth"),
+    ("sYes", "Yes"),
+];
+
+/// The vanilla values of every known game setting, keyed by lowercase id.
+static VANILLA: LazyLock<HashMap<String, GameSettingValue>> = LazyLock::new(|| {
+    let floats = FLOATS
+        .iter()
+        .map(|(id, value)| (id.to_ascii_lowercase(), GameSettingValue::Float(*value)));
+    let integers = INTEGERS
+        .iter()
+        .map(|(id, value)| (id.to_ascii_lowercase(), GameSettingValue::Integer(*value)));
+    let strings = STRINGS
+        .iter()
+        .map(|(id, value)| (id.to_ascii_lowercase(), GameSettingValue::String((*value).into())));
+    floats.chain(integers).chain(strings).collect()
+});
+
+/// The type of value a game setting holds.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GameSettingType {
+    Float,
+    Integer,
+    String,
+}
+
+impl GameSettingType {
+    /// The type implied by the prefix of a game setting id: `f`, `i` or `s`.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.as_bytes().first()?.to_ascii_lowercase() {
+            b'f' => Some(Self::Float),
+            b'i' => Some(Self::Integer),
+            b's' => Some(Self::String),
+            _ => None,
+        }
+    }
+}
+
+impl GameSettingValue {
+    pub const fn setting_type(&self) -> GameSettingType {
+        match self {
+            Self::Float(_) => GameSettingType::Float,
+            Self::Integer(_) => GameSettingType::Integer,
+            Self::String(_) => GameSettingType::String,
+        }
+    }
+}
+
+/// A problem with a game setting record.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameSettingIssue {
+    /// The stored value does not have the type implied by the id prefix.
+    TypeMismatch {
+        expected: GameSettingType,
+        found: GameSettingType,
+    },
+    /// The record sets the vanilla value, and has no effect.
+    EqualsDefault,
+}
+
+impl GameSetting {
+    /// The vanilla value of this setting, if it is known.
+    pub fn vanilla_value(&self) -> Option<&'static GameSettingValue> {
+        GameSettings::vanilla(&self.id)
+    }
+
+    /// Check the stored value against the type implied by the id prefix, and the vanilla value.
+    pub fn check(&self) -> Option<GameSettingIssue> {
+        let found = self.value.setting_type();
+        if let Some(expected) = GameSettingType::from_id(&self.id) {
+            if expected != found {
+                return Some(GameSettingIssue::TypeMismatch { expected, found });
+            }
+        }
+        if self.vanilla_value() == Some(&self.value) {
+            return Some(GameSettingIssue::EqualsDefault);
+        }
+        None
+    }
+}
+
+/// Game settings collected from a load ordered set of plugins, with typed access.
+///
+/// Settings which are not defined by any plugin use their vanilla values. Only some string
+/// settings have built-in values, see `STRINGS`.
+///
+#[derive(Clone, Debug, Default)]
+pub struct GameSettings {
+    values: HashMap<String, GameSettingValue>,
+}
+
+impl GameSettings {
+    /// Game settings with only their vanilla values.
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Collect game settings from plugins, which must be provided in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::new();
+        for plugin in plugins {
+            for setting in plugin.objects_of_type::<GameSetting>() {
+                this.insert(setting);
+            }
+        }
+        this
+    }
+
+    /// Apply a game setting record, replacing any previous value. Deleted records restore the
+    /// vanilla value.
+    pub fn insert(&mut self, setting: &GameSetting) {
+        let id = setting.id.to_ascii_lowercase();
+        if setting.deleted() {
+            self.values.remove(&id);
+        } else {
+            self.values.insert(id, setting.value.clone());
+        }
+    }
+
+    /// The vanilla value of a game setting. Ids are compared case-insensitively.
+    pub fn vanilla(id: &str) -> Option<&'static GameSettingValue> {
+        VANILLA.get(&id.to_ascii_lowercase())
+    }
+
+    /// The value of a game setting. Ids are compared case-insensitively.
+    pub fn get(&self, id: &str) -> Option<&GameSettingValue> {
+        let id = id.to_ascii_lowercase();
+        self.values.get(&id).or_else(|| VANILLA.get(&id))
+    }
+
+    /// The value of a numeric game setting as a float. Integer values are converted, as in the
+    /// engine.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn get_f32(&self, id: &str) -> Option<f32> {
+        match self.get(id)? {
+            GameSettingValue::Float(value) => Some(*value),
+            GameSettingValue::Integer(value) => Some(*value as f32),
+            GameSettingValue::String(_) => None,
+        }
+    }
+
+    /// The value of a numeric game setting as an integer. Float values are truncated, as in the
+    /// engine.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn get_i32(&self, id: &str) -> Option<i32> {
+        match self.get(id)? {
+            GameSettingValue::Float(value) => Some(*value as i32),
+            GameSettingValue::Integer(value) => Some(*value),
+            GameSettingValue::String(_) => None,
+        }
+    }
+
+    /// The value of a string game setting.
+    pub fn get_str(&self, id: &str) -> Option<&str> {
+        match self.get(id)? {
+            GameSettingValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(id: &str, value: GameSettingValue) -> GameSetting {
+        GameSetting {
+            id: id.into(),
+            value,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn typed_game_settings() {
+        let mut plugin = Plugin::new();
+        plugin.objects = vec![
+            setting("fCombatDistance", GameSettingValue::Float(256.0)).into(),
+            setting("sTargetCriticalStrike", GameSettingValue::String("Critical!".into())).into(),
+            setting("iMaxActivateDist", GameSettingValue::Integer(300)).into(),
+        ];
+        let mut deleted = setting("iMaxActivateDist", GameSettingValue::Integer(0));
+        deleted.set_deleted(true);
+
+        let mut settings = GameSettings::from_plugins([&plugin]);
+        assert_eq!(settings.get_f32("fcombatdistance"), Some(256.0));
+        assert_eq!(settings.get_f32("fEffectCostMult"), Some(0.5));
+        assert_eq!(settings.get_f32("iAlchemyMod"), Some(2.0));
+        assert_eq!(settings.get_i32("iMaxActivateDist"), Some(300));
+        assert_eq!(settings.get_str("sTargetCriticalStrike"), Some("Critical!"));
+        assert_eq!(settings.get_str("fCombatDistance"), None);
+        assert_eq!(settings.get("fUnknownSetting"), None);
+
+        settings.insert(&deleted);
+        assert_eq!(settings.get_i32("iMaxActivateDist"), Some(192));
+
+        assert_eq!(
+            setting("fCombatDistance", GameSettingValue::Integer(128)).check(),
+            Some(GameSettingIssue::TypeMismatch {
+                expected: GameSettingType::Float,
+                found: GameSettingType::Integer,
+            })
+        );
+        assert_eq!(
+            setting("FCOMBATDISTANCE", GameSettingValue::Float(128.0)).check(),
+            Some(GameSettingIssue::EqualsDefault)
+        );
+        assert_eq!(setting("fCombatDistance", GameSettingValue::Float(100.0)).check(), None);
+
+        assert_eq!(settings.get_f32("fWereWolfStrength"), Some(150.0));
+        assert_eq!(settings.get_str("sSkillHandtohand"), Some("Hand-to-hand"));
+        assert_eq!(
+            setting("sSkillLongblade", GameSettingValue::String("Long Blade".into())).check(),
+            Some(GameSettingIssue::EqualsDefault)
+        );
+    }
+
+    /// Compares the built-in values against a copy of `Morrowind.esm`, listing every setting
+    /// that is missing or differs in the syntax of the tables above, so that they can be
+    /// updated by pasting the output. Built-in values which `Morrowind.esm` does not define are
+    /// listed as well.
+    #[test]
+    #[ignore = "requires Morrowind.esm, whose path is given by MORROWIND_ESM"]
+    fn vanilla_values_match_morrowind_esm() -> io::Result<()> {
+        let path = std::env::var("MORROWIND_ESM").map_err(|error| io::Error::new(io::ErrorKind::NotFound, error))?;
+        let plugin = Plugin::from_path(path)?;
+
+        let mut differences: Vec<_> = plugin
+            .objects_of_type::<GameSetting>()
+            .filter(|setting| setting.vanilla_value() != Some(&setting.value))
+            .map(|setting| match &setting.value {
+                GameSettingValue::Float(value) => format!("({:?}, {value:?}),", setting.id),
+                GameSettingValue::Integer(value) => format!("({:?}, {value}),", setting.id),
+                GameSettingValue::String(value) => format!("({:?}, {value:?}),", setting.id),
+            })
+            .collect();
+        differences.sort_unstable();
+
+        let defined: HashSet<_> = plugin
+            .objects_of_type::<GameSetting>()
+            .map(|setting| setting.id.to_ascii_lowercase())
+            .collect();
+        let mut unused: Vec<_> = VANILLA.keys().filter(|id| !defined.contains(*id)).collect();
+        unused.sort_unstable();
+
+        assert!(
+            differences.is_empty() && unused.is_empty(),
+            "missing or different:\n{}\nnot defined by Morrowind.esm:\n{unused:?}",
+            differences.join("\n")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use crate::prelude::*;
+
+impl Plugin {
+    /// Find the landscape texture with the given index.
+    ///
+    /// Note that texture indices stored in [`Landscape::texture_indices`] are offset by one.
+    ///
+    pub fn get_landscape_texture(&self, index: u32) -> Option<&LandscapeTexture> {
+        self.objects_of_type::<LandscapeTexture>()
+            .filter(|texture| !texture.deleted())
+            .find(|texture| texture.index == index)
+    }
+
+    /// Find the landscape texture with the given file name, ignoring case.
+    pub fn find_landscape_texture(&self, file_name: &str) -> Option<&LandscapeTexture> {
+        self.objects_of_type::<LandscapeTexture>()
+            .filter(|texture| !texture.deleted())
+            .find(|texture| texture.file_name.eq_ignore_ascii_case(file_name))
+    }
+
+    /// Resolve the texture file name of each texel of a landscape, in row-major order.
+    ///
+    /// The landscape is expected to be defined by this plugin, as texture indices are only
+    /// meaningful relative to the plugin that defines them. Texels using the default texture,
+    /// or whose texture could not be found, are `None`.
+    ///
+    pub fn resolve_landscape_textures(&self, landscape: &Landscape) -> Box<[[Option<&str>; 16]; 16]> {
+        let mut file_names = Box::new([[None; 16]; 16]);
+
+        if landscape.landscape_flags.contains(LandscapeFlags::USES_TEXTURES) {
+            for (row, indices) in file_names.iter_mut().zip(landscape.decode_texture_indices().iter()) {
+                for (file_name, &value) in row.iter_mut().zip(indices) {
+                    *file_name = value
+                        .checked_sub(1)
+                        .and_then(|index| self.get_landscape_texture(index.into()))
+                        .map(|texture| texture.file_name.as_str());
+                }
+            }
+        }
+
+        file_names
+    }
+
+    /// Get the texture index for the given texture file name, creating a new
+    /// [`LandscapeTexture`] if this plugin does not define one yet.
+    ///
+    /// The returned value is offset by one, as used by [`Landscape::texture_indices`]. New
+    /// textures are appended to the objects list, use [`Plugin::sort_objects`] to reorder them.
+    ///
+    pub fn get_or_insert_landscape_texture(&mut self, file_name: &str) -> io::Result<u16> {
+        if let Some(texture) = self.find_landscape_texture(file_name) {
+            return texture_value(texture.index);
+        }
+
+        let index = self
+            .objects_of_type::<LandscapeTexture>()
+            .map(|texture| texture.index + 1)
+            .max()
+            .unwrap_or(0);
+        let value = texture_value(index)?;
+
+        let texture = LandscapeTexture {
+            id: self.unique_landscape_texture_id(file_name),
+            index,
+            file_name: file_name.into(),
+            ..default()
+        };
+        self.objects.push(texture.into());
+
+        Ok(value)
+    }
+
+    /// Paint the texel at (`x`, `y`) of the landscape at `grid` with the given texture file name.
+    ///
+    /// Texel coordinates are in row-major order, see [`Landscape::decode_texture_indices`].
+    /// Returns an error, without creating the texture, if the landscape does not exist or the
+    /// coordinates are outside of its 16x16 texels.
+    ///
+    pub fn paint_landscape_texture(&mut self, grid: (i32, i32), texel: (usize, usize), file_name: &str) -> io::Result<()> {
+        let Some(landscape) = self.objects_of_type::<Landscape>().find(|landscape| landscape.grid == grid) else {
+            return Err(landscape_not_found(grid));
+        };
+        if landscape.texture_index(texel.0, texel.1).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Texel is out of bounds: {texel:?}"),
+            ));
+        }
+
+        let value = self.get_or_insert_landscape_texture(file_name)?;
+
+        self.objects_of_type_mut::<Landscape>()
+            .find(|landscape| landscape.grid == grid)
+            .ok_or_else(|| landscape_not_found(grid))?
+            .set_texture_index(texel.0, texel.1, value)
+    }
+
+    /// Remap the texture indices of a landscape taken from the `source` plugin, so that they
+    /// refer to the textures of this plugin. Missing textures are created as necessary.
+    ///
+    /// Texels whose texture could not be resolved in the `source` plugin use the default texture.
+    ///
+    pub fn remap_landscape_textures(&mut self, landscape: &mut Landscape, source: &Plugin) -> io::Result<()> {
+        if !landscape.landscape_flags.contains(LandscapeFlags::USES_TEXTURES) {
+            return Ok(());
+        }
+
+        let mut remapped = HashMap::new();
+
+        for value in landscape.texture_indices.data.as_flattened_mut() {
+            if let Some(&new_value) = remapped.get(value) {
+                *value = new_value;
+                continue;
+            }
+
+            let texture = value
+                .checked_sub(1)
+                .and_then(|index| source.get_landscape_texture(index.into()));
+
+            let new_value = match texture {
+                Some(texture) => self.get_or_insert_landscape_texture(&texture.file_name)?,
+                None => 0,
+            };
+
+            remapped.insert(*value, new_value);
+            *value = new_value;
+        }
+
+        Ok(())
+    }
+
+    fn unique_landscape_texture_id(&self, file_name: &str) -> String {
+        let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+        let stem = stem.rsplit(['\\', '/']).next().unwrap_or(stem);
+
+        let is_unique = |id: &str| {
+            !self
+                .objects_of_type::<LandscapeTexture>()
+                .any(|texture| texture.id.eq_ignore_ascii_case(id))
+        };
+
+        if is_unique(stem) {
+            return stem.into();
+        }
+
+        (1..u32::MAX)
+            .map(|i| format!("{stem}_{i}"))
+            .find(|id| is_unique(id))
+            .unwrap_or_default()
+    }
+}
+
+fn texture_value(index: u32) -> io::Result<u16> {
+    index
+        .checked_add(1)
+        .and_then(|value| value.try_into().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "too many landscape textures"))
+}
+
+fn landscape_not_found(grid: (i32, i32)) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("Unable to find landscape {grid:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture(id: &str, index: u32, file_name: &str) -> TES3Object {
+        LandscapeTexture {
+            id: id.into(),
+            index,
+            file_name: file_name.into(),
+            ..default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn paint_and_remap_textures() -> io::Result<()> {
+        let mut plugin = Plugin {
+            objects: vec![
+                texture("grass", 0, "tx_grass.dds"),
+                Landscape {
+                    grid: (0, 0),
+                    ..default()
+                }
+                .into(),
+            ],
+        };
+
+        // existing textures are reused, new textures get the next index and a unique id
+        assert_eq!(plugin.get_or_insert_landscape_texture("TX_Grass.dds")?, 1);
+        assert_eq!(plugin.get_or_insert_landscape_texture("textures\\grass.tga")?, 2);
+        let created = plugin.get_landscape_texture(1).unwrap();
+        assert_eq!(
+            (created.id.as_str(), created.file_name.as_str()),
+            ("grass_1", "textures\\grass.tga")
+        );
+
+        plugin.paint_landscape_texture((0, 0), (3, 2), "tx_rock.dds")?;
+        let landscape = plugin.objects_of_type::<Landscape>().next().unwrap();
+        assert_eq!(landscape.texture_index(3, 2), Some(3));
+        assert_eq!(plugin.resolve_landscape_textures(landscape)[2][3], Some("tx_rock.dds"));
+
+        // invalid texels or grids fail without creating textures
+        assert!(plugin.paint_landscape_texture((0, 0), (16, 0), "tx_sand.dds").is_err());
+        assert!(plugin.paint_landscape_texture((5, 5), (0, 0), "tx_sand.dds").is_err());
+        assert!(plugin.find_landscape_texture("tx_sand.dds").is_none());
+
+        // remapping resolves indices through the source plugin
+        let source = Plugin {
+            objects: vec![texture("rock", 0, "tx_rock.dds"), texture("mud", 1, "tx_mud.dds")],
+        };
+        let mut landscape = Landscape::default();
+        landscape.set_texture_index(0, 0, 1)?;
+        landscape.set_texture_index(1, 0, 2)?;
+        landscape.set_texture_index(2, 0, 9)?;
+        plugin.remap_landscape_textures(&mut landscape, &source)?;
+        assert_eq!(landscape.texture_index(0, 0), Some(3));
+        assert_eq!(landscape.texture_index(1, 0), Some(4));
+        assert_eq!(landscape.texture_index(2, 0), Some(0));
+        assert_eq!(plugin.get_landscape_texture(3).unwrap().id, "tx_mud");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+use crate::prelude::*;
+
+/// The chances of each final object being chosen from a leveled list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LeveledDistribution {
+    /// Final objects and their probabilities, most likely first.
+    pub objects: Vec<(String, f64)>,
+    /// The probability that nothing is chosen.
+    pub nothing: f64,
+}
+
+impl LeveledDistribution {
+    /// The probability of an object being chosen. Ids are compared case-insensitively.
+    pub fn probability(&self, id: &str) -> f64 {
+        self.objects
+            .iter()
+            .find(|(object, _)| object.eq_ignore_ascii_case(id))
+            .map_or(0.0, |(_, probability)| *probability)
+    }
+}
+
+/// The parts of a leveled item or creature list used for resolution.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct LeveledList {
+    chance_none: u8,
+    all_levels: bool,
+    entries: Vec<(String, u16)>,
+}
+
+impl LeveledList {
+    /// The entries the engine chooses between at a given player level.
+    ///
+    /// Only entries of the highest level not above the player level are considered, unless the
+    /// list calculates from all levels.
+    ///
+    fn candidates(&self, level: u16) -> Vec<&str> {
+        let highest = self
+            .entries
+            .iter()
+            .map(|(_, entry_level)| *entry_level)
+            .filter(|entry_level| *entry_level <= level)
+            .max()
+            .unwrap_or_default();
+
+        self.entries
+            .iter()
+            .filter(|(_, entry_level)| *entry_level <= level && (self.all_levels || *entry_level == highest))
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    fn chance_none(&self) -> f64 {
+        f64::from(self.chance_none.min(100)) / 100.0
+    }
+}
+
+/// Leveled item and creature lists collected from a load ordered set of plugins.
+///
+/// Entries that do not refer to another leveled list are treated as final objects, whether or
+/// not they exist.
+///
+#[derive(Clone, Debug, Default)]
+pub struct LeveledLists {
+    lists: HashMap<String, LeveledList>,
+}
+
+impl LeveledLists {
+    /// Collect leveled lists from plugins, which must be provided in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::default();
+
+        for object in plugins.into_iter().flat_map(|plugin| &plugin.objects) {
+            let list = match object {
+                TES3Object::LeveledItem(list) => LeveledList {
+                    chance_none: list.chance_none,
+                    all_levels: list.leveled_item_flags.contains(LeveledItemFlags::CALCULATE_FROM_ALL_LEVELS),
+                    entries: list.items.clone(),
+                },
+                TES3Object::LeveledCreature(list) => LeveledList {
+                    chance_none: list.chance_none,
+                    all_levels: list
+                        .leveled_creature_flags
+                        .contains(LeveledCreatureFlags::CALCULATE_FROM_ALL_LEVELS),
+                    entries: list.creatures.clone(),
+                },
+                _ => continue,
+            };
+
+            let id = object.editor_id_ascii_lowercase().into_owned();
+            this.lists.remove(&id);
+            if !object.deleted() {
+                this.lists.insert(id, list);
+            }
+        }
+
+        this
+    }
+
+    /// Whether a leveled list with the given id exists.
+    pub fn contains(&self, id: &str) -> bool {
+        self.lists.contains_key(&id.to_ascii_lowercase())
+    }
+
+    /// Calculate the exact chances of each final object being chosen from a leveled list at a
+    /// given player level, recursing through nested lists.
+    ///
+    /// Fails if the list does not exist, or if it can reach itself through nested lists.
+    ///
+    pub fn distribution(&self, id: &str, level: u16) -> io::Result<LeveledDistribution> {
+        if !self.contains(id) {
+            return Err(unknown_list(id));
+        }
+
+        let mut objects: HashMap<String, (String, f64)> = HashMap::new();
+        let mut nothing = 0.0;
+        let mut stack = vec![];
+
+        self.accumulate(id, 1.0, level, &mut stack, &mut |object, probability| match object {
+            Some(object) => {
+                objects
+                    .entry(object.to_ascii_lowercase())
+                    .or_insert_with(|| (object.into(), 0.0))
+                    .1 += probability;
+            }
+            None => nothing += probability,
+        })?;
+
+        let mut objects: Vec<_> = objects.into_values().collect();
+        objects.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Ok(LeveledDistribution { objects, nothing })
+    }
+
+    fn accumulate(
+        &self,
+        id: &str,
+        probability: f64,
+        level: u16,
+        stack: &mut Vec<String>,
+        add: &mut impl FnMut(Option<&str>, f64),
+    ) -> io::Result<()> {
+        let key = id.to_ascii_lowercase();
+        let Some(list) = self.lists.get(&key) else {
+            add(Some(id), probability);
+            return Ok(());
+        };
+
+        if stack.contains(&key) {
+            return Err(cycle_error(stack, &key));
+        }
+
+        let candidates = list.candidates(level);
+        if candidates.is_empty() {
+            add(None, probability);
+            return Ok(());
+        }
+
+        let chance_none = list.chance_none();
+        add(None, probability * chance_none);
+
+        #[allow(clippy::cast_precision_loss)]
+        let each = probability * (1.0 - chance_none) / candidates.len() as f64;
+
+        stack.push(key);
+        for candidate in candidates {
+            self.accumulate(candidate, each, level, stack, add)?;
+        }
+        stack.pop();
+
+        Ok(())
+    }
+
+    /// Find a chain of nested lists leading from a leveled list back to itself, at any level.
+    ///
+    /// The returned chain starts and ends with the same lowercase id.
+    ///
+    pub fn find_cycle(&self, id: &str) -> Option<Vec<String>> {
+        fn visit(lists: &LeveledLists, key: String, stack: &mut Vec<String>) -> Option<Vec<String>> {
+            if let Some(start) = stack.iter().position(|entry| *entry == key) {
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(key);
+                return Some(cycle);
+            }
+            let list = lists.lists.get(&key)?;
+            stack.push(key);
+            for (entry, _) in &list.entries {
+                if let Some(cycle) = visit(lists, entry.to_ascii_lowercase(), stack) {
+                    return Some(cycle);
+                }
+            }
+            stack.pop();
+            None
+        }
+
+        visit(self, id.to_ascii_lowercase(), &mut vec![])
+    }
+}
+
+/// Chooses objects from leveled lists the same way the engine does, using a seeded random
+/// number generator so that results can be reproduced.
+#[derive(Clone, Debug)]
+pub struct LeveledListSampler<'a> {
+    lists: &'a LeveledLists,
+    state: u64,
+}
+
+impl<'a> LeveledListSampler<'a> {
+    pub const fn new(lists: &'a LeveledLists, seed: u64) -> Self {
+        Self { lists, state: seed }
+    }
+
+    /// The next value of a `SplitMix64` generator.
+    const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `0..n`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn roll(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Choose a final object from a leveled list at a given player level, or `None` if nothing
+    /// is chosen.
+    ///
+    /// Fails if the list does not exist, or if a nested list chosen along the way is already
+    /// being resolved.
+    ///
+    pub fn sample(&mut self, id: &str, level: u16) -> io::Result<Option<String>> {
+        if !self.lists.contains(id) {
+            return Err(unknown_list(id));
+        }
+
+        let mut stack = vec![];
+        let mut id = id;
+
+        loop {
+            let key = id.to_ascii_lowercase();
+            let Some(list) = self.lists.lists.get(&key) else {
+                return Ok(Some(id.into()));
+            };
+            if stack.contains(&key) {
+                return Err(cycle_error(&stack, &key));
+            }
+
+            if self.roll(100) < usize::from(list.chance_none) {
+                return Ok(None);
+            }
+            let candidates = list.candidates(level);
+            if candidates.is_empty() {
+                return Ok(None);
+            }
+
+            id = candidates[self.roll(candidates.len())];
+            stack.push(key);
+        }
+    }
+}
+
+fn unknown_list(id: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("Unknown leveled list: {id}"))
+}
+
+fn cycle_error(stack: &[String], key: &str) -> io::Error {
+    let start = stack.iter().position(|entry| entry == key).unwrap_or_default();
+    let mut path = stack[start..].join(" -> ");
+    path.push_str(" -> ");
+    path.push_str(key);
+    io::Error::new(io::ErrorKind::InvalidData, format!("Leveled list cycle: {path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leveled_item(id: &str, chance_none: u8, all_levels: bool, items: &[(&str, u16)]) -> TES3Object {
+        LeveledItem {
+            id: id.into(),
+            chance_none,
+            leveled_item_flags: if all_levels {
+                LeveledItemFlags::CALCULATE_FROM_ALL_LEVELS
+            } else {
+                LeveledItemFlags::empty()
+            },
+            items: items.iter().map(|(id, level)| ((*id).into(), *level)).collect(),
+            ..default()
+        }
+        .into()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn resolve_leveled_lists() {
+        let mut plugin = Plugin::new();
+        plugin.objects = vec![
+            leveled_item("random_weapon", 0, true, &[("iron", 1), ("steel", 5), ("Random_Glass", 5)]),
+            leveled_item("random_glass", 50, false, &[("glass", 1)]),
+            leveled_item("random_armor", 0, false, &[("chitin", 1), ("bonemold", 5), ("Netch", 5)]),
+            leveled_item("a", 0, false, &[("b", 1)]),
+            leveled_item("b", 0, false, &[("iron", 1), ("A", 10)]),
+        ];
+        let lists = LeveledLists::from_plugins([&plugin]);
+
+        let distribution = lists.distribution("random_weapon", 5).unwrap();
+        assert_eq!(
+            distribution.objects,
+            [
+                ("iron".to_string(), 1.0 / 3.0),
+                ("steel".to_string(), 1.0 / 3.0),
+                ("glass".to_string(), 1.0 / 6.0)
+            ]
+        );
+        assert_eq!(distribution.nothing, 1.0 / 6.0);
+        assert_eq!(lists.distribution("random_weapon", 1).unwrap().probability("IRON"), 1.0);
+
+        let distribution = lists.distribution("random_armor", 7).unwrap();
+        assert_eq!(distribution.probability("chitin"), 0.0);
+        assert_eq!(distribution.probability("netch"), 0.5);
+
+        assert_eq!(lists.distribution("a", 1).unwrap().probability("iron"), 1.0);
+        assert!(lists.distribution("a", 10).is_err());
+        assert!(lists.distribution("iron", 10).is_err());
+        assert_eq!(lists.find_cycle("A").unwrap(), ["a", "b", "a"]);
+        assert_eq!(lists.find_cycle("random_weapon"), None);
+
+        let mut sampler = LeveledListSampler::new(&lists, 7);
+        let results: Vec<_> = (0..600).map(|_| sampler.sample("random_weapon", 5).unwrap()).collect();
+        let count = |id: Option<&str>| results.iter().filter(|result| result.as_deref() == id).count();
+        assert!((150..250).contains(&count(Some("iron"))));
+        assert!((50..150).contains(&count(Some("glass"))));
+        assert!((50..150).contains(&count(None)));
+
+        let mut sampler = LeveledListSampler::new(&lists, 7);
+        assert_eq!(sampler.sample("random_weapon", 5).unwrap(), results[0]);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::prelude::*;
+
+/// The entries a plugin added to and removed from a leveled list, relative to its master version.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LeveledListChanges {
+    /// The index of the plugin, in load order.
+    pub plugin_index: usize,
+    pub added: Vec<(String, u16)>,
+    pub removed: Vec<(String, u16)>,
+}
+
+/// A leveled list combining the changes of every plugin which modified it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergedLeveledList<T> {
+    pub list: T,
+    /// The changes of each plugin which defined the list, in load order.
+    pub changes: Vec<LeveledListChanges>,
+}
+
+/// The merged leveled lists of a load ordered set of plugins.
+///
+/// Each plugin's version of a list is compared against the version in the masters. Lists the
+/// masters do not define are compared against their first definition, so the changes of later
+/// plugins are applied on top of the plugin which introduced the list. This is the same policy as
+/// [`ObjectMerge`]. The merged list then follows these rules:
+///
+/// - Entries are compared by id, ignoring case, and level. Duplicate entries are counted, as they
+///   affect the chances of being chosen.
+/// - An entry removed by any plugin is removed. When plugins remove different numbers of copies
+///   of an entry, the largest number is removed.
+/// - An entry added by any plugin is added. When plugins add the same entry, it is only added as
+///   many times as a single plugin added it, so that identical changes are not counted twice.
+/// - `chance_none` and each list flag take the value of the last plugin which changed them from
+///   the master version.
+/// - Entries are sorted by level, keeping the master order for entries of equal level.
+///
+/// Lists whose last definition in load order deletes them are not merged.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LeveledListMerge {
+    pub items: Vec<MergedLeveledList<LeveledItem>>,
+    pub creatures: Vec<MergedLeveledList<LeveledCreature>>,
+}
+
+impl LeveledListMerge {
+    /// Merge the leveled lists of the given plugins against their masters. Both must be provided
+    /// in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(
+        masters: impl IntoIterator<Item = &'a Plugin>,
+        plugins: impl IntoIterator<Item = &'a Plugin>,
+    ) -> Self {
+        let mut items = Versions::default();
+        let mut creatures = Versions::default();
+
+        for object in masters.into_iter().flat_map(|plugin| &plugin.objects) {
+            match object {
+                TES3Object::LeveledItem(list) => items.insert_master(list),
+                TES3Object::LeveledCreature(list) => creatures.insert_master(list),
+                _ => {}
+            }
+        }
+
+        for (plugin_index, plugin) in plugins.into_iter().enumerate() {
+            for object in &plugin.objects {
+                match object {
+                    TES3Object::LeveledItem(list) => items.insert(plugin_index, list),
+                    TES3Object::LeveledCreature(list) => creatures.insert(plugin_index, list),
+                    _ => {}
+                }
+            }
+        }
+
+        Self {
+            items: items.merge(),
+            creatures: creatures.merge(),
+        }
+    }
+
+    /// Create a patch plugin containing every list that was modified by more than one plugin.
+    ///
+    /// Master files are not filled in and should be set by the caller.
+    ///
+    pub fn to_patch(&self) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(Header::default().into());
+
+        for merged in &self.items {
+            if merged.changes.len() > 1 {
+                plugin.objects.push(merged.list.clone().into());
+            }
+        }
+        for merged in &self.creatures {
+            if merged.changes.len() > 1 {
+                plugin.objects.push(merged.list.clone().into());
+            }
+        }
+
+        plugin
+    }
+}
+
+/// Access to the parts of leveled item and creature lists that are merged.
+trait LeveledRecord: Clone + EditorId + ObjectInfo {
+    fn entries(&self) -> &[(String, u16)];
+    fn entries_mut(&mut self) -> &mut Vec<(String, u16)>;
+    fn chance_none(&self) -> u8;
+    fn set_chance_none(&mut self, chance_none: u8);
+    fn list_flags(&self) -> u32;
+    fn set_list_flags(&mut self, bits: u32);
+}
+
+impl LeveledRecord for LeveledItem {
+    fn entries(&self) -> &[(String, u16)] {
+        &self.items
+    }
+    fn entries_mut(&mut self) -> &mut Vec<(String, u16)> {
+        &mut self.items
+    }
+    fn chance_none(&self) -> u8 {
+        self.chance_none
+    }
+    fn set_chance_none(&mut self, chance_none: u8) {
+        self.chance_none = chance_none;
+    }
+    fn list_flags(&self) -> u32 {
+        self.leveled_item_flags.bits()
+    }
+    fn set_list_flags(&mut self, bits: u32) {
+        self.leveled_item_flags = LeveledItemFlags::from_bits_retain(bits);
+    }
+}
+
+impl LeveledRecord for LeveledCreature {
+    fn entries(&self) -> &[(String, u16)] {
+        &self.creatures
+    }
+    fn entries_mut(&mut self) -> &mut Vec<(String, u16)> {
+        &mut self.creatures
+    }
+    fn chance_none(&self) -> u8 {
+        self.chance_none
+    }
+    fn set_chance_none(&mut self, chance_none: u8) {
+        self.chance_none = chance_none;
+    }
+    fn list_flags(&self) -> u32 {
+        self.leveled_creature_flags.bits()
+    }
+    fn set_list_flags(&mut self, bits: u32) {
+        self.leveled_creature_flags = LeveledCreatureFlags::from_bits_retain(bits);
+    }
+}
+
+/// The master version and plugin versions of each list, keyed by lowercase id.
+#[derive(Debug)]
+struct Versions<'a, T> {
+    masters: HashMap<String, &'a T>,
+    plugins: HashMap<String, Vec<(usize, &'a T)>>,
+    order: Vec<String>,
+}
+
+impl<T> Default for Versions<'_, T> {
+    fn default() -> Self {
+        Self {
+            masters: HashMap::new(),
+            plugins: HashMap::new(),
+            order: vec![],
+        }
+    }
+}
+
+impl<'a, T: LeveledRecord> Versions<'a, T> {
+    fn insert_master(&mut self, list: &'a T) {
+        let id = list.editor_id_ascii_lowercase().into_owned();
+        self.masters.remove(&id);
+        if !list.deleted() {
+            self.masters.insert(id, list);
+        }
+    }
+
+    fn insert(&mut self, plugin_index: usize, list: &'a T) {
+        let id = list.editor_id_ascii_lowercase().into_owned();
+        self.plugins
+            .entry(id)
+            .or_insert_with_key(|id| {
+                self.order.push(id.clone());
+                vec![]
+            })
+            .push((plugin_index, list));
+    }
+
+    fn merge(&self) -> Vec<MergedLeveledList<T>> {
+        self.order
+            .iter()
+            .filter_map(|id| merge_list(self.masters.get(id).copied(), &self.plugins[id]))
+            .collect()
+    }
+}
+
+fn merge_list<T: LeveledRecord>(master: Option<&T>, versions: &[(usize, &T)]) -> Option<MergedLeveledList<T>> {
+    if versions.last()?.1.deleted() {
+        return None;
+    }
+    let versions: Vec<_> = versions.iter().filter(|(_, list)| !list.deleted()).collect();
+
+    // Lists that are new to the plugins are compared against their first definition.
+    let base = master.unwrap_or(versions[0].1);
+
+    let base_counts = count(base.entries());
+    let mut changes = vec![];
+    let mut added_counts: HashMap<(String, u16), usize> = HashMap::new();
+    let mut removed_counts: HashMap<(String, u16), usize> = HashMap::new();
+
+    for &&(plugin_index, list) in &versions {
+        let counts = count(list.entries());
+        let added = difference(list.entries(), &base_counts);
+        let removed = difference(base.entries(), &counts);
+
+        for (entries, totals) in [(&added, &mut added_counts), (&removed, &mut removed_counts)] {
+            for (key, n) in count(entries) {
+                let total = totals.entry(key).or_default();
+                *total = (*total).max(n);
+            }
+        }
+
+        changes.push(LeveledListChanges {
+            plugin_index,
+            added,
+            removed,
+        });
+    }
+
+    let mut entries = remove_last(base.entries(), &removed_counts);
+    let mut added_so_far: HashMap<(String, u16), usize> = HashMap::new();
+    for change in &changes {
+        for (id, level) in &change.added {
+            let key = (id.to_ascii_lowercase(), *level);
+            let added = added_so_far.entry(key.clone()).or_default();
+            if *added < added_counts[&key] {
+                *added += 1;
+                entries.push((id.clone(), *level));
+            }
+        }
+    }
+    entries.sort_by_key(|(_, level)| *level);
+
+    let base_flags = base.list_flags();
+    let mut chance_none = base.chance_none();
+    let mut flags = base_flags;
+    for (_, version) in &versions {
+        if version.chance_none() != base.chance_none() {
+            chance_none = version.chance_none();
+        }
+        let differs = version.list_flags() ^ base_flags;
+        flags = (flags & !differs) | (version.list_flags() & differs);
+    }
+
+    let mut list = versions[versions.len() - 1].1.clone();
+    *list.entries_mut() = entries;
+    list.set_chance_none(chance_none);
+    list.set_list_flags(flags);
+
+    Some(MergedLeveledList { list, changes })
+}
+
+/// Count the occurrences of each entry.
+fn count(entries: &[(String, u16)]) -> HashMap<(String, u16), usize> {
+    let mut counts = HashMap::new();
+    for (id, level) in entries {
+        *counts.entry((id.to_ascii_lowercase(), *level)).or_default() += 1;
+    }
+    counts
+}
+
+/// The entries which occur more often in `entries` than in `counts`.
+fn difference(entries: &[(String, u16)], counts: &HashMap<(String, u16), usize>) -> Vec<(String, u16)> {
+    let mut seen: HashMap<(String, u16), usize> = HashMap::new();
+    entries
+        .iter()
+        .filter(|(id, level)| {
+            let key = (id.to_ascii_lowercase(), *level);
+            let limit = counts.get(&key).copied().unwrap_or_default();
+            let n = seen.entry(key).or_default();
+            *n += 1;
+            *n > limit
+        })
+        .cloned()
+        .collect()
+}
+
+/// Remove the given number of occurrences of each entry, starting from the last.
+fn remove_last(entries: &[(String, u16)], removed: &HashMap<(String, u16), usize>) -> Vec<(String, u16)> {
+    let mut remaining = count(entries);
+    for (key, n) in removed {
+        if let Some(remaining) = remaining.get_mut(key) {
+            *remaining = remaining.saturating_sub(*n);
+        }
+    }
+    entries
+        .iter()
+        .filter(|(id, level)| {
+            let key = (id.to_ascii_lowercase(), *level);
+            let remaining = remaining.entry(key).or_default();
+            let keep = *remaining > 0;
+            *remaining = remaining.saturating_sub(1);
+            keep
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(chance_none: u8, flags: LeveledItemFlags, items: &[(&str, u16)]) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            LeveledItem {
+                id: "random_weapon".into(),
+                chance_none,
+                leveled_item_flags: flags,
+                items: items.iter().map(|(id, level)| ((*id).into(), *level)).collect(),
+                ..default()
+            }
+            .into(),
+        );
+        plugin
+    }
+
+    fn entries(list: &LeveledItem) -> Vec<(&str, u16)> {
+        list.items.iter().map(|(id, level)| (id.as_str(), *level)).collect()
+    }
+
+    #[test]
+    fn merge_leveled_lists() {
+        let all_levels = LeveledItemFlags::CALCULATE_FROM_ALL_LEVELS;
+        let master = plugin(0, all_levels, &[("iron", 1), ("steel", 5), ("steel", 5)]);
+        let mod_1 = plugin(25, all_levels, &[("iron", 1), ("steel", 5), ("glass", 10)]);
+        let mod_2 = plugin(
+            0,
+            LeveledItemFlags::empty(),
+            &[("Iron", 1), ("steel", 5), ("steel", 5), ("Glass", 10), ("silver", 3)],
+        );
+
+        let merge = LeveledListMerge::from_plugins([&master], [&mod_1, &mod_2]);
+        assert!(merge.creatures.is_empty());
+
+        let merged = &merge.items[0];
+        assert_eq!(
+            entries(&merged.list),
+            [("iron", 1), ("silver", 3), ("steel", 5), ("glass", 10)]
+        );
+        assert_eq!(merged.list.chance_none, 25);
+        assert_eq!(merged.list.leveled_item_flags, LeveledItemFlags::empty());
+        assert_eq!(
+            merged.changes[0],
+            LeveledListChanges {
+                plugin_index: 0,
+                added: vec![("glass".into(), 10)],
+                removed: vec![("steel".into(), 5)],
+            }
+        );
+        assert_eq!(
+            merged.changes[1].added,
+            [("Glass".to_string(), 10), ("silver".to_string(), 3)]
+        );
+        assert!(merged.changes[1].removed.is_empty());
+
+        let patch = merge.to_patch();
+        assert_eq!(patch.objects.len(), 2);
+
+        // Without masters, the first definition is the base.
+        let merge = LeveledListMerge::from_plugins([], [&mod_1, &mod_2]);
+        let merged = &merge.items[0];
+        assert_eq!(
+            entries(&merged.list),
+            [("iron", 1), ("silver", 3), ("steel", 5), ("steel", 5), ("glass", 10)]
+        );
+        assert_eq!(merged.list.chance_none, 0);
+        assert!(merged.changes[0].added.is_empty() && merged.changes[0].removed.is_empty());
+        assert_eq!(merged.changes[1].added, [("steel".to_string(), 5), ("silver".to_string(), 3)]);
+
+        let merge = LeveledListMerge::from_plugins([], [&mod_2, &mod_1]);
+        assert_eq!(entries(&merge.items[0].list), [("Iron", 1), ("steel", 5), ("Glass", 10)]);
+
+        let mut deleted = plugin(0, all_levels, &[]);
+        deleted.objects[0].set_deleted(true);
+        assert!(LeveledListMerge::from_plugins([&master], [&mod_1, &deleted]).items.is_empty());
+    }
+}
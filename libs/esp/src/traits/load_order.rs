@@ -0,0 +1,100 @@
+use crate::prelude::*;
+
+/// The names of a load ordered set of plugins, for resolving the master indices of references.
+///
+/// The master index of a reference is relative to the plugin defining it: `0` refers to the
+/// plugin itself, and other values to the 1-based position of a master in its header. Load order
+/// indices instead refer to the 1-based position of a plugin in the load order, so that the
+/// references of different plugins can be compared.
+///
+/// A patch which lists every plugin of the load order as its masters, in the same order, can
+/// use load order indices as its own master indices.
+///
+#[derive(Clone, Debug, Default)]
+pub struct LoadOrder {
+    names: Vec<String>,
+}
+
+/// The load order indices of a plugin and its masters, see [`LoadOrder::push`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MasterIndices {
+    /// The load order index for each master index, or `None` if the master is not loaded.
+    indices: Vec<Option<u32>>,
+}
+
+impl LoadOrder {
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// The names of the plugins, in load order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Append the next plugin in load order, returning the load order indices of the plugin
+    /// itself and of the masters listed in its header.
+    ///
+    /// Masters are matched by name, ignoring case, against the plugins appended before.
+    ///
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn push(&mut self, name: &str, plugin: &Plugin) -> MasterIndices {
+        let position = |name: &str| {
+            let position = self.names.iter().rposition(|loaded| loaded.eq_ignore_ascii_case(name))?;
+            Some(position as u32 + 1)
+        };
+
+        let masters = plugin.objects_of_type::<Header>().flat_map(|header| &header.masters);
+        let mut indices: Vec<_> = masters.map(|(master, _)| position(master)).collect();
+
+        self.names.push(name.into());
+        indices.insert(0, Some(self.names.len() as u32));
+
+        MasterIndices { indices }
+    }
+}
+
+impl MasterIndices {
+    /// Convert the plugin relative indices of a reference to load order indices.
+    ///
+    /// Returns `None` if the reference belongs to a master which is not loaded.
+    ///
+    pub fn resolve(&self, (mast_index, refr_index): (u32, u32)) -> Option<(u32, u32)> {
+        let index = self.indices.get(usize::try_from(mast_index).ok()?).copied()??;
+        Some((index, refr_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(masters: &[&str]) -> Plugin {
+        let header = Header {
+            masters: masters.iter().map(|master| ((*master).into(), 0)).collect(),
+            ..default()
+        };
+        Plugin {
+            objects: vec![header.into()],
+        }
+    }
+
+    #[test]
+    fn resolve_master_indices() {
+        let mut load_order = LoadOrder::new();
+        let morrowind = load_order.push("Morrowind.esm", &plugin(&[]));
+        load_order.push("Tribunal.esm", &plugin(&["Morrowind.esm"]));
+        let bloodmoon = load_order.push("Bloodmoon.esm", &plugin(&["Morrowind.esm"]));
+        let patch = load_order.push("patch.esp", &plugin(&["bloodmoon.esm", "Morrowind.esm", "missing.esp"]));
+
+        assert_eq!(morrowind.resolve((0, 7)), Some((1, 7)));
+        assert_eq!(bloodmoon.resolve((0, 7)), Some((3, 7)));
+        assert_eq!(bloodmoon.resolve((1, 7)), Some((1, 7)));
+        assert_eq!(patch.resolve((0, 7)), Some((4, 7)));
+        assert_eq!(patch.resolve((1, 7)), Some((3, 7)));
+        assert_eq!(patch.resolve((2, 7)), Some((1, 7)));
+        assert_eq!(patch.resolve((3, 7)), None);
+        assert_eq!(patch.resolve((4, 7)), None);
+        assert_eq!(load_order.names().len(), 4);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::prelude::*;
+
+/// The record type an effect cost is calculated for, as the engine uses slightly different
+/// formulas for each.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EffectCostMethod {
+    Spell,
+    Enchantment,
+    Potion,
+}
+
+/// The stored number a [`CostIssue`] refers to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CostField {
+    /// `Spell::data.cost` or `Enchanting::data.cost`.
+    Cost,
+    /// `Enchanting::data.max_charge`.
+    Charge,
+    /// `Alchemy::data.value`.
+    Value,
+}
+
+/// A record whose stored cost disagrees with the engine's formula.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CostIssue {
+    pub id: String,
+    pub field: CostField,
+    pub stored: u32,
+    pub calculated: u32,
+    /// Whether the record has its auto calculate flag set, in which case the engine ignores the
+    /// stored number. Otherwise the stored number is used as is.
+    pub auto_calculated: bool,
+}
+
+/// Calculates spell costs, enchantment costs and charges, and potion values the same way the
+/// engine does for records with the auto calculate flag set.
+///
+/// Magic effects and game settings are taken from a load ordered set of plugins. Game settings
+/// which are not defined by any plugin use their vanilla values.
+///
+#[derive(Clone, Debug, Default)]
+pub struct MagicCostCalculator {
+    magic_effects: HashMap<EffectId, MagicEffectData>,
+    game_settings: GameSettings,
+}
+
+impl MagicCostCalculator {
+    /// Collect magic effects and game settings from plugins, which must be provided in load order.
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::default();
+
+        for object in plugins.into_iter().flat_map(|plugin| &plugin.objects) {
+            match object {
+                TES3Object::MagicEffect(effect) => {
+                    this.magic_effects.insert(effect.effect_id, effect.data.clone());
+                }
+                TES3Object::GameSetting(setting) => {
+                    this.game_settings.insert(setting);
+                }
+                _ => {}
+            }
+        }
+
+        this
+    }
+
+    /// The value of a numeric game setting, falling back to its vanilla value.
+    fn game_setting(&self, id: &str) -> f32 {
+        self.game_settings.get_f32(id).unwrap_or_default()
+    }
+
+    /// Calculate the cost of a single effect, excluding the range multiplier.
+    ///
+    /// Returns `None` if the magic effect is not defined.
+    ///
+    #[allow(clippy::cast_precision_loss)]
+    pub fn effect_cost(&self, effect: &Effect, method: EffectCostMethod) -> Option<f32> {
+        let id = EffectId::try_from(effect.magic_effect as i32).ok()?;
+        let data = self.magic_effects.get(&id)?;
+
+        let (mut min, mut max) = if data.flags.contains(MagicEffectFlags::NO_MAGNITUDE) {
+            (1, 1)
+        } else {
+            (effect.min_magnitude, effect.max_magnitude)
+        };
+        if method != EffectCostMethod::Enchantment {
+            min = min.max(1);
+            max = max.max(1);
+        }
+
+        let mut duration = if data.flags.contains(MagicEffectFlags::NO_DURATION) {
+            1
+        } else {
+            effect.duration
+        };
+        if !data.flags.contains(MagicEffectFlags::APPLIED_ONCE) {
+            duration = duration.max(1);
+        }
+
+        let (min_area, multiplier) = match method {
+            EffectCostMethod::Potion => (1, self.game_setting("iAlchemyMod")),
+            _ => (0, self.game_setting("fEffectCostMult")),
+        };
+
+        let mut cost = 0.5 * (min + max) as f32;
+        cost *= 0.1 * data.base_cost;
+        cost *= duration as f32;
+        cost += 0.05 * effect.area.max(min_area) as f32 * data.base_cost;
+        Some(cost * multiplier)
+    }
+
+    /// The total cost of a list of effects. Effects with a target range cost 50% more.
+    fn effects_cost(&self, effects: &[Effect], method: EffectCostMethod) -> Option<f32> {
+        effects.iter().try_fold(0.0, |total, effect| {
+            let mut cost = self.effect_cost(effect, method)?.max(0.0);
+            if effect.range == EffectRange::OnTarget && method != EffectCostMethod::Potion {
+                cost *= 1.5;
+            }
+            Some(total + cost)
+        })
+    }
+
+    /// Calculate the magicka cost of a spell.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn spell_cost(&self, spell: &Spell) -> Option<u32> {
+        let cost = self.effects_cost(&spell.effects, EffectCostMethod::Spell)?;
+        Some(cost.round() as u32)
+    }
+
+    /// Calculate the charge used each time an enchantment is cast.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn enchantment_cost(&self, enchantment: &Enchanting) -> Option<u32> {
+        let cost = self.effects_cost(&enchantment.effects, EffectCostMethod::Enchantment)?;
+        Some(cost as u32)
+    }
+
+    /// Calculate the maximum charge of an enchantment, a multiple of its cost depending on its type.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn enchantment_charge(&self, enchantment: &Enchanting) -> Option<u32> {
+        let multiplier = match enchantment.data.enchant_type {
+            EnchantType::CastOnce => "iMagicItemChargeOnce",
+            EnchantType::CastOnStrike => "iMagicItemChargeStrike",
+            EnchantType::CastWhenUsed => "iMagicItemChargeUse",
+            EnchantType::ConstantEffect => "iMagicItemChargeConst",
+        };
+        let cost = self.enchantment_cost(enchantment)?;
+        Some((cost as f32 * self.game_setting(multiplier)) as u32)
+    }
+
+    /// Calculate the gold value of a potion.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn potion_value(&self, potion: &Alchemy) -> Option<u32> {
+        let value = self.effects_cost(&potion.effects, EffectCostMethod::Potion)?;
+        Some(value.round() as u32)
+    }
+
+    /// Compare the stored costs of a spell, enchantment or potion against the formulas.
+    ///
+    /// Spells without the auto calculate flag are only checked if they are regular spells, as
+    /// abilities, powers and diseases are not cast with magicka. Records using magic effects
+    /// that are not defined are skipped.
+    ///
+    pub fn check(&self, object: &TES3Object) -> Vec<CostIssue> {
+        let mut issues = vec![];
+        let mut compare = |id: &str, field, stored, calculated: Option<u32>, auto_calculated| {
+            if let Some(calculated) = calculated.filter(|calculated| *calculated != stored) {
+                issues.push(CostIssue {
+                    id: id.into(),
+                    field,
+                    stored,
+                    calculated,
+                    auto_calculated,
+                });
+            }
+        };
+
+        match object {
+            TES3Object::Spell(spell) => {
+                let auto_calculated = spell.data.flags.contains(SpellFlags::AUTO_CALCULATE);
+                if auto_calculated || spell.data.spell_type == SpellType::Spell {
+                    let cost = self.spell_cost(spell);
+                    compare(&spell.id, CostField::Cost, spell.data.cost, cost, auto_calculated);
+                }
+            }
+            TES3Object::Enchanting(enchantment) => {
+                let auto_calculated = enchantment.data.flags.contains(EnchantingFlags::AUTO_CALCULATE);
+                let data = &enchantment.data;
+                let cost = self.enchantment_cost(enchantment);
+                compare(&enchantment.id, CostField::Cost, data.cost, cost, auto_calculated);
+                let charge = self.enchantment_charge(enchantment);
+                compare(&enchantment.id, CostField::Charge, data.max_charge, charge, auto_calculated);
+            }
+            TES3Object::Alchemy(potion) => {
+                let auto_calculated = potion.data.flags.contains(AlchemyFlags::AUTO_CALCULATE);
+                let value = self.potion_value(potion);
+                compare(&potion.id, CostField::Value, potion.data.value, value, auto_calculated);
+            }
+            _ => {}
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_and_check_costs() {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            MagicEffect {
+                effect_id: EffectId::FireDamage,
+                data: MagicEffectData {
+                    base_cost: 5.0,
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        plugin.objects.push(
+            MagicEffect {
+                effect_id: EffectId::RestoreHealth,
+                data: MagicEffectData {
+                    base_cost: 1.0,
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        let calculator = MagicCostCalculator::from_plugins([&plugin]);
+
+        let fire = Effect {
+            magic_effect: EffectId2::FireDamage,
+            range: EffectRange::OnTarget,
+            min_magnitude: 10,
+            max_magnitude: 20,
+            duration: 1,
+            area: 10,
+            ..default()
+        };
+        // 15 average magnitude * 0.5 base cost * 1 second + 0.05 * 10 area * 5 base cost,
+        // halved by the cost multiplier. The target range multiplier is applied per record.
+        assert_eq!(calculator.effect_cost(&fire, EffectCostMethod::Spell), Some(5.0));
+
+        let spell = Spell {
+            id: "fireball".into(),
+            effects: vec![fire.clone()],
+            data: SpellData {
+                cost: 10,
+                flags: SpellFlags::AUTO_CALCULATE,
+                ..default()
+            },
+            ..default()
+        };
+        assert_eq!(calculator.spell_cost(&spell), Some(8));
+
+        let enchantment = Enchanting {
+            id: "fire_strike".into(),
+            effects: vec![fire],
+            data: EnchantingData {
+                enchant_type: EnchantType::CastOnStrike,
+                cost: 7,
+                max_charge: 70,
+                ..default()
+            },
+            ..default()
+        };
+        assert_eq!(calculator.enchantment_cost(&enchantment), Some(7));
+        assert_eq!(calculator.enchantment_charge(&enchantment), Some(70));
+
+        let mut used = enchantment.clone();
+        used.data.enchant_type = EnchantType::CastWhenUsed;
+        used.data.max_charge = 35;
+        // iMagicItemChargeUse is 5, half the charge of a cast on strike enchantment.
+        assert_eq!(calculator.enchantment_charge(&used), Some(35));
+        assert_eq!(calculator.check(&used.into()), []);
+
+        let potion = Alchemy {
+            id: "healing".into(),
+            effects: vec![Effect {
+                magic_effect: EffectId2::RestoreHealth,
+                min_magnitude: 10,
+                max_magnitude: 10,
+                duration: 5,
+                ..default()
+            }],
+            data: AlchemyData { value: 20, ..default() },
+            ..default()
+        };
+        // 10 magnitude * 0.1 base cost * 5 seconds + 0.05 * 1 minimum area, doubled by iAlchemyMod.
+        assert_eq!(calculator.potion_value(&potion), Some(10));
+
+        assert_eq!(calculator.check(&enchantment.into()), []);
+        assert_eq!(
+            calculator.check(&spell.into()),
+            [CostIssue {
+                id: "fireball".into(),
+                field: CostField::Cost,
+                stored: 10,
+                calculated: 8,
+                auto_calculated: true,
+            }]
+        );
+        assert_eq!(calculator.check(&potion.into())[0].calculated, 10);
+    }
+
+    #[test]
+    fn check_manual_and_undefined_costs() {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            MagicEffect {
+                effect_id: EffectId::FireDamage,
+                data: MagicEffectData {
+                    base_cost: 5.0,
+                    ..default()
+                },
+                ..default()
+            }
+            .into(),
+        );
+        let calculator = MagicCostCalculator::from_plugins([&plugin]);
+
+        let fire = Effect {
+            magic_effect: EffectId2::FireDamage,
+            min_magnitude: 10,
+            max_magnitude: 10,
+            duration: 2,
+            ..default()
+        };
+        let mut spell = Spell {
+            id: "fire_touch".into(),
+            effects: vec![fire],
+            data: SpellData { cost: 10, ..default() },
+            ..default()
+        };
+        // 10 magnitude * 0.5 base cost * 2 seconds, halved by the cost multiplier.
+        assert_eq!(calculator.spell_cost(&spell), Some(5));
+
+        // regular spells without the auto calculate flag are reported, but marked as manual
+        assert_eq!(
+            calculator.check(&spell.clone().into()),
+            [CostIssue {
+                id: "fire_touch".into(),
+                field: CostField::Cost,
+                stored: 10,
+                calculated: 5,
+                auto_calculated: false,
+            }]
+        );
+
+        // abilities without the auto calculate flag are not cast with magicka
+        spell.data.spell_type = SpellType::Ability;
+        assert_eq!(calculator.check(&spell.clone().into()), []);
+
+        // records using undefined magic effects are skipped
+        spell.data.spell_type = SpellType::Spell;
+        spell.data.flags = SpellFlags::AUTO_CALCULATE;
+        spell.effects[0].magic_effect = EffectId2::FrostDamage;
+        assert_eq!(calculator.spell_cost(&spell), None);
+        assert_eq!(calculator.check(&spell.into()), []);
+    }
+}
@@ -0,0 +1,736 @@
+// rust std imports
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// internal imports
+use crate::prelude::*;
+
+/// A value that was changed differently by several versions of an object.
+///
+/// The value of the last version which changed it is used in the merged object.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeConflict {
+    /// The path of the value within the object, e.g. `data.level` or `inventory["iron dagger"]`.
+    pub path: String,
+    /// The indices of the versions which changed the value.
+    pub versions: Vec<usize>,
+}
+
+/// Three-way merging of several versions of a value against a common base.
+///
+/// Implemented for every record type and their fields. Structs are merged field by field, flags
+/// are merged bit by bit, and other values are replaced as a whole. Collections such as
+/// inventories, spell lists and faction reactions are merged by key, so that entries added,
+/// removed or changed by different versions are all kept. AI packages and travel destinations
+/// are merged as ordered lists, see [`merge_list`].
+///
+pub trait Merge: Sized {
+    /// Merge the changes each version made to `base`, recording values that were changed in
+    /// incompatible ways.
+    fn merge(base: &Self, versions: &[&Self], path: &str, conflicts: &mut Vec<MergeConflict>) -> Self;
+
+    /// Merge the changes each version made to `base`, returning the merged value and any
+    /// conflicts.
+    fn merge_versions(base: &Self, versions: &[&Self]) -> (Self, Vec<MergeConflict>) {
+        let mut conflicts = vec![];
+        let merged = Self::merge(base, versions, "", &mut conflicts);
+        (merged, conflicts)
+    }
+}
+
+/// The key identifying an element of a collection that is merged by key.
+pub trait MergeKey {
+    type Key: Clone + Debug + Eq + Hash;
+
+    fn merge_key(&self) -> Self::Key;
+}
+
+/// Spells, by id.
+impl MergeKey for String {
+    type Key = String;
+
+    fn merge_key(&self) -> Self::Key {
+        self.to_ascii_lowercase()
+    }
+}
+
+/// Inventory items, by id.
+impl MergeKey for (i32, FixedString<32>) {
+    type Key = String;
+
+    fn merge_key(&self) -> Self::Key {
+        self.1.to_ascii_lowercase()
+    }
+}
+
+/// Region sounds, by id.
+impl MergeKey for (FixedString<32>, u8) {
+    type Key = String;
+
+    fn merge_key(&self) -> Self::Key {
+        self.0.to_ascii_lowercase()
+    }
+}
+
+/// Leveled list entries, by id and level.
+impl MergeKey for (String, u16) {
+    type Key = (String, u16);
+
+    fn merge_key(&self) -> Self::Key {
+        (self.0.to_ascii_lowercase(), self.1)
+    }
+}
+
+impl MergeKey for FactionReaction {
+    type Key = String;
+
+    fn merge_key(&self) -> Self::Key {
+        self.faction.to_ascii_lowercase()
+    }
+}
+
+/// Merge values which can not be merged in parts.
+///
+/// Unchanged values and values changed the same way by every version merge cleanly. Otherwise
+/// the value of the last version which changed it is used.
+///
+pub fn merge_atomic<T: Clone + PartialEq>(base: &T, versions: &[&T], path: &str, conflicts: &mut Vec<MergeConflict>) -> T {
+    let versions: Vec<_> = versions.iter().map(|version| Some(*version)).collect();
+    resolve(Some(base), &versions, || path.into(), conflicts)
+        .unwrap_or(base)
+        .clone()
+}
+
+/// Merge collections whose elements are identified by a key.
+///
+/// Each key is merged as a single value, so that entries added, removed or changed by different
+/// versions are all kept. Repeated keys are numbered and merged separately. Entries keep the order
+/// of the base, followed by new entries in the order of the versions that added them.
+///
+pub fn merge_keyed<T, K>(
+    base: &[T],
+    versions: &[&Vec<T>],
+    path: &str,
+    conflicts: &mut Vec<MergeConflict>,
+    key: impl Fn(&T) -> K,
+) -> Vec<T>
+where
+    T: Clone + PartialEq,
+    K: Clone + Debug + Eq + Hash,
+{
+    fn keyed<T, K: Clone + Eq + Hash>(entries: &[T], key: impl Fn(&T) -> K) -> Vec<((K, usize), &T)> {
+        let mut counts: HashMap<K, usize> = HashMap::new();
+        let mut keyed = vec![];
+        for entry in entries {
+            let key = key(entry);
+            let count = counts.entry(key.clone()).or_default();
+            keyed.push(((key, *count), entry));
+            *count += 1;
+        }
+        keyed
+    }
+
+    let base = keyed(base, &key);
+    let versions: Vec<_> = versions.iter().map(|version| keyed(version, &key)).collect();
+
+    let mut order = vec![];
+    let mut seen = HashSet::new();
+    for (key, _) in base.iter().chain(versions.iter().flatten()) {
+        if seen.insert(key) {
+            order.push(key);
+        }
+    }
+
+    let base: HashMap<_, _> = base.iter().map(|(key, entry)| (key, *entry)).collect();
+    let versions: Vec<HashMap<_, _>> = versions
+        .iter()
+        .map(|version| version.iter().map(|(key, entry)| (key, *entry)).collect())
+        .collect();
+
+    order
+        .into_iter()
+        .filter_map(|key| {
+            let values: Vec<_> = versions.iter().map(|version| version.get(key).copied()).collect();
+            let path = || match key {
+                (key, 0) => format!("{path}[{key:?}]"),
+                (key, n) => format!("{path}[{key:?}#{n}]"),
+            };
+            resolve(base.get(key).copied(), &values, path, conflicts).cloned()
+        })
+        .collect()
+}
+
+/// Merge ordered lists whose elements have no identity of their own, such as AI packages.
+///
+/// Each version is aligned against the base by their longest common subsequence. Base elements
+/// removed by any version are removed, and elements inserted by the versions are kept at their
+/// position relative to the base. When several versions insert different elements at the same
+/// position, a conflict is recorded for that position, e.g. `ai_packages[2]`, and the elements of
+/// the last version are used.
+///
+pub fn merge_list<T: Clone + PartialEq>(
+    base: &[T],
+    versions: &[&Vec<T>],
+    path: &str,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Vec<T> {
+    let mut removed = vec![false; base.len()];
+    let mut insertions: Vec<Vec<Vec<T>>> = vec![vec![]; base.len() + 1];
+
+    for version in versions {
+        let matches = longest_common_subsequence(base, version);
+
+        // elements are inserted before the next base element kept by the version
+        let mut inserted = vec![vec![]; base.len() + 1];
+        let mut previous = (0, 0);
+        for (b, v) in matches.into_iter().chain([(base.len(), version.len())]) {
+            removed[previous.0..b].iter_mut().for_each(|removed| *removed = true);
+            inserted[b].extend_from_slice(&version[previous.1..v]);
+            previous = (b + 1, v + 1);
+        }
+
+        for (position, inserted) in inserted.into_iter().enumerate() {
+            insertions[position].push(inserted);
+        }
+    }
+
+    let mut merged = vec![];
+    let empty = vec![];
+    for (position, inserted) in insertions.iter().enumerate() {
+        let values: Vec<_> = inserted.iter().map(Some).collect();
+        let path = || format!("{path}[{position}]");
+        merged.extend(resolve(Some(&empty), &values, path, conflicts).into_iter().flatten().cloned());
+        if let Some(element) = base.get(position).filter(|_| !removed[position]) {
+            merged.push(element.clone());
+        }
+    }
+    merged
+}
+
+/// The index pairs of a longest common subsequence of `a` and `b`, in order.
+fn longest_common_subsequence<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// The path of a field of the value at `path`.
+#[doc(hidden)]
+pub fn merge_field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.into()
+    } else {
+        format!("{path}.{field}")
+    }
+}
+
+/// Choose between the versions of a value, where `None` means the value is absent.
+fn resolve<'a, T: PartialEq>(
+    base: Option<&'a T>,
+    versions: &[Option<&'a T>],
+    path: impl FnOnce() -> String,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<&'a T> {
+    let changed: Vec<usize> = (0..versions.len()).filter(|&i| versions[i] != base).collect();
+    let Some(&last) = changed.last() else {
+        return base;
+    };
+    if changed.iter().any(|&i| versions[i] != versions[last]) {
+        conflicts.push(MergeConflict {
+            path: path(),
+            versions: changed,
+        });
+    }
+    versions[last]
+}
+
+macro_rules! impl_merge_atomic {
+    ($($T:ty),* $(,)?) => {
+        $(
+            impl Merge for $T {
+                fn merge(base: &Self, versions: &[&Self], path: &str, conflicts: &mut Vec<MergeConflict>) -> Self {
+                    merge_atomic(base, versions, path, conflicts)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_merge_atomic_generic {
+    ($([$($P:tt)*] $T:ty),* $(,)?) => {
+        $(
+            impl<$($P)*> Merge for $T {
+                fn merge(base: &Self, versions: &[&Self], path: &str, conflicts: &mut Vec<MergeConflict>) -> Self {
+                    merge_atomic(base, versions, path, conflicts)
+                }
+            }
+        )*
+    };
+}
+
+impl_merge_atomic!(bool, u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, String);
+
+impl_merge_atomic_generic!(
+    [T: Clone + PartialEq] Vec<T>,
+    [T: Clone + PartialEq] Option<T>,
+    [T: Clone + PartialEq] Box<T>,
+    [T: Clone + PartialEq, const N: usize] [T; N],
+    [A: Clone + PartialEq, B: Clone + PartialEq] (A, B),
+    [A: Clone + PartialEq, B: Clone + PartialEq, C: Clone + PartialEq] (A, B, C),
+);
+
+impl<K, V> Merge for HashMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash,
+    V: Clone + Merge + PartialEq,
+{
+    /// Entries present in the base and every version are merged recursively. Otherwise entries
+    /// are added or removed as a whole.
+    fn merge(base: &Self, versions: &[&Self], path: &str, conflicts: &mut Vec<MergeConflict>) -> Self {
+        let keys: HashSet<&K> = base
+            .keys()
+            .chain(versions.iter().flat_map(|version| version.keys()))
+            .collect();
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let path = format!("{path}[{key:?}]");
+                let values: Vec<_> = versions.iter().map(|version| version.get(key)).collect();
+                let value = match (base.get(key), values.iter().copied().collect::<Option<Vec<_>>>()) {
+                    (Some(base), Some(values)) => V::merge(base, &values, &path, conflicts),
+                    (base, _) => resolve(base, &values, || path, conflicts)?.clone(),
+                };
+                Some((key.clone(), value))
+            })
+            .collect()
+    }
+}
+
+impl Merge for TES3Object {
+    /// Objects of the same type are merged field by field. Otherwise they are replaced as a whole.
+    fn merge(base: &Self, versions: &[&Self], path: &str, conflicts: &mut Vec<MergeConflict>) -> Self {
+        delegate! {
+            match base {
+                inner => merge_variant(inner, versions, path, conflicts)
+            }
+        }
+        .unwrap_or_else(|| merge_atomic(base, versions, path, conflicts))
+    }
+}
+
+fn merge_variant<T>(base: &T, versions: &[&TES3Object], path: &str, conflicts: &mut Vec<MergeConflict>) -> Option<TES3Object>
+where
+    T: Merge + Into<TES3Object>,
+    for<'a> &'a T: TryFrom<&'a TES3Object>,
+{
+    let versions: Vec<&T> = versions
+        .iter()
+        .map(|version| (*version).try_into().ok())
+        .collect::<Option<_>>()?;
+    Some(T::merge(base, &versions, path, conflicts).into())
+}
+
+/// An object defined by several plugins, merged field by field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergedObject {
+    pub object: TES3Object,
+    /// The indices of the plugins which defined the object, in load order.
+    pub plugin_indices: Vec<usize>,
+    /// Values changed differently by several plugins. Versions are given as plugin indices.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// The merged objects of a load ordered set of plugins.
+///
+/// Every object defined by more than one plugin is merged against its version in the masters.
+/// Objects the masters do not define are merged against their first definition, so the changes
+/// of later plugins are applied on top of the plugin which introduced the object. This is the
+/// same policy as [`LeveledListMerge`]. Objects whose last definition in load order deletes them
+/// are not merged.
+///
+/// Exterior cells are identified by their grid, and interior cells by their name. The references
+/// of cells are identified by load order indices, see [`LoadOrder`], so that references added by
+/// different plugins are kept apart.
+///
+/// Headers are skipped, as are dialogue topics and infos, whose order matters and which are
+/// merged by [`DialogueMerge`] instead.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectMerge {
+    pub objects: Vec<MergedObject>,
+    /// The file names of the masters and plugins, in load order.
+    pub masters: Vec<String>,
+}
+
+/// Identifies the versions of an object across plugins.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ObjectKey {
+    /// Cells, with interior names in lowercase. Exterior cells are identified by their grid
+    /// alone, as their editor ids include the region name.
+    Cell(CellId),
+    Object(&'static [u8; 4], String),
+}
+
+impl ObjectKey {
+    fn new(object: &TES3Object) -> Self {
+        match object {
+            TES3Object::Cell(cell) => Self::Cell(match cell.cell_id() {
+                CellId::Interior(name) => CellId::Interior(name.to_ascii_lowercase()),
+                exterior @ CellId::Exterior(..) => exterior,
+            }),
+            _ => Self::Object(object.tag(), object.editor_id_ascii_lowercase().into_owned()),
+        }
+    }
+}
+
+impl ObjectMerge {
+    /// Merge the objects of the given plugins against their masters. Both must be provided with
+    /// their file names, in load order.
+    ///
+    /// References of masters which are not loaded are skipped.
+    ///
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(
+        masters: impl IntoIterator<Item = (&'a str, &'a Plugin)>,
+        plugins: impl IntoIterator<Item = (&'a str, &'a Plugin)>,
+    ) -> Self {
+        let mut load_order = LoadOrder::new();
+
+        let mut bases = HashMap::new();
+        for (name, plugin) in masters {
+            let master_indices = load_order.push(name, plugin);
+            for object in plugin.objects.iter().filter(|object| is_mergeable(object)) {
+                let key = ObjectKey::new(object);
+                bases.remove(&key);
+                if !object.deleted() {
+                    bases.insert(key, with_load_order_indices(object, &master_indices));
+                }
+            }
+        }
+
+        let mut definitions: HashMap<_, Vec<(usize, Cow<'_, TES3Object>)>> = HashMap::new();
+        let mut order = vec![];
+        for (plugin_index, (name, plugin)) in plugins.into_iter().enumerate() {
+            let master_indices = load_order.push(name, plugin);
+            for object in plugin.objects.iter().filter(|object| is_mergeable(object)) {
+                definitions
+                    .entry(ObjectKey::new(object))
+                    .or_insert_with_key(|key| {
+                        order.push(key.clone());
+                        vec![]
+                    })
+                    .push((plugin_index, with_load_order_indices(object, &master_indices)));
+            }
+        }
+
+        let objects = order
+            .iter()
+            .filter_map(|key| {
+                let definitions = &definitions[key];
+                if definitions.len() < 2 || definitions.last()?.1.deleted() {
+                    return None;
+                }
+
+                let (plugin_indices, versions): (Vec<usize>, Vec<&TES3Object>) = definitions
+                    .iter()
+                    .filter(|(_, object)| !object.deleted())
+                    .map(|(plugin_index, object)| (*plugin_index, object.as_ref()))
+                    .unzip();
+                let base = bases.get(key).map_or(versions[0], AsRef::as_ref);
+
+                let (object, mut conflicts) = TES3Object::merge_versions(base, &versions);
+                for conflict in &mut conflicts {
+                    for version in &mut conflict.versions {
+                        *version = plugin_indices[*version];
+                    }
+                }
+
+                Some(MergedObject {
+                    object,
+                    plugin_indices,
+                    conflicts,
+                })
+            })
+            .collect();
+
+        Self {
+            objects,
+            masters: load_order.names().to_vec(),
+        }
+    }
+
+    /// Create a patch plugin containing every merged object.
+    ///
+    /// The patch lists the masters and plugins as its own masters, in load order, so that the
+    /// load order indices of references are valid master indices. Master file sizes are not
+    /// known and should be set by the caller.
+    ///
+    pub fn to_patch(&self) -> Plugin {
+        let header = Header {
+            masters: self.masters.iter().map(|name| (name.clone(), 0)).collect(),
+            ..default()
+        };
+        let mut plugin = Plugin::new();
+        plugin.objects.push(header.into());
+        plugin.objects.extend(self.objects.iter().map(|merged| merged.object.clone()));
+        plugin
+    }
+}
+
+fn is_mergeable(object: &TES3Object) -> bool {
+    !matches!(
+        object,
+        TES3Object::Header(_) | TES3Object::Dialogue(_) | TES3Object::DialogueInfo(_)
+    )
+}
+
+/// Replace the plugin relative master indices of a cell's references with load order indices,
+/// skipping references of masters which are not loaded.
+fn with_load_order_indices<'a>(object: &'a TES3Object, master_indices: &MasterIndices) -> Cow<'a, TES3Object> {
+    let TES3Object::Cell(cell) = object else {
+        return Cow::Borrowed(object);
+    };
+    let mut cell = cell.clone();
+    cell.references = std::mem::take(&mut cell.references)
+        .into_iter()
+        .filter_map(|(indices, mut reference)| {
+            let indices = master_indices.resolve(indices)?;
+            (reference.mast_index, reference.refr_index) = indices;
+            Some((indices, reference))
+        })
+        .collect();
+    Cow::Owned(cell.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npc(level: i16, inventory: &[(i32, &str)], spells: &[&str], npc_flags: NpcFlags) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects.push(
+            Npc {
+                id: "fargoth".into(),
+                inventory: inventory
+                    .iter()
+                    .map(|(count, id)| (*count, FixedString((*id).into())))
+                    .collect(),
+                spells: spells.iter().map(|spell| (*spell).into()).collect(),
+                npc_flags,
+                data: NpcData { level, ..default() },
+                ..default()
+            }
+            .into(),
+        );
+        plugin
+    }
+
+    #[test]
+    fn merge_objects() {
+        let master = npc(2, &[(1, "iron dagger"), (5, "gold_001")], &["fire bite"], NpcFlags::empty());
+        let mod_1 = npc(
+            10,
+            &[(2, "iron dagger"), (5, "gold_001"), (1, "ring")],
+            &["fire bite", "shield"],
+            NpcFlags::FEMALE,
+        );
+        let mod_2 = npc(2, &[(3, "Iron Dagger"), (5, "gold_001")], &[], NpcFlags::ESSENTIAL);
+
+        let merge = ObjectMerge::from_plugins([("Master.esm", &master)], [("Mod_1.esp", &mod_1), ("Mod_2.esp", &mod_2)]);
+        let [merged] = &merge.objects[..] else {
+            panic!("expected a single merged object");
+        };
+        let TES3Object::Npc(npc) = &merged.object else {
+            panic!("expected an npc");
+        };
+
+        assert_eq!(npc.data.level, 10);
+        assert_eq!(npc.npc_flags, NpcFlags::FEMALE | NpcFlags::ESSENTIAL);
+        assert_eq!(npc.spells, ["shield"]);
+        assert_eq!(
+            npc.inventory,
+            [
+                (3, FixedString("Iron Dagger".into())),
+                (5, FixedString("gold_001".into())),
+                (1, FixedString("ring".into()))
+            ]
+        );
+        assert_eq!(
+            merged.conflicts,
+            [MergeConflict {
+                path: "inventory[\"iron dagger\"]".into(),
+                versions: vec![0, 1],
+            }]
+        );
+        assert_eq!(merge.to_patch().objects.len(), 2);
+
+        assert!(
+            ObjectMerge::from_plugins([], [("Master.esm", &master), ("Mod_2.esp", &mod_2)]).objects[0]
+                .conflicts
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn merge_lists() {
+        let wander = |distance| AiPackage::Wander(AiWanderPackage { distance, ..default() });
+        let creature = |ai_packages: Vec<AiPackage>, cells: &[&str]| {
+            let mut plugin = Plugin::new();
+            plugin.objects.push(
+                Creature {
+                    id: "guar".into(),
+                    ai_packages,
+                    travel_destinations: cells
+                        .iter()
+                        .map(|cell| TravelDestination {
+                            cell: (*cell).into(),
+                            ..default()
+                        })
+                        .collect(),
+                    ..default()
+                }
+                .into(),
+            );
+            plugin
+        };
+
+        let master = creature(vec![wander(1), wander(2)], &["Balmora", "Vivec"]);
+        let mod_1 = creature(vec![wander(1), wander(2), wander(3)], &["Vivec"]);
+        let mod_2 = creature(vec![wander(0), wander(2), wander(4)], &["Balmora", "Vivec", "Gnisis"]);
+
+        let merge = ObjectMerge::from_plugins([("Master.esm", &master)], [("Mod_1.esp", &mod_1), ("Mod_2.esp", &mod_2)]);
+        let TES3Object::Creature(creature) = &merge.objects[0].object else {
+            panic!("expected a creature");
+        };
+
+        assert_eq!(creature.ai_packages, [wander(0), wander(2), wander(4)]);
+        assert_eq!(
+            creature
+                .travel_destinations
+                .iter()
+                .map(|destination| &destination.cell)
+                .collect::<Vec<_>>(),
+            ["Vivec", "Gnisis"]
+        );
+        assert_eq!(
+            merge.objects[0].conflicts,
+            [MergeConflict {
+                path: "ai_packages[2]".into(),
+                versions: vec![0, 1],
+            }]
+        );
+    }
+
+    #[test]
+    fn merge_cells() -> io::Result<()> {
+        let reference = |id: &str, x| Reference {
+            id: id.into(),
+            translation: [x, 0.0, 0.0],
+            ..default()
+        };
+        let plugin = |masters: &[&str], region: &str, references: Vec<((u32, u32), Reference)>| {
+            let header = Header {
+                masters: masters.iter().map(|master| ((*master).into(), 0)).collect(),
+                ..default()
+            };
+            let cell = Cell {
+                region: Some(region.into()),
+                data: CellData {
+                    flags: default(),
+                    grid: (0, 0),
+                },
+                references: references.into_iter().collect(),
+                ..default()
+            };
+            Plugin {
+                objects: vec![header.into(), cell.into()],
+            }
+        };
+
+        let master = plugin(
+            &[],
+            "Ascadian Isles Region",
+            vec![((0, 1), reference("rock", 0.0)), ((0, 2), reference("tree", 0.0))],
+        );
+        // both plugins change a master reference and add a new reference with the same indices
+        let mod_1 = plugin(
+            &["Morrowind.esm"],
+            "Bitter Coast Region",
+            vec![
+                ((1, 1), reference("rock", 100.0)),
+                ((1, 2), reference("tree", 0.0)),
+                ((0, 1), reference("flora_a", 0.0)),
+            ],
+        );
+        let mod_2 = plugin(
+            &["Morrowind.esm"],
+            "Ascadian Isles Region",
+            vec![
+                ((1, 1), reference("rock", 0.0)),
+                ((1, 2), reference("tree", 200.0)),
+                ((0, 1), reference("flora_b", 0.0)),
+            ],
+        );
+
+        let merge = ObjectMerge::from_plugins([("Morrowind.esm", &master)], [("Mod_1.esp", &mod_1), ("Mod_2.esp", &mod_2)]);
+        assert_eq!(merge.masters, ["Morrowind.esm", "Mod_1.esp", "Mod_2.esp"]);
+        let [merged] = &merge.objects[..] else {
+            panic!("expected a single merged cell");
+        };
+        let TES3Object::Cell(cell) = &merged.object else {
+            panic!("expected a cell");
+        };
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(cell.region.as_deref(), Some("Bitter Coast Region"));
+
+        let references = |cell: &Cell| {
+            let mut references: Vec<_> = cell
+                .references
+                .iter()
+                .map(|(indices, reference)| (*indices, reference.id.clone(), reference.translation[0]))
+                .collect();
+            references.sort_by_key(|(indices, ..)| *indices);
+            references
+        };
+        let expected = [
+            ((1, 1), "rock".to_string(), 100.0),
+            ((1, 2), "tree".to_string(), 200.0),
+            ((2, 1), "flora_a".to_string(), 0.0),
+            ((3, 1), "flora_b".to_string(), 0.0),
+        ];
+        assert_eq!(references(cell), expected);
+
+        // the patch refers to the references through its own masters
+        let mut patch = Plugin::new();
+        patch.load_bytes(&merge.to_patch().save_bytes()?)?;
+        let header = patch.objects_of_type::<Header>().next().unwrap();
+        assert_eq!(header.masters.len(), 3);
+        assert_eq!(references(patch.objects_of_type::<Cell>().next().unwrap()), expected);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use crate::prelude::*;
+
+/// A stage of a [`Quest`], defined by a journal info.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QuestStage {
+    pub index: i32,
+    pub info_id: String,
+    pub text: String,
+    pub finished: bool,
+    pub restart: bool,
+}
+
+/// A quest, built from a journal topic.
+///
+/// The quest name is given by the info with [`QuestState::Name`], and the remaining infos define
+/// the stages of the quest, using `data.disposition` as the journal index.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Quest {
+    pub id: String,
+    pub name: Option<String>,
+    /// The stages of the quest, ordered by index.
+    pub stages: Vec<QuestStage>,
+    /// The number of infos giving the quest a name, which should be exactly one.
+    pub name_count: usize,
+}
+
+/// A place that reads or sets the journal index of a quest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QuestReference {
+    /// A `Journal` filter of a dialogue info.
+    Filter {
+        topic_id: String,
+        info_id: String,
+        comparison: FilterComparison,
+        index: i32,
+    },
+    /// A `Journal` or `SetJournalIndex` call in the result script of a dialogue info.
+    InfoScript { topic_id: String, info_id: String, index: i32 },
+    /// A `Journal` or `SetJournalIndex` call in a script.
+    Script { script_id: String, index: i32 },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QuestIssue {
+    MissingName,
+    DuplicateName,
+    DuplicateIndex(i32),
+    MissingText(i32),
+    /// A stage that is set by a script, or compared for equality by a filter, but not defined.
+    UndefinedStage(QuestReference),
+}
+
+impl Quest {
+    /// Build a quest from a journal topic, returning `None` for other dialogue types.
+    pub fn from_topic(topic: &Topic) -> Option<Self> {
+        if topic.dialogue.dialogue_type != DialogueType2::Journal {
+            return None;
+        }
+
+        let mut this = Self {
+            id: topic.id().into(),
+            ..default()
+        };
+
+        for info in topic.infos.iter().filter(|info| !info.deleted()) {
+            if info.quest_state == Some(QuestState::Name) {
+                this.name_count += 1;
+                this.name.get_or_insert_with(|| info.text.clone());
+                continue;
+            }
+            this.stages.push(QuestStage {
+                index: info.data.disposition,
+                info_id: info.id.clone(),
+                text: info.text.clone(),
+                finished: info.quest_state == Some(QuestState::Finished),
+                restart: info.quest_state == Some(QuestState::Restart),
+            });
+        }
+
+        this.stages.sort_by_key(|stage| stage.index);
+
+        Some(this)
+    }
+
+    pub fn stage(&self, index: i32) -> Option<&QuestStage> {
+        self.stages.iter().find(|stage| stage.index == index)
+    }
+
+    /// The stages which mark the quest as finished.
+    pub fn finishing_stages(&self) -> impl Iterator<Item = &QuestStage> {
+        self.stages.iter().filter(|stage| stage.finished)
+    }
+
+    /// Check the quest for common mistakes, using the given references to the quest as
+    /// returned by [`Plugin::quest_references`].
+    pub fn validate(&self, references: &[QuestReference]) -> Vec<QuestIssue> {
+        let mut issues = vec![];
+
+        match self.name_count {
+            0 => issues.push(QuestIssue::MissingName),
+            1 => {}
+            _ => issues.push(QuestIssue::DuplicateName),
+        }
+
+        for pair in self.stages.windows(2) {
+            if pair[0].index == pair[1].index {
+                issues.push(QuestIssue::DuplicateIndex(pair[1].index));
+            }
+        }
+        issues.dedup();
+
+        for stage in &self.stages {
+            if stage.text.trim().is_empty() {
+                issues.push(QuestIssue::MissingText(stage.index));
+            }
+        }
+
+        for reference in references {
+            let exact = match reference {
+                QuestReference::Filter { comparison, .. } => {
+                    matches!(comparison, FilterComparison::Equal | FilterComparison::NotEqual)
+                }
+                QuestReference::InfoScript { .. } | QuestReference::Script { .. } => true,
+            };
+            // Index zero is the default state of every quest.
+            if exact && reference.index() != 0 && self.stage(reference.index()).is_none() {
+                issues.push(QuestIssue::UndefinedStage(reference.clone()));
+            }
+        }
+
+        issues
+    }
+}
+
+impl QuestReference {
+    pub const fn index(&self) -> i32 {
+        match self {
+            Self::Filter { index, .. } | Self::InfoScript { index, .. } | Self::Script { index, .. } => *index,
+        }
+    }
+
+    /// Whether this reference sets the journal index, rather than reading it.
+    pub const fn sets_stage(&self) -> bool {
+        !matches!(self, Self::Filter { .. })
+    }
+}
+
+impl Plugin {
+    /// Build the quests of all journal topics of this plugin.
+    pub fn quests(&self) -> Vec<Quest> {
+        self.topics().iter().filter_map(Quest::from_topic).collect()
+    }
+
+    /// Find all `Journal` filters, and all `Journal` or `SetJournalIndex` script calls, which
+    /// refer to the given quest id, ignoring case.
+    pub fn quest_references(&self, quest_id: &str) -> Vec<QuestReference> {
+        let mut references = vec![];
+
+        for topic in self.topics() {
+            for info in &topic.infos {
+                for filter in &info.filters {
+                    if filter.filter_type == FilterType::Journal && filter.id.eq_ignore_ascii_case(quest_id) {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let index = match filter.value {
+                            FilterValue::Integer(value) => value,
+                            FilterValue::Float(value) => value as i32,
+                        };
+                        references.push(QuestReference::Filter {
+                            topic_id: topic.id().into(),
+                            info_id: info.id.clone(),
+                            comparison: filter.comparison,
+                            index,
+                        });
+                    }
+                }
+                for index in journal_calls(&info.script_text, quest_id) {
+                    references.push(QuestReference::InfoScript {
+                        topic_id: topic.id().into(),
+                        info_id: info.id.clone(),
+                        index,
+                    });
+                }
+            }
+        }
+
+        for script in self.objects_of_type::<Script>() {
+            for index in journal_calls(&script.text, quest_id) {
+                references.push(QuestReference::Script {
+                    script_id: script.id.clone(),
+                    index,
+                });
+            }
+        }
+
+        references
+    }
+}
+
+/// Find the journal indices set for the given quest by `Journal` and `SetJournalIndex` calls.
+fn journal_calls(text: &str, quest_id: &str) -> Vec<i32> {
+    let (ast, _) = ScriptAst::parse(text, &ScriptFunctions::default());
+
+    let mut indices = vec![];
+    ast.walk(&mut |statement| {
+        let StatementKind::Call(call) = &statement.kind else {
+            return;
+        };
+        let function = &call.function.text;
+        if !function.eq_ignore_ascii_case("Journal") && !function.eq_ignore_ascii_case("SetJournalIndex") {
+            return;
+        }
+        if let [id, index, ..] = call.arguments.as_slice() {
+            let (ExpressionKind::Name(id) | ExpressionKind::String(id)) = &id.kind else {
+                return;
+            };
+            if let ExpressionKind::Number(index) = &index.kind {
+                if let (true, Ok(index)) = (id.eq_ignore_ascii_case(quest_id), index.parse()) {
+                    indices.push(index);
+                }
+            }
+        }
+    });
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, index: i32, quest_state: Option<QuestState>, text: &str) -> DialogueInfo {
+        DialogueInfo {
+            id: id.into(),
+            data: DialogueData {
+                dialogue_type: DialogueType::Journal,
+                disposition: index,
+                ..default()
+            },
+            quest_state,
+            text: text.into(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn quest_stages_and_references() {
+        let mut topic = Topic::new(Dialogue {
+            id: "A1_1_FindSpymaster".into(),
+            dialogue_type: DialogueType2::Journal,
+            ..default()
+        });
+        topic.push(info("0", 0, Some(QuestState::Name), "Report to Caius Cosades"));
+        topic.push(info("2", 10, None, "I should find Caius."));
+        topic.push(info("1", 1, None, "I've been released."));
+        topic.push(info("3", 10, Some(QuestState::Finished), ""));
+
+        let mut greeting = Topic::new(Dialogue {
+            id: "Greeting 1".into(),
+            dialogue_type: DialogueType2::Greeting,
+            ..default()
+        });
+        greeting.push(DialogueInfo {
+            id: "4".into(),
+            filters: vec!["Journal A1_1_FindSpymaster < 10".parse().unwrap()],
+            script_text: "Journal, \"a1_1_findspymaster\", 20 ; stage 20".into(),
+            ..default()
+        });
+
+        let mut plugin = Plugin::new();
+        plugin.set_topic(topic);
+        plugin.set_topic(greeting);
+        plugin.objects.push(
+            Script {
+                id: "CharGen".into(),
+                text: [
+                    "begin CharGen",
+                    "  SetJournalIndex A1_1_FindSpymaster 1",
+                    "  if ( GetJournalIndex A1_1_FindSpymaster < 30 )",
+                    "    player->Journal A1_1_FindSpymaster 30",
+                    "  endif",
+                    "end",
+                ]
+                .join("\n"),
+                ..default()
+            }
+            .into(),
+        );
+
+        let quests = plugin.quests();
+        assert_eq!(quests.len(), 1);
+
+        let quest = &quests[0];
+        assert_eq!(quest.name.as_deref(), Some("Report to Caius Cosades"));
+        assert_eq!(quest.stages.iter().map(|stage| stage.index).collect::<Vec<_>>(), [1, 10, 10]);
+        assert_eq!(quest.finishing_stages().count(), 1);
+
+        let references = plugin.quest_references("a1_1_findspymaster");
+        assert_eq!(references.len(), 4);
+        assert_eq!(references.iter().filter(|r| r.sets_stage()).count(), 3);
+
+        assert_eq!(
+            quest.validate(&references),
+            [
+                QuestIssue::DuplicateIndex(10),
+                QuestIssue::MissingText(10),
+                QuestIssue::UndefinedStage(QuestReference::InfoScript {
+                    topic_id: "Greeting 1".into(),
+                    info_id: "4".into(),
+                    index: 20,
+                }),
+                QuestIssue::UndefinedStage(QuestReference::Script {
+                    script_id: "CharGen".into(),
+                    index: 30,
+                }),
+            ]
+        );
+    }
+}
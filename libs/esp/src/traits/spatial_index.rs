@@ -0,0 +1,316 @@
+use crate::prelude::*;
+
+/// The size of each bucket of the index, matching the size of an exterior cell.
+const BUCKET_SIZE: f32 = 8192.0;
+
+/// A reference stored in a `SpatialIndex`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpatialEntry {
+    /// The exterior cell the reference belongs to, after applying `moved_cell`.
+    pub cell: (i32, i32),
+    pub indices: (u32, u32),
+    pub id: String,
+    pub translation: [f32; 3],
+}
+
+impl SpatialEntry {
+    #[allow(clippy::unnecessary_map_or)] // Option::is_none_or requires Rust 1.82
+    fn matches_id(&self, id: Option<&str>) -> bool {
+        id.map_or(true, |id| self.id.eq_ignore_ascii_case(id))
+    }
+
+    fn distance_squared(&self, point: [f32; 3]) -> f32 {
+        let [x, y, z] = self.translation;
+        let [px, py, pz] = point;
+        (z - pz).mul_add(z - pz, (y - py).mul_add(y - py, (x - px) * (x - px)))
+    }
+}
+
+/// A grid of exterior cell references, for finding references by position.
+///
+/// References are identified by load order indices, see [`LoadOrder`].
+///
+#[derive(Clone, Debug, Default)]
+pub struct SpatialIndex {
+    entries: HashMap<(u32, u32), SpatialEntry>,
+    buckets: HashMap<(i32, i32), HashSet<(u32, u32)>>,
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Index the exterior references of plugins and their file names, which must be provided in
+    /// load order.
+    ///
+    /// Later plugins replace the references of earlier ones, and deleted references are removed.
+    /// Deleted cells remove all references assigned to them. References of masters which are not
+    /// loaded are skipped.
+    ///
+    #[allow(single_use_lifetimes)]
+    pub fn from_plugins<'a>(plugins: impl IntoIterator<Item = (&'a str, &'a Plugin)>) -> Self {
+        let mut this = Self::new();
+
+        let mut load_order = LoadOrder::new();
+
+        for (name, plugin) in plugins {
+            let master_indices = load_order.push(name, plugin);
+
+            for cell in plugin.objects_of_type::<Cell>() {
+                let Some(grid) = cell.exterior_coords() else {
+                    continue;
+                };
+                if cell.deleted() {
+                    this.remove_cell(grid);
+                    continue;
+                }
+                for (&indices, reference) in &cell.references {
+                    if let Some(indices) = master_indices.resolve(indices) {
+                        this.insert(grid, indices, reference);
+                    }
+                }
+            }
+        }
+
+        this
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, indices: (u32, u32)) -> Option<&SpatialEntry> {
+        self.entries.get(&indices)
+    }
+
+    /// Insert or replace a reference contained by the exterior cell `grid`.
+    ///
+    /// References with a `moved_cell` are assigned to that cell, and deleted references are
+    /// removed from the index.
+    ///
+    pub fn insert(&mut self, grid: (i32, i32), indices: (u32, u32), reference: &Reference) {
+        self.remove(indices);
+
+        if reference.deleted() {
+            return;
+        }
+
+        let entry = SpatialEntry {
+            cell: reference.moved_cell.unwrap_or(grid),
+            indices,
+            id: reference.id.clone(),
+            translation: reference.translation,
+        };
+        self.buckets.entry(bucket(entry.translation)).or_default().insert(indices);
+        self.entries.insert(indices, entry);
+    }
+
+    pub fn remove(&mut self, indices: (u32, u32)) -> Option<SpatialEntry> {
+        let entry = self.entries.remove(&indices)?;
+
+        let key = bucket(entry.translation);
+        if let Some(bucket) = self.buckets.get_mut(&key) {
+            bucket.remove(&indices);
+            if bucket.is_empty() {
+                self.buckets.remove(&key);
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Remove all references assigned to the exterior cell `grid`.
+    pub fn remove_cell(&mut self, grid: (i32, i32)) {
+        let indices: Vec<_> = self
+            .entries
+            .values()
+            .filter(|entry| entry.cell == grid)
+            .map(|entry| entry.indices)
+            .collect();
+        for indices in indices {
+            self.remove(indices);
+        }
+    }
+
+    /// Move a reference to a new position, updating its cell to the one containing it.
+    ///
+    /// Returns `false` if the reference is not in the index.
+    ///
+    pub fn move_reference(&mut self, indices: (u32, u32), translation: [f32; 3]) -> bool {
+        let Some(mut entry) = self.remove(indices) else {
+            return false;
+        };
+
+        entry.cell = bucket(translation);
+        entry.translation = translation;
+
+        self.buckets.entry(entry.cell).or_default().insert(indices);
+        self.entries.insert(indices, entry);
+
+        true
+    }
+
+    /// Find references within `radius` of a point, nearest first.
+    ///
+    /// When `id` is given only references to that object are returned, compared
+    /// case-insensitively.
+    ///
+    pub fn within_radius(&self, center: [f32; 3], radius: f32, id: Option<&str>) -> Vec<&SpatialEntry> {
+        let [x, y, z] = center;
+        let min = [x - radius, y - radius, z - radius];
+        let max = [x + radius, y + radius, z + radius];
+
+        let mut entries: Vec<_> = self
+            .within_bounds(min, max, id)
+            .into_iter()
+            .map(|entry| (entry.distance_squared(center), entry))
+            .filter(|(distance, _)| *distance <= radius * radius)
+            .collect();
+
+        entries.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// Find references inside an axis aligned bounding box, sorted by their indices.
+    ///
+    /// When `id` is given only references to that object are returned, compared
+    /// case-insensitively.
+    ///
+    pub fn within_bounds(&self, min: [f32; 3], max: [f32; 3], id: Option<&str>) -> Vec<&SpatialEntry> {
+        let (min_x, min_y) = bucket(min);
+        let (max_x, max_y) = bucket(max);
+
+        let mut entries = vec![];
+
+        // iterate whichever is smaller: the buckets overlapping the box or the occupied buckets
+        // computed in i128, as the full i32 range of buckets overflows an i64
+        let span = (i128::from(max_x) - i128::from(min_x) + 1) * (i128::from(max_y) - i128::from(min_y) + 1);
+        let occupied = i128::try_from(self.buckets.len()).unwrap_or(i128::MAX);
+        let keys: Vec<_> = if span > occupied {
+            self.buckets
+                .keys()
+                .filter(|(x, y)| (min_x..=max_x).contains(x) && (min_y..=max_y).contains(y))
+                .copied()
+                .collect()
+        } else {
+            (min_x..=max_x).flat_map(|x| (min_y..=max_y).map(move |y| (x, y))).collect()
+        };
+
+        for key in keys {
+            let Some(bucket) = self.buckets.get(&key) else {
+                continue;
+            };
+            for indices in bucket {
+                let entry = &self.entries[indices];
+                let inside = (0..3).all(|i| (min[i]..=max[i]).contains(&entry.translation[i]));
+                if inside && entry.matches_id(id) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries.sort_by_key(|entry| entry.indices);
+        entries
+    }
+}
+
+/// The exterior cell containing a position.
+#[allow(clippy::cast_possible_truncation)]
+fn bucket([x, y, _]: [f32; 3]) -> (i32, i32) {
+    ((x / BUCKET_SIZE).floor() as i32, (y / BUCKET_SIZE).floor() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_queries() {
+        let reference = |id: &str, translation| Reference {
+            id: id.into(),
+            translation,
+            ..default()
+        };
+
+        let mut moved = reference("rock", [9000.0, 100.0, 0.0]);
+        moved.moved_cell = Some((1, 0));
+
+        let mut deleted = reference("rock", [50.0, 50.0, 0.0]);
+        deleted.deleted = Some(true);
+
+        let cell = Cell {
+            data: CellData {
+                flags: default(),
+                grid: (0, 0),
+            },
+            references: [
+                ((0, 1), reference("Rock", [100.0, 100.0, 0.0])),
+                ((0, 2), reference("tree", [200.0, 100.0, 0.0])),
+                ((0, 3), moved),
+                ((0, 4), deleted),
+            ]
+            .into_iter()
+            .collect(),
+            ..default()
+        };
+        let plugin = Plugin {
+            objects: vec![cell.clone().into()],
+        };
+
+        let mut index = SpatialIndex::from_plugins([("Plugin.esp", &plugin)]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get((1, 3)).map(|entry| entry.cell), Some((1, 0)));
+
+        let ids = |entries: Vec<&SpatialEntry>| entries.iter().map(|entry| entry.indices).collect::<Vec<_>>();
+        assert_eq!(ids(index.within_radius([190.0, 100.0, 0.0], 200.0, None)), [(1, 2), (1, 1)]);
+        assert_eq!(ids(index.within_radius([190.0, 100.0, 0.0], 200.0, Some("rock"))), [(1, 1)]);
+        assert_eq!(
+            ids(index.within_bounds([0.0, 0.0, -10.0], [10000.0, 200.0, 10.0], Some("ROCK"))),
+            [(1, 1), (1, 3)]
+        );
+
+        assert!(index.move_reference((1, 1), [-100.0, 100.0, 0.0]));
+        assert_eq!(index.get((1, 1)).map(|entry| entry.cell), Some((-1, 0)));
+        assert_eq!(ids(index.within_radius([190.0, 100.0, 0.0], 200.0, None)), [(1, 2)]);
+
+        // unbounded queries only visit occupied buckets
+        assert_eq!(index.within_radius([0.0; 3], f32::INFINITY, None).len(), 3);
+        assert_eq!(index.within_bounds([f32::MIN; 3], [f32::MAX; 3], None).len(), 3);
+
+        // master indices are resolved by name, and deleted cells remove their references
+        let mut patch = Cell {
+            references: HashMap::from([((1, 1), reference("rock", [150.0, 100.0, 0.0]))]),
+            ..cell
+        };
+        let header = Header {
+            masters: vec![("Plugin.esp".into(), 0)],
+            ..default()
+        };
+        let patch_plugin = Plugin {
+            objects: vec![header.clone().into(), patch.clone().into()],
+        };
+        let plugins = [
+            ("Other.esp", &Plugin::new()),
+            ("Plugin.esp", &plugin),
+            ("Patch.esp", &patch_plugin),
+        ];
+        let index = SpatialIndex::from_plugins(plugins);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get((2, 1)).map(|entry| entry.translation), Some([150.0, 100.0, 0.0]));
+
+        patch.references.clear();
+        patch.set_deleted(true);
+        let patch_plugin = Plugin {
+            objects: vec![header.into(), patch.into()],
+        };
+        let plugins = [("Plugin.esp", &plugin), ("Patch.esp", &patch_plugin)];
+        let index = SpatialIndex::from_plugins(plugins);
+        assert_eq!(index.get((1, 3)).map(|entry| entry.cell), Some((1, 0)));
+        assert_eq!(index.len(), 1);
+    }
+}
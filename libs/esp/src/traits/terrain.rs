@@ -0,0 +1,533 @@
+use crate::prelude::*;
+
+/// The number of vertex spacings along each edge of a landscape.
+const EDGE: i32 = 64;
+
+/// Height differences below this threshold are not considered seam mismatches.
+const SEAM_TOLERANCE: f32 = 1e-3;
+
+type Heights = Box<[[f32; 65]; 65]>;
+
+/// A vertex of a specific landscape, as `(grid, x, y)`.
+type LandscapeVertex = ((i32, i32), usize, usize);
+
+/// A world-level view over the landscapes of one or more plugins.
+///
+/// Each exterior grid maps to the landscape which wins the load order. Deleted landscapes, or
+/// landscapes without vertex heights, remove any previous landscape from their grid.
+///
+#[derive(Debug, Default)]
+pub struct Terrain<'a> {
+    pub landscapes: HashMap<(i32, i32), &'a mut Landscape>,
+}
+
+/// A read-only world-level view over the landscapes of one or more plugins.
+///
+/// Landscapes are collected like in [`Terrain`], but only borrowed immutably, for detecting
+/// seam mismatches and exporting images.
+///
+#[derive(Debug, Default)]
+pub struct TerrainView<'a> {
+    pub landscapes: HashMap<(i32, i32), &'a Landscape>,
+}
+
+/// A whole-world image of exterior cell data.
+///
+/// Pixels are stored row by row starting from the north-west corner. For vertex data, such as
+/// heightmaps, vertices shared by neighboring landscapes are stored only once, so each cell
+/// covers 64x64 pixels plus the trailing edge of the image.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TerrainImage<T> {
+    /// The grid of the south-west corner cell.
+    pub origin: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    /// The height represented by a heightmap pixel value of zero. Each step above zero
+    /// represents 8 units, the precision of encoded vertex heights. Unused by other images.
+    pub base_height: f32,
+    pub pixels: Vec<T>,
+}
+
+/// Describes an edge shared by two landscapes whose vertex heights disagree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeamMismatch {
+    pub grid: (i32, i32),
+    pub neighbor: (i32, i32),
+    pub mismatched_vertices: usize,
+    pub max_difference: f32,
+}
+
+impl<'a> Terrain<'a> {
+    pub fn from_plugin(plugin: &'a mut Plugin) -> Self {
+        Self::from_plugins([plugin])
+    }
+
+    /// Build the terrain from the given plugins, which must be provided in load order.
+    pub fn from_plugins(plugins: impl IntoIterator<Item = &'a mut Plugin>) -> Self {
+        let mut landscapes = HashMap::new();
+
+        for plugin in plugins {
+            for landscape in plugin.objects_of_type_mut::<Landscape>() {
+                if is_terrain(landscape) {
+                    landscapes.insert(landscape.grid, landscape);
+                } else {
+                    landscapes.remove(&landscape.grid);
+                }
+            }
+        }
+
+        Self { landscapes }
+    }
+
+    /// Borrow the landscapes immutably.
+    pub fn view(&self) -> TerrainView<'_> {
+        TerrainView {
+            landscapes: self
+                .landscapes
+                .iter()
+                .map(|(&grid, landscape)| (grid, &**landscape))
+                .collect(),
+        }
+    }
+
+    /// See [`TerrainView::seam_mismatches`].
+    pub fn seam_mismatches(&self) -> Vec<SeamMismatch> {
+        self.view().seam_mismatches()
+    }
+
+    /// Make the heights of all shared edge vertices match exactly.
+    ///
+    /// Mismatched vertices are set to the average height of all landscapes sharing them, rounded
+    /// to a multiple of 8, and the affected landscapes are re-encoded. As the encoded heights of a
+    /// landscape are relative to its first vertex, all heights of an affected landscape are
+    /// rounded to multiples of 8, moving them by up to 4 units, so that landscapes encoded
+    /// separately agree on their shared vertices. Vertices that already agree are left
+    /// unchanged. Afterwards all vertex normals are recalculated to include the vertices of
+    /// neighboring landscapes.
+    ///
+    /// Returns the number of landscapes whose vertex heights were modified.
+    ///
+    #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn stitch_seams(&mut self) -> usize {
+        let mut heights = self.view().decode_vertex_heights();
+
+        // collect the landscapes sharing each edge vertex, keyed by world vertex coordinates
+        let mut shared: HashMap<(i32, i32), Vec<LandscapeVertex>> = HashMap::new();
+        for &(gx, gy) in heights.keys() {
+            for y in 0..=EDGE {
+                for x in 0..=EDGE {
+                    if x == 0 || y == 0 || x == EDGE || y == EDGE {
+                        let key = (gx * EDGE + x, gy * EDGE + y);
+                        shared.entry(key).or_default().push(((gx, gy), x as usize, y as usize));
+                    }
+                }
+            }
+        }
+
+        let mut modified = HashSet::new();
+
+        for vertices in shared.values().filter(|vertices| vertices.len() > 1) {
+            let values: Vec<f32> = vertices.iter().map(|(grid, x, y)| heights[grid][*y][*x]).collect();
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            if max - min < SEAM_TOLERANCE {
+                continue;
+            }
+            let average = values.iter().sum::<f32>() / values.len() as f32;
+            let target = (average / 8.0).round() * 8.0;
+            for &(grid, x, y) in vertices {
+                if let Some(landscape_heights) = heights.get_mut(&grid) {
+                    landscape_heights[y][x] = target;
+                }
+                modified.insert(grid);
+            }
+        }
+
+        for grid in &modified {
+            if let (Some(landscape), Some(heights)) = (self.landscapes.get_mut(grid), heights.get_mut(grid)) {
+                heights
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|height| *height = (*height / 8.0).round() * 8.0);
+                landscape.encode_vertex_heights(heights);
+            }
+        }
+
+        self.recalculate_vertex_normals();
+
+        modified.len()
+    }
+
+    /// Recalculate the vertex normals of all landscapes, including the vertices of neighboring
+    /// landscapes for edge vertices.
+    pub fn recalculate_vertex_normals(&mut self) {
+        let heights = self.view().decode_vertex_heights();
+
+        for (&(gx, gy), landscape) in &mut self.landscapes {
+            landscape.recalculate_vertex_normals_with_neighbors(|x, y| {
+                let (wx, wy) = (gx * EDGE + x, gy * EDGE + y);
+                let neighbor = heights.get(&(wx.div_euclid(EDGE), wy.div_euclid(EDGE)))?;
+                #[allow(clippy::cast_sign_loss)]
+                Some(neighbor[wy.rem_euclid(EDGE) as usize][wx.rem_euclid(EDGE) as usize])
+            });
+        }
+    }
+
+    /// Recalculate the world map data of all landscapes from their vertex heights.
+    ///
+    /// See [`Landscape::recalculate_world_map_data`].
+    ///
+    pub fn recalculate_world_map_data(&mut self) {
+        for landscape in self.landscapes.values_mut() {
+            landscape.recalculate_world_map_data();
+        }
+    }
+
+    /// See [`TerrainView::export_heightmap`].
+    pub fn export_heightmap(&self) -> Option<TerrainImage<u16>> {
+        self.view().export_heightmap()
+    }
+
+    /// See [`TerrainView::export_vertex_colors`].
+    pub fn export_vertex_colors(&self) -> Option<TerrainImage<[u8; 3]>> {
+        self.view().export_vertex_colors()
+    }
+
+    /// Import the heights of all landscapes covered by a heightmap image.
+    ///
+    /// The image is expected to have the layout produced by [`Terrain::export_heightmap`].
+    /// Landscapes are never created, so cells without a landscape are ignored.
+    ///
+    /// Returns the number of landscapes that were updated, or an error if the number of pixels
+    /// does not match the image size.
+    ///
+    pub fn import_heightmap(&mut self, image: &TerrainImage<u16>) -> io::Result<usize> {
+        let count = self.import_image(image, |landscape, pixel_at| {
+            let mut heights: Heights = bytemuck::zeroed_box();
+            for (y, row) in heights.iter_mut().enumerate() {
+                for (x, height) in row.iter_mut().enumerate() {
+                    *height = f32::from(pixel_at(x, y)).mul_add(8.0, image.base_height);
+                }
+            }
+            landscape.encode_vertex_heights(&heights);
+        })?;
+
+        self.recalculate_vertex_normals();
+
+        Ok(count)
+    }
+
+    /// Import the vertex colors of all landscapes covered by a vertex color image.
+    ///
+    /// The image is expected to have the layout produced by [`Terrain::export_vertex_colors`].
+    /// Landscapes are never created, so cells without a landscape are ignored.
+    ///
+    /// Returns the number of landscapes that were updated, or an error if the number of pixels
+    /// does not match the image size.
+    ///
+    pub fn import_vertex_colors(&mut self, image: &TerrainImage<[u8; 3]>) -> io::Result<usize> {
+        self.import_image(image, |landscape, pixel_at| {
+            for (y, row) in landscape.vertex_colors.data.iter_mut().enumerate() {
+                for (x, color) in row.iter_mut().enumerate() {
+                    *color = pixel_at(x, y);
+                }
+            }
+            landscape.landscape_flags.insert(LandscapeFlags::USES_VERTEX_COLORS);
+        })
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn import_image<T: Copy>(
+        &mut self,
+        image: &TerrainImage<T>,
+        mut apply: impl FnMut(&mut Landscape, &dyn Fn(usize, usize) -> T),
+    ) -> io::Result<usize> {
+        let (width, height) = (image.width as usize, image.height as usize);
+        if image.pixels.len() != width * height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid terrain image: expected {} pixels for {width}x{height}, found {}",
+                    width * height,
+                    image.pixels.len()
+                ),
+            ));
+        }
+
+        let mut count = 0;
+
+        for (&grid, landscape) in &mut self.landscapes {
+            let (cx, cy) = (grid.0 - image.origin.0, grid.1 - image.origin.1);
+            if cx < 0 || cy < 0 {
+                continue;
+            }
+
+            let px = cx as usize * EDGE as usize;
+            let py = cy as usize * EDGE as usize;
+            if px + 65 > width || py + 65 > height {
+                continue;
+            }
+
+            let pixel_at = |x: usize, y: usize| image.pixels[(height - 1 - (py + y)) * width + px + x];
+            apply(landscape, &pixel_at);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+impl<'a> TerrainView<'a> {
+    pub fn from_plugin(plugin: &'a Plugin) -> Self {
+        Self::from_plugins([plugin])
+    }
+
+    /// Build the view from the given plugins, which must be provided in load order.
+    pub fn from_plugins(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut landscapes = HashMap::new();
+
+        for plugin in plugins {
+            for landscape in plugin.objects_of_type::<Landscape>() {
+                if is_terrain(landscape) {
+                    landscapes.insert(landscape.grid, landscape);
+                } else {
+                    landscapes.remove(&landscape.grid);
+                }
+            }
+        }
+
+        Self { landscapes }
+    }
+
+    /// Find all edges where the vertex heights of neighboring landscapes do not match.
+    ///
+    /// Each mismatch is reported once, from the landscape to the west or south of the seam.
+    ///
+    pub fn seam_mismatches(&self) -> Vec<SeamMismatch> {
+        let heights = self.decode_vertex_heights();
+
+        let mut grids: Vec<_> = heights.keys().copied().collect();
+        grids.sort_unstable();
+
+        let mut mismatches = vec![];
+
+        for grid @ (x, y) in grids {
+            let this = &heights[&grid];
+
+            let east = (x + 1, y);
+            if let Some(that) = heights.get(&east) {
+                let pairs = (0..65).map(|i| (this[i][64], that[i][0]));
+                mismatches.extend(seam_mismatch(grid, east, pairs));
+            }
+
+            let north = (x, y + 1);
+            if let Some(that) = heights.get(&north) {
+                let pairs = (0..65).map(|i| (this[64][i], that[0][i]));
+                mismatches.extend(seam_mismatch(grid, north, pairs));
+            }
+        }
+
+        mismatches
+    }
+
+    /// Export the heights of all landscapes as a single 16-bit grayscale image.
+    ///
+    /// Returns `None` if there are no landscapes. Heights outside the 16-bit range above the
+    /// lowest vertex are clamped. Areas without landscapes use a pixel value of zero.
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn export_heightmap(&self) -> Option<TerrainImage<u16>> {
+        let heights = self.decode_vertex_heights();
+
+        let base_height = heights.values().flat_map(|h| h.iter().flatten()).copied().reduce(f32::min)?;
+
+        self.export_image(base_height, 0, |grid, x, y| {
+            let height = heights[grid][y][x];
+            ((height - base_height) / 8.0).round().clamp(0.0, u16::MAX.into()) as u16
+        })
+    }
+
+    /// Export the vertex colors of all landscapes as a single RGB image.
+    ///
+    /// Returns `None` if there are no landscapes. Landscapes without vertex colors are white.
+    ///
+    pub fn export_vertex_colors(&self) -> Option<TerrainImage<[u8; 3]>> {
+        self.export_image(0.0, [0; 3], |grid, x, y| {
+            let landscape = &self.landscapes[grid];
+            if landscape.landscape_flags.contains(LandscapeFlags::USES_VERTEX_COLORS) {
+                landscape.vertex_colors.data[y][x]
+            } else {
+                [255; 3]
+            }
+        })
+    }
+
+    /// The bounds of all landscape grids, as `(min, max)`.
+    fn grid_bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        let mut grids = self.landscapes.keys();
+        let &first = grids.next()?;
+        Some(grids.fold((first, first), |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        }))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn export_image<T: Copy>(
+        &self,
+        base_height: f32,
+        empty: T,
+        pixel_at: impl Fn(&(i32, i32), usize, usize) -> T,
+    ) -> Option<TerrainImage<T>> {
+        let (min, max) = self.grid_bounds()?;
+
+        let width = ((max.0 - min.0 + 1) * EDGE + 1) as u32;
+        let height = ((max.1 - min.1 + 1) * EDGE + 1) as u32;
+
+        let mut pixels = vec![empty; width as usize * height as usize];
+
+        // visit grids in order, so shared edges are always written by the same landscape
+        let mut grids: Vec<_> = self.landscapes.keys().copied().collect();
+        grids.sort_unstable();
+
+        for grid in grids {
+            let px = ((grid.0 - min.0) * EDGE) as usize;
+            let py = ((grid.1 - min.1) * EDGE) as usize;
+            for y in 0..65 {
+                let row = height as usize - 1 - (py + y);
+                for x in 0..65 {
+                    pixels[row * width as usize + px + x] = pixel_at(&grid, x, y);
+                }
+            }
+        }
+
+        Some(TerrainImage {
+            origin: min,
+            width,
+            height,
+            base_height,
+            pixels,
+        })
+    }
+
+    fn decode_vertex_heights(&self) -> HashMap<(i32, i32), Heights> {
+        self.landscapes
+            .iter()
+            .map(|(&grid, landscape)| (grid, landscape.decode_vertex_heights()))
+            .collect()
+    }
+}
+
+/// Whether a landscape defines terrain, rather than removing it from its grid.
+fn is_terrain(landscape: &Landscape) -> bool {
+    !landscape.deleted()
+        && landscape
+            .landscape_flags
+            .contains(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS)
+}
+
+fn seam_mismatch(grid: (i32, i32), neighbor: (i32, i32), pairs: impl Iterator<Item = (f32, f32)>) -> Option<SeamMismatch> {
+    let mut mismatched_vertices = 0;
+    let mut max_difference = 0.0f32;
+
+    for (a, b) in pairs {
+        let difference = (a - b).abs();
+        if difference >= SEAM_TOLERANCE {
+            mismatched_vertices += 1;
+            max_difference = max_difference.max(difference);
+        }
+    }
+
+    (mismatched_vertices > 0).then_some(SeamMismatch {
+        grid,
+        neighbor,
+        mismatched_vertices,
+        max_difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat_landscape(grid: (i32, i32), height: f32) -> TES3Object {
+        let mut heights: Heights = bytemuck::zeroed_box();
+        heights.iter_mut().flatten().for_each(|z| *z = height);
+        let mut landscape = Landscape { grid, ..default() };
+        landscape.encode_vertex_heights(&heights);
+        landscape.into()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn stitch_seams() {
+        let mut plugin = Plugin {
+            objects: vec![flat_landscape((0, 0), 0.0), flat_landscape((1, 0), 64.0)],
+        };
+
+        let mismatches = TerrainView::from_plugin(&plugin).seam_mismatches();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].grid, (0, 0));
+        assert_eq!(mismatches[0].neighbor, (1, 0));
+        assert_eq!(mismatches[0].mismatched_vertices, 65);
+
+        let mut terrain = Terrain::from_plugin(&mut plugin);
+        assert_eq!(terrain.stitch_seams(), 2);
+        assert!(terrain.seam_mismatches().is_empty());
+
+        // the normals on both sides of the seam agree
+        let normals = |grid| terrain.landscapes[&grid].vertex_normals.data[32];
+        assert_eq!(normals((0, 0))[64], normals((1, 0))[0]);
+        drop(terrain);
+
+        // edges which already agree are left alone, even if not multiples of 8
+        for landscape in plugin.objects_of_type_mut::<Landscape>() {
+            landscape.vertex_heights.offset = 0.5;
+            landscape
+                .vertex_heights
+                .data
+                .iter_mut()
+                .flatten()
+                .for_each(|delta| *delta = 0);
+        }
+        assert_eq!(Terrain::from_plugin(&mut plugin).stitch_seams(), 0);
+        assert_eq!(
+            plugin.objects_of_type::<Landscape>().next().unwrap().vertex_heights.offset,
+            0.5
+        );
+
+        // landscapes with fractional offsets are rounded to multiples of 8 when stitched
+        let mut plugin = Plugin {
+            objects: vec![flat_landscape((0, 0), 3.0), flat_landscape((1, 0), 61.0)],
+        };
+        let mut terrain = Terrain::from_plugin(&mut plugin);
+        assert_eq!(terrain.stitch_seams(), 2);
+        assert!(terrain.seam_mismatches().is_empty());
+        assert_eq!(terrain.landscapes[&(0, 0)].vertex_heights.offset, 0.0);
+        assert_eq!(terrain.landscapes[&(1, 0)].decode_vertex_heights()[32][32], 64.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn heightmap_round_trip() {
+        let mut plugin = Plugin {
+            objects: vec![flat_landscape((-1, 2), -80.0), flat_landscape((0, 2), 160.0)],
+        };
+        let mut terrain = Terrain::from_plugin(&mut plugin);
+
+        let mut image = terrain.export_heightmap().unwrap();
+        assert_eq!((image.origin, image.width, image.height), ((-1, 2), 129, 65));
+        assert_eq!(image.base_height, -80.0);
+        assert_eq!(image.pixels[0], 0);
+        assert_eq!(image.pixels[128], 30);
+
+        let mut truncated = image.clone();
+        truncated.pixels.pop();
+        assert!(terrain.import_heightmap(&truncated).is_err());
+
+        image.pixels.fill(10);
+        assert_eq!(terrain.import_heightmap(&image).unwrap(), 2);
+        assert_eq!(terrain.landscapes[&(0, 2)].decode_vertex_heights()[5][5], 0.0);
+        assert!(terrain.seam_mismatches().is_empty());
+    }
+}
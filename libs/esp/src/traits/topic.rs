@@ -0,0 +1,370 @@
+// rust std imports
+use std::hash::BuildHasher;
+
+// internal imports
+use crate::prelude::*;
+
+/// A dialogue topic, grouping a [`Dialogue`] with its infos in file order.
+///
+/// Infos form a linked list through their `prev_id` and `next_id` fields. Plugins often only
+/// contain some of the infos of a topic, with the remaining infos defined by their masters.
+/// For that reason, editing operations follow the linked list semantics: links to infos which
+/// are not part of this topic are preserved.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Topic {
+    pub dialogue: Dialogue,
+    pub infos: Vec<DialogueInfo>,
+}
+
+impl Topic {
+    pub fn new(dialogue: Dialogue) -> Self {
+        Self { dialogue, infos: vec![] }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.dialogue.id
+    }
+
+    pub fn position(&self, info_id: &str) -> Option<usize> {
+        self.infos.iter().position(|info| info.id == info_id)
+    }
+
+    pub fn get(&self, info_id: &str) -> Option<&DialogueInfo> {
+        self.infos.iter().find(|info| info.id == info_id)
+    }
+
+    pub fn get_mut(&mut self, info_id: &str) -> Option<&mut DialogueInfo> {
+        self.infos.iter_mut().find(|info| info.id == info_id)
+    }
+
+    /// Append an info to the end of the topic, linking it after the current last info.
+    pub fn push(&mut self, mut info: DialogueInfo) {
+        if let Some(last) = self.infos.last_mut() {
+            info.prev_id.clone_from(&last.id);
+            info.next_id = std::mem::take(&mut last.next_id);
+            last.next_id.clone_from(&info.id);
+        } else {
+            info.prev_id.clear();
+            info.next_id.clear();
+        }
+        self.infos.push(info);
+    }
+
+    /// Insert an info directly after the info `anchor_id`.
+    ///
+    /// An empty anchor inserts the info at the start of the topic.
+    ///
+    /// The anchor may be an info that is not part of this topic, such as an info defined by a
+    /// master file. In that case the new info is placed before the info that follows the
+    /// anchor, or at the start of the topic.
+    ///
+    pub fn insert_after(&mut self, anchor_id: &str, mut info: DialogueInfo) {
+        let anchor = self.position(anchor_id);
+
+        info.prev_id = anchor_id.into();
+        info.next_id = match anchor {
+            Some(i) => std::mem::replace(&mut self.infos[i].next_id, info.id.clone()),
+            None => self
+                .infos
+                .iter()
+                .find(|other| other.prev_id == anchor_id)
+                .map(|other| other.id.clone())
+                .unwrap_or_default(),
+        };
+
+        if let Some(next) = self.infos.iter_mut().find(|other| other.id == info.next_id) {
+            next.prev_id.clone_from(&info.id);
+        }
+
+        let index = match anchor {
+            Some(i) => i + 1,
+            None => self.position(&info.next_id).unwrap_or(0),
+        };
+        self.infos.insert(index, info);
+    }
+
+    /// Insert an info directly before the info `anchor_id`.
+    ///
+    /// An empty anchor inserts the info at the end of the topic.
+    ///
+    /// The anchor may be an info that is not part of this topic, such as an info defined by a
+    /// master file. In that case the new info is placed after the info that precedes the
+    /// anchor, or at the end of the topic.
+    ///
+    pub fn insert_before(&mut self, anchor_id: &str, mut info: DialogueInfo) {
+        let anchor = self.position(anchor_id);
+
+        info.next_id = anchor_id.into();
+        info.prev_id = match anchor {
+            Some(i) => std::mem::replace(&mut self.infos[i].prev_id, info.id.clone()),
+            None => self
+                .infos
+                .iter()
+                .find(|other| other.next_id == anchor_id)
+                .map(|other| other.id.clone())
+                .unwrap_or_default(),
+        };
+
+        if let Some(prev) = self.infos.iter_mut().find(|other| other.id == info.prev_id) {
+            prev.next_id.clone_from(&info.id);
+        }
+
+        let index = match anchor {
+            Some(i) => i,
+            None => self.position(&info.prev_id).map_or(self.infos.len(), |i| i + 1),
+        };
+        self.infos.insert(index, info);
+    }
+
+    /// Remove an info, linking its previous and next infos to each other.
+    pub fn remove(&mut self, info_id: &str) -> Option<DialogueInfo> {
+        let info = self.infos.remove(self.position(info_id)?);
+
+        for other in &mut self.infos {
+            if other.next_id == info.id {
+                other.next_id.clone_from(&info.next_id);
+            }
+            if other.prev_id == info.id {
+                other.prev_id.clone_from(&info.prev_id);
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Move an info to directly after the info `anchor_id`.
+    ///
+    /// Returns `false` if the info is not part of this topic.
+    ///
+    pub fn move_after(&mut self, info_id: &str, anchor_id: &str) -> bool {
+        if info_id == anchor_id {
+            return self.position(info_id).is_some();
+        }
+        let Some(info) = self.remove(info_id) else {
+            return false;
+        };
+        self.insert_after(anchor_id, info);
+        true
+    }
+
+    /// Move an info to directly before the info `anchor_id`.
+    ///
+    /// Returns `false` if the info is not part of this topic.
+    ///
+    pub fn move_before(&mut self, info_id: &str, anchor_id: &str) -> bool {
+        if info_id == anchor_id {
+            return self.position(info_id).is_some();
+        }
+        let Some(info) = self.remove(info_id) else {
+            return false;
+        };
+        self.insert_before(anchor_id, info);
+        true
+    }
+
+    /// Rebuild all `prev_id`/`next_id` links from the order of `infos`.
+    ///
+    /// This is only appropriate for topics that contain all of their infos, as any links to
+    /// infos outside of this topic are removed.
+    ///
+    pub fn relink(&mut self) {
+        let ids: Vec<_> = self.infos.iter().map(|info| info.id.clone()).collect();
+        for (i, info) in self.infos.iter_mut().enumerate() {
+            info.prev_id = i.checked_sub(1).map(|j| ids[j].clone()).unwrap_or_default();
+            info.next_id = ids.get(i + 1).cloned().unwrap_or_default();
+        }
+    }
+
+    /// Generate a new info id which is not used by any info of this topic, or of any topic in
+    /// `plugin`.
+    ///
+    /// Ids follow the style of the Construction Set, which concatenates two random numbers.
+    ///
+    pub fn generate_info_id(&self, plugin: &Plugin) -> String {
+        let used: HashSet<&str> = plugin
+            .objects_of_type::<DialogueInfo>()
+            .chain(&self.infos)
+            .map(|info| info.id.as_str())
+            .collect();
+
+        let state = std::collections::hash_map::RandomState::new();
+        (0..u64::MAX)
+            .map(|i| {
+                let hash = state.hash_one((self.id(), self.infos.len(), i));
+                #[allow(clippy::cast_possible_truncation)]
+                let (a, b) = ((hash >> 32) as u32, hash as u32);
+                format!("{a}{b}")
+            })
+            .find(|id| !used.contains(id.as_str()))
+            .unwrap_or_default()
+    }
+}
+
+impl Plugin {
+    /// Group the dialogues and infos of this plugin into topics, in file order.
+    ///
+    /// Each topic contains the infos which directly follow its dialogue, as preserved by
+    /// [`Plugin::sort_objects`]. Infos separated from their dialogue by other objects are
+    /// ignored. All topic methods of `Plugin` group infos this way.
+    ///
+    pub fn topics(&self) -> Vec<Topic> {
+        (0..self.objects.len())
+            .filter(|&start| matches!(self.objects[start], TES3Object::Dialogue(_)))
+            .filter_map(|start| self.topic_at(start))
+            .collect()
+    }
+
+    /// Get the topic with the given id, ignoring case.
+    pub fn get_topic(&self, id: &str) -> Option<Topic> {
+        self.topic_at(self.topic_position(id)?)
+    }
+
+    /// Insert a topic, replacing the dialogue and infos of an existing topic with the same id.
+    ///
+    /// New topics are appended to the end of the objects list.
+    ///
+    pub fn set_topic(&mut self, topic: Topic) {
+        let range = self.topic_range(topic.id());
+
+        let objects = std::iter::once(topic.dialogue.into()).chain(topic.infos.into_iter().map(Into::into));
+
+        match range {
+            Some(range) => {
+                self.objects.splice(range, objects);
+            }
+            None => {
+                self.objects.extend(objects);
+            }
+        }
+    }
+
+    /// Remove the topic with the given id, ignoring case.
+    pub fn remove_topic(&mut self, id: &str) -> Option<Topic> {
+        let range = self.topic_range(id)?;
+
+        let mut objects = self.objects.drain(range);
+        let mut topic = Topic::new(objects.next()?.try_into().ok()?);
+        topic.infos.extend(objects.filter_map(|object| object.try_into().ok()));
+
+        Some(topic)
+    }
+
+    fn topic_position(&self, id: &str) -> Option<usize> {
+        self.objects.iter().position(|object| match object {
+            TES3Object::Dialogue(dialogue) => dialogue.id.eq_ignore_ascii_case(id),
+            _ => false,
+        })
+    }
+
+    fn topic_at(&self, start: usize) -> Option<Topic> {
+        let mut objects = self.objects[self.topic_range_at(start)].iter();
+        let mut topic = Topic::new(objects.next()?.clone().try_into().ok()?);
+        topic
+            .infos
+            .extend(objects.filter_map(|object| <&DialogueInfo>::try_from(object).ok()).cloned());
+        Some(topic)
+    }
+
+    fn topic_range(&self, id: &str) -> Option<std::ops::Range<usize>> {
+        Some(self.topic_range_at(self.topic_position(id)?))
+    }
+
+    /// The range of the dialogue at `start` and the infos directly following it.
+    fn topic_range_at(&self, start: usize) -> std::ops::Range<usize> {
+        let len = self.objects[start + 1..]
+            .iter()
+            .take_while(|object| matches!(object, TES3Object::DialogueInfo(_)))
+            .count();
+        start..start + 1 + len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, prev_id: &str, next_id: &str) -> DialogueInfo {
+        DialogueInfo {
+            id: id.into(),
+            prev_id: prev_id.into(),
+            next_id: next_id.into(),
+            ..default()
+        }
+    }
+
+    fn links(topic: &Topic) -> Vec<(&str, &str, &str)> {
+        topic
+            .infos
+            .iter()
+            .map(|info| (info.prev_id.as_str(), info.id.as_str(), info.next_id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn edit_partial_topic() {
+        // a plugin topic with one info inserted between two master infos
+        let mut topic = Topic::new(Dialogue {
+            id: "Background".into(),
+            ..default()
+        });
+        topic.infos.push(info("B", "master_a", "master_c"));
+
+        topic.insert_after("B", info("D", "", ""));
+        topic.insert_before("B", info("E", "", ""));
+        assert_eq!(
+            links(&topic),
+            [("master_a", "E", "B"), ("E", "B", "D"), ("B", "D", "master_c")]
+        );
+
+        topic.remove("B");
+        assert_eq!(links(&topic), [("master_a", "E", "D"), ("E", "D", "master_c")]);
+
+        assert!(topic.move_after("E", "D"));
+        assert_eq!(links(&topic), [("master_a", "D", "E"), ("D", "E", "master_c")]);
+
+        let mut plugin = Plugin::new();
+        plugin.objects.push(info("F", "", "").into());
+        let id = topic.generate_info_id(&plugin);
+        assert!(id.bytes().all(|b| b.is_ascii_digit()));
+        assert!(topic.position(&id).is_none());
+    }
+
+    #[test]
+    fn plugin_round_trip() {
+        let mut plugin = Plugin::new();
+        let mut topic = Topic::new(Dialogue {
+            id: "Greeting 0".into(),
+            dialogue_type: DialogueType2::Greeting,
+            ..default()
+        });
+        topic.push(info("1", "", ""));
+        topic.push(info("2", "", ""));
+        plugin.set_topic(topic.clone());
+
+        assert_eq!(plugin.topics(), [topic.clone()]);
+
+        topic.insert_after("", info("0", "", ""));
+        plugin.set_topic(topic.clone());
+        assert_eq!(plugin.objects.len(), 4);
+        assert_eq!(plugin.get_topic("greeting 0"), Some(topic.clone()));
+        assert_eq!(plugin.remove_topic("GREETING 0"), Some(topic));
+        assert!(plugin.objects.is_empty());
+
+        // infos separated from their dialogue are not part of the topic
+        plugin.objects.extend([
+            Dialogue {
+                id: "Background".into(),
+                ..default()
+            }
+            .into(),
+            info("3", "", "").into(),
+            Header::default().into(),
+            info("4", "", "").into(),
+        ]);
+        let topics = plugin.topics();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].infos, [info("3", "", "")]);
+        assert_eq!(plugin.get_topic("background").as_ref(), topics.first());
+    }
+}
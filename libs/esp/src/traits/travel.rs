@@ -0,0 +1,473 @@
+use crate::prelude::*;
+
+/// The size of an exterior cell, in world units.
+const CELL_SIZE: f32 = 8192.0;
+
+/// Something which transports the player to a `TravelDestination`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TravelSource {
+    /// A door reference, identified by its cell and load order indices.
+    Door { cell: CellId, indices: (u32, u32) },
+    /// The nth travel destination of an NPC or creature.
+    Actor { id: String, index: usize },
+}
+
+/// A travel destination resolved against the loaded cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TravelLink {
+    pub source: TravelSource,
+    pub destination: TravelDestination,
+    /// The cell the destination lands in, or `None` if no interior cell has the given name.
+    pub target: Option<CellId>,
+    /// The indices of the door in `target` which leads back to the source door, if any.
+    pub return_door: Option<(u32, u32)>,
+}
+
+/// A problem found with a travel destination.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TravelIssue {
+    /// The destination names an interior cell which does not exist.
+    MissingCell { source: TravelSource, cell: String },
+    /// The destination is an interior cell without any references.
+    EmptyCell { source: TravelSource, cell: CellId },
+    /// The destination is an exterior cell without a landscape.
+    NoLandscape { source: TravelSource, grid: (i32, i32) },
+    /// No door in the destination cell leads back to the source door.
+    OneWay { source: TravelSource, cell: CellId },
+}
+
+/// Cells, landscapes and travel destinations collected from a load ordered set of plugins.
+///
+/// References are identified by load order indices, see [`LoadOrder`].
+///
+#[derive(Debug, Default)]
+pub struct TravelNetwork<'a> {
+    /// Cells keyed by their id, with interior names in lowercase.
+    pub cells: HashMap<CellId, &'a Cell>,
+    /// The references of each cell, combined from all plugins, after applying `moved_cell`.
+    pub references: HashMap<CellId, HashMap<(u32, u32), &'a Reference>>,
+    pub landscapes: HashSet<(i32, i32)>,
+    /// NPC and creature travel destinations keyed by their lowercase id.
+    pub actors: HashMap<String, (&'a str, &'a [TravelDestination])>,
+}
+
+impl<'a> TravelNetwork<'a> {
+    /// Collect travel data from plugins and their file names, which must be provided in load
+    /// order.
+    ///
+    /// References with a `moved_cell` are assigned to that exterior cell. Deleted cells and
+    /// references remove any previous record. References of masters which are not loaded are
+    /// skipped.
+    ///
+    pub fn from_plugins(plugins: impl IntoIterator<Item = (&'a str, &'a Plugin)>) -> Self {
+        let mut this = Self::default();
+
+        let mut load_order = LoadOrder::new();
+
+        // the cell each reference is currently assigned to
+        let mut locations: HashMap<(u32, u32), CellId> = HashMap::new();
+
+        for (name, plugin) in plugins {
+            let master_indices = load_order.push(name, plugin);
+
+            for object in &plugin.objects {
+                match object {
+                    TES3Object::Cell(cell) => {
+                        let id = lowercase_id(cell.cell_id());
+                        if cell.deleted() {
+                            this.cells.remove(&id);
+                            for indices in this.references.remove(&id).into_iter().flat_map(HashMap::into_keys) {
+                                locations.remove(&indices);
+                            }
+                            continue;
+                        }
+                        for (&indices, reference) in &cell.references {
+                            let Some(indices) = master_indices.resolve(indices) else {
+                                continue;
+                            };
+                            if let Some(previous) = locations.remove(&indices) {
+                                if let Some(references) = this.references.get_mut(&previous) {
+                                    references.remove(&indices);
+                                }
+                            }
+                            if !reference.deleted() {
+                                let cell = reference
+                                    .moved_cell
+                                    .map_or_else(|| id.clone(), |(x, y)| CellId::Exterior(x, y));
+                                this.references.entry(cell.clone()).or_default().insert(indices, reference);
+                                locations.insert(indices, cell);
+                            }
+                        }
+                        this.references.entry(id.clone()).or_default();
+                        this.cells.insert(id, cell);
+                    }
+                    TES3Object::Landscape(landscape) => {
+                        if landscape.deleted() {
+                            this.landscapes.remove(&landscape.grid);
+                        } else {
+                            this.landscapes.insert(landscape.grid);
+                        }
+                    }
+                    TES3Object::Npc(npc) => {
+                        this.insert_actor(&npc.id, &npc.travel_destinations, npc.deleted());
+                    }
+                    TES3Object::Creature(creature) => {
+                        this.insert_actor(&creature.id, &creature.travel_destinations, creature.deleted());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        this
+    }
+
+    fn insert_actor(&mut self, id: &'a str, destinations: &'a [TravelDestination], deleted: bool) {
+        let key = id.to_ascii_lowercase();
+        if deleted {
+            self.actors.remove(&key);
+        } else {
+            self.actors.insert(key, (id, destinations));
+        }
+    }
+
+    /// Get a cell by id. Interior names are compared case-insensitively.
+    pub fn get_cell(&self, id: &CellId) -> Option<&'a Cell> {
+        self.cells.get(&lowercase_id(id.clone())).copied()
+    }
+
+    /// Whether any references are assigned to a cell.
+    fn has_references(&self, id: &CellId) -> bool {
+        self.references
+            .get(&lowercase_id(id.clone()))
+            .is_some_and(|references| !references.is_empty())
+    }
+
+    /// The cell a destination lands in.
+    ///
+    /// Destinations without a cell name are exterior, and always resolve to the grid containing
+    /// their translation, since the engine creates missing exterior cells on demand.
+    ///
+    pub fn resolve(&self, destination: &TravelDestination) -> Option<CellId> {
+        if destination.cell.is_empty() {
+            let [x, y, _] = destination.translation;
+            return Some(CellId::Exterior(grid_coord(x), grid_coord(y)));
+        }
+        self.get_cell(&CellId::Interior(destination.cell.clone()))
+            .filter(|cell| cell.is_interior())
+            .map(Cell::cell_id)
+    }
+
+    /// Resolve every door and actor travel destination, pairing doors with their return doors.
+    ///
+    /// A return door is a door in the target cell whose destination lands near the source door:
+    /// in the same interior cell, or within one cell's width of it outdoors. When several doors
+    /// qualify, the one landing closest to the source door is chosen.
+    ///
+    pub fn links(&self) -> Vec<TravelLink> {
+        let mut links = vec![];
+
+        for (cell, references) in &self.references {
+            for (&indices, reference) in references {
+                let Some(destination) = &reference.destination else {
+                    continue;
+                };
+                let source = TravelSource::Door {
+                    cell: self.get_cell(cell).map_or_else(|| cell.clone(), Cell::cell_id),
+                    indices,
+                };
+                let target = self.resolve(destination);
+                let return_door = target
+                    .as_ref()
+                    .and_then(|target| self.find_return_door(cell, reference, target));
+                links.push(TravelLink {
+                    source,
+                    destination: destination.clone(),
+                    target,
+                    return_door,
+                });
+            }
+        }
+
+        for (id, destinations) in self.actors.values() {
+            for (index, destination) in destinations.iter().enumerate() {
+                links.push(TravelLink {
+                    source: TravelSource::Actor {
+                        id: (*id).to_owned(),
+                        index,
+                    },
+                    destination: destination.clone(),
+                    target: self.resolve(destination),
+                    return_door: None,
+                });
+            }
+        }
+
+        links.sort_by(|a, b| a.source.cmp(&b.source));
+        links
+    }
+
+    fn find_return_door(&self, source_cell: &CellId, door: &Reference, target: &CellId) -> Option<(u32, u32)> {
+        let distance = |destination: &TravelDestination| {
+            let [x, y, z] = destination.translation;
+            let [dx, dy, dz] = door.translation;
+            (x - dx).hypot(y - dy).hypot(z - dz)
+        };
+
+        self.references
+            .get(&lowercase_id(target.clone()))?
+            .iter()
+            .filter_map(|(&indices, reference)| Some((indices, reference.destination.as_ref()?)))
+            .filter(|(_, destination)| match (source_cell, self.resolve(destination)) {
+                (CellId::Exterior(..), Some(CellId::Exterior(..))) => distance(destination) <= CELL_SIZE,
+                (CellId::Interior(_), Some(id)) => lowercase_id(id) == *source_cell,
+                _ => false,
+            })
+            .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+            .map(|(indices, _)| indices)
+    }
+
+    /// Find broken, one-way and void travel destinations.
+    ///
+    /// A destination is considered void only if it is an interior cell without any references;
+    /// interiors whose references are unreachable or lack floors are not detected. Actor travel is
+    /// not expected to have a way back, so only doors are checked for one-way links.
+    ///
+    pub fn issues(&self) -> Vec<TravelIssue> {
+        let mut issues = vec![];
+
+        for link in self.links() {
+            let source = link.source;
+            match link.target {
+                None => issues.push(TravelIssue::MissingCell {
+                    source,
+                    cell: link.destination.cell,
+                }),
+                Some(CellId::Exterior(x, y)) if !self.landscapes.contains(&(x, y)) => {
+                    issues.push(TravelIssue::NoLandscape { source, grid: (x, y) });
+                }
+                Some(cell @ CellId::Interior(_)) if !self.has_references(&cell) => {
+                    issues.push(TravelIssue::EmptyCell { source, cell });
+                }
+                Some(cell) if link.return_door.is_none() && matches!(source, TravelSource::Door { .. }) => {
+                    issues.push(TravelIssue::OneWay { source, cell });
+                }
+                Some(_) => {}
+            }
+        }
+
+        issues
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn grid_coord(value: f32) -> i32 {
+    (value / CELL_SIZE).floor() as i32
+}
+
+fn lowercase_id(id: CellId) -> CellId {
+    match id {
+        CellId::Interior(name) => CellId::Interior(name.to_ascii_lowercase()),
+        exterior @ CellId::Exterior(..) => exterior,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn door(translation: [f32; 3], cell: &str, destination: [f32; 3]) -> Reference {
+        Reference {
+            id: "door".into(),
+            translation,
+            destination: Some(TravelDestination {
+                translation: destination,
+                rotation: [0.0; 3],
+                cell: cell.into(),
+            }),
+            ..default()
+        }
+    }
+
+    /// A cell whose references are numbered from `first_index`, as indices are unique per plugin.
+    fn cell(name: &str, grid: (i32, i32), first_index: u32, references: Vec<Reference>) -> TES3Object {
+        let flags = if name.is_empty() { default() } else { CellFlags::IS_INTERIOR };
+        Cell {
+            name: name.into(),
+            data: CellData { flags, grid },
+            references: references
+                .into_iter()
+                .zip(first_index..)
+                .map(|(reference, index)| ((0, index), reference))
+                .collect(),
+            ..default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_travel_issues() {
+        let plugin = Plugin {
+            objects: vec![
+                cell(
+                    "",
+                    (0, 0),
+                    1,
+                    vec![
+                        door([100.0, 100.0, 0.0], "House", [0.0; 3]),
+                        door([200.0, 100.0, 0.0], "Nowhere", [0.0; 3]),
+                        door([300.0, 100.0, 0.0], "Void", [0.0; 3]),
+                    ],
+                ),
+                cell(
+                    "House",
+                    (0, 0),
+                    4,
+                    vec![
+                        door([0.0; 3], "", [120.0, 90.0, 0.0]),
+                        door([50.0, 0.0, 0.0], "", [9000.0, 0.0, 0.0]),
+                    ],
+                ),
+                cell("Void", (0, 0), 6, vec![]),
+                Landscape {
+                    grid: (0, 0),
+                    ..default()
+                }
+                .into(),
+            ],
+        };
+
+        let network = TravelNetwork::from_plugins([("Plugin.esp", &plugin)]);
+        let links = network.links();
+
+        let outside = TravelSource::Door {
+            cell: CellId::Exterior(0, 0),
+            indices: (1, 1),
+        };
+        let link = links.iter().find(|link| link.source == outside).unwrap();
+        assert_eq!(link.target, Some(CellId::Interior("House".into())));
+        assert_eq!(link.return_door, Some((1, 4)));
+
+        let house = |index| TravelSource::Door {
+            cell: CellId::Interior("House".into()),
+            indices: (1, index),
+        };
+        assert_eq!(
+            network.issues(),
+            [
+                TravelIssue::NoLandscape {
+                    source: house(5),
+                    grid: (1, 0),
+                },
+                TravelIssue::MissingCell {
+                    source: TravelSource::Door {
+                        cell: CellId::Exterior(0, 0),
+                        indices: (1, 2),
+                    },
+                    cell: "Nowhere".into(),
+                },
+                TravelIssue::EmptyCell {
+                    source: TravelSource::Door {
+                        cell: CellId::Exterior(0, 0),
+                        indices: (1, 3),
+                    },
+                    cell: CellId::Interior("Void".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_order() {
+        let landscape = |grid| Landscape { grid, ..default() }.into();
+        let master = Plugin {
+            objects: vec![
+                cell(
+                    "",
+                    (0, 0),
+                    1,
+                    vec![
+                        door([100.0, 100.0, 0.0], "House", [0.0; 3]),
+                        door([200.0, 100.0, 0.0], "Shack", [0.0; 3]),
+                    ],
+                ),
+                cell("House", (0, 0), 3, vec![door([0.0; 3], "", [16484.0, 100.0, 0.0])]),
+                cell("Shack", (0, 0), 4, vec![Reference::default()]),
+                landscape((0, 0)),
+                landscape((2, 0)),
+            ],
+        };
+
+        // the master door is moved to another cell, and a new door replaces it
+        let mut moved = door([16484.0, 100.0, 0.0], "House", [0.0; 3]);
+        moved.moved_cell = Some((2, 0));
+        let exterior = Cell {
+            data: CellData {
+                flags: default(),
+                grid: (0, 0),
+            },
+            references: [((2, 1), moved), ((0, 1), door([300.0, 100.0, 0.0], "House", [0.0; 3]))]
+                .into_iter()
+                .collect(),
+            ..default()
+        };
+        let mut shack = Cell {
+            name: "Shack".into(),
+            data: CellData {
+                flags: CellFlags::IS_INTERIOR,
+                grid: (0, 0),
+            },
+            ..default()
+        };
+        shack.set_deleted(true);
+        // the masters are listed in a different order than they are loaded in
+        let other = Plugin::new();
+        let header = Header {
+            masters: vec![("Other.esm".into(), 0), ("Master.esm".into(), 0)],
+            ..default()
+        };
+        let plugin = Plugin {
+            objects: vec![header.into(), exterior.into(), shack.into()],
+        };
+
+        let network = TravelNetwork::from_plugins([("Master.esm", &master), ("Other.esm", &other), ("Plugin.esp", &plugin)]);
+
+        let keys = |cell: CellId| {
+            let mut keys: Vec<_> = network.references[&cell].keys().copied().collect();
+            keys.sort_unstable();
+            keys
+        };
+        assert_eq!(keys(CellId::Exterior(0, 0)), [(1, 2), (3, 1)]);
+        assert_eq!(keys(CellId::Exterior(2, 0)), [(1, 1)]);
+        assert!(network.get_cell(&CellId::Interior("shack".into())).is_none());
+
+        let links = network.links();
+        let moved = TravelSource::Door {
+            cell: CellId::Exterior(2, 0),
+            indices: (1, 1),
+        };
+        let link = links.iter().find(|link| link.source == moved).unwrap();
+        assert_eq!(link.return_door, Some((1, 3)));
+
+        let new_door = TravelSource::Door {
+            cell: CellId::Exterior(0, 0),
+            indices: (3, 1),
+        };
+        assert_eq!(
+            network.issues(),
+            [
+                TravelIssue::MissingCell {
+                    source: TravelSource::Door {
+                        cell: CellId::Exterior(0, 0),
+                        indices: (1, 2),
+                    },
+                    cell: "Shack".into(),
+                },
+                TravelIssue::OneWay {
+                    source: new_door,
+                    cell: CellId::Interior("House".into()),
+                },
+            ]
+        );
+    }
+}
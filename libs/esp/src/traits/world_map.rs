@@ -0,0 +1,246 @@
+use crate::prelude::*;
+
+/// The number of pixels along each edge of a cell, matching the resolution of `WorldMapData`.
+const CELL_PIXELS: usize = 9;
+
+const SHALLOW_WATER: [f32; 3] = [38.0, 70.0, 112.0];
+const DEEP_WATER: [f32; 3] = [14.0, 38.0, 80.0];
+const LOWLAND: [f32; 3] = [81.0, 96.0, 52.0];
+const HIGHLAND: [f32; 3] = [196.0, 186.0, 164.0];
+
+/// A view over the exterior cells of one or more plugins, for rendering world map images.
+///
+/// Each exterior grid maps to the records which win the load order.
+///
+#[derive(Debug, Default)]
+pub struct WorldMap<'a> {
+    pub cells: HashMap<(i32, i32), &'a Cell>,
+    pub landscapes: HashMap<(i32, i32), &'a Landscape>,
+    /// Regions keyed by their lowercase id.
+    pub regions: HashMap<String, &'a Region>,
+}
+
+impl<'a> WorldMap<'a> {
+    pub fn from_plugin(plugin: &'a Plugin) -> Self {
+        Self::from_plugins([plugin])
+    }
+
+    /// Build the world map from the given plugins, which must be provided in load order.
+    ///
+    /// Deleted cells and landscapes remove any previous record from their grid.
+    ///
+    pub fn from_plugins(plugins: impl IntoIterator<Item = &'a Plugin>) -> Self {
+        let mut this = Self::default();
+
+        for plugin in plugins {
+            for object in &plugin.objects {
+                match object {
+                    TES3Object::Cell(cell) => {
+                        if let Some(grid) = cell.exterior_coords() {
+                            if cell.deleted() {
+                                this.cells.remove(&grid);
+                            } else {
+                                this.cells.insert(grid, cell);
+                            }
+                        }
+                    }
+                    TES3Object::Landscape(landscape) => {
+                        if landscape.deleted() {
+                            this.landscapes.remove(&landscape.grid);
+                        } else {
+                            this.landscapes.insert(landscape.grid, landscape);
+                        }
+                    }
+                    TES3Object::Region(region) => {
+                        this.regions.insert(region.id.to_ascii_lowercase(), region);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        this
+    }
+
+    /// Render an image of all exterior cells, with 9x9 pixels per cell.
+    ///
+    /// Terrain is shaded from the `WorldMapData` of each landscape, so it should be up to date
+    /// with the vertex heights (see [`Landscape::recalculate_world_map_data`]). Cells without a
+    /// landscape are rendered as deep water.
+    ///
+    /// Each cell is then tinted by its map color, or the map color of its region when the cell
+    /// does not define one, using the given `region_opacity` in the range `0.0..=1.0`.
+    ///
+    /// Returns `None` if there are no exterior cells.
+    ///
+    #[allow(clippy::cast_sign_loss)]
+    pub fn render(&self, region_opacity: f32) -> Option<TerrainImage<[u8; 3]>> {
+        let (min, max) = self.grid_bounds()?;
+
+        let columns = (max.0 - min.0 + 1) as usize;
+        let rows = (max.1 - min.1 + 1) as usize;
+
+        let width = columns * CELL_PIXELS;
+        let height = rows * CELL_PIXELS;
+
+        let mut pixels = vec![to_rgb(DEEP_WATER); width * height];
+
+        for row in 0..rows {
+            for column in 0..columns {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let grid = (min.0 + column as i32, min.1 + row as i32);
+
+                let landscape = self.landscapes.get(&grid);
+                let tint = self.map_color(grid);
+
+                for y in 0..CELL_PIXELS {
+                    for x in 0..CELL_PIXELS {
+                        let mut color =
+                            landscape.map_or(DEEP_WATER, |landscape| terrain_color(landscape.world_map_data.data[y][x]));
+                        if let Some(tint) = tint {
+                            color = lerp(color, tint, region_opacity.clamp(0.0, 1.0));
+                        }
+                        let py = height - 1 - (row * CELL_PIXELS + y);
+                        let px = column * CELL_PIXELS + x;
+                        pixels[py * width + px] = to_rgb(color);
+                    }
+                }
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        Some(TerrainImage {
+            origin: min,
+            width: width as u32,
+            height: height as u32,
+            base_height: 0.0,
+            pixels,
+        })
+    }
+
+    /// The map color of the cell at `grid`, falling back to the map color of its region.
+    fn map_color(&self, grid: (i32, i32)) -> Option<[f32; 3]> {
+        let cell = self.cells.get(&grid)?;
+        let [r, g, b, _] = cell.map_color.or_else(|| {
+            let region = cell.region.as_ref()?;
+            Some(self.regions.get(&region.to_ascii_lowercase())?.map_color)
+        })?;
+        Some([r.into(), g.into(), b.into()])
+    }
+
+    /// The bounds of all exterior grids, as `(min, max)`.
+    fn grid_bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        let mut grids = self.cells.keys().chain(self.landscapes.keys());
+        let &first = grids.next()?;
+        Some(grids.fold((first, first), |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        }))
+    }
+}
+
+/// Shade a `WorldMapData` value, using blues below sea level and greens to browns above.
+fn terrain_color(value: i8) -> [f32; 3] {
+    if value < 0 {
+        lerp(SHALLOW_WATER, DEEP_WATER, f32::from(value) / f32::from(i8::MIN))
+    } else {
+        lerp(LOWLAND, HIGHLAND, f32::from(value) / f32::from(i8::MAX))
+    }
+}
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| (b[i] - a[i]).mul_add(t, a[i]))
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_rgb(color: [f32; 3]) -> [u8; 3] {
+    color.map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exterior(grid: (i32, i32), map_color: Option<[u8; 4]>) -> TES3Object {
+        Cell {
+            data: CellData { flags: default(), grid },
+            map_color,
+            ..default()
+        }
+        .into()
+    }
+
+    fn flat_landscape(grid: (i32, i32), height: f32) -> Landscape {
+        let mut heights: Box<[[f32; 65]; 65]> = bytemuck::zeroed_box();
+        heights.iter_mut().flatten().for_each(|z| *z = height);
+        let mut landscape = Landscape { grid, ..default() };
+        landscape.encode_vertex_heights(&heights);
+        landscape
+    }
+
+    #[test]
+    fn render_world_map() {
+        let mut landscape = Landscape {
+            grid: (0, 0),
+            ..default()
+        };
+        landscape.world_map_data.data[0][1] = -64;
+        landscape.world_map_data.data[8][0] = i8::MAX;
+
+        let mut deleted = Cell {
+            data: CellData {
+                flags: default(),
+                grid: (2, 0),
+            },
+            ..default()
+        };
+        deleted.set_deleted(true);
+
+        let master = Plugin {
+            objects: vec![
+                exterior((0, 0), None),
+                landscape.into(),
+                exterior((1, 0), Some([255, 0, 0, 0])),
+                exterior((2, 0), None),
+            ],
+        };
+        let plugin = Plugin {
+            objects: vec![deleted.into()],
+        };
+
+        let map = WorldMap::from_plugins([&master, &plugin]);
+        assert!(!map.cells.contains_key(&(2, 0)));
+
+        let image = map.render(0.5).unwrap();
+        assert_eq!((image.origin, image.width, image.height), ((0, 0), 18, 9));
+
+        // the first row of map data is the southern edge, at the bottom of the image
+        let pixel = |x: usize, y: usize| image.pixels[y * 18 + x];
+        assert_eq!(pixel(0, 8), to_rgb(LOWLAND));
+        assert_eq!(pixel(1, 8), [26, 54, 96]);
+        assert_eq!(pixel(0, 0), to_rgb(HIGHLAND));
+
+        // cells without a landscape are deep water, tinted by their map color
+        assert_eq!(pixel(9, 8), [135, 19, 40]);
+        assert_eq!(map.render(0.0).unwrap().pixels[9], to_rgb(DEEP_WATER));
+    }
+
+    #[test]
+    fn recalculate_world_map_data() {
+        let mut plugin = Plugin {
+            objects: vec![flat_landscape((0, 0), 1280.0).into(), flat_landscape((1, 0), -160.0).into()],
+        };
+
+        let mut terrain = Terrain::from_plugin(&mut plugin);
+        terrain.recalculate_world_map_data();
+        drop(terrain);
+
+        let map = WorldMap::from_plugin(&plugin);
+        assert!(map.landscapes[&(0, 0)].world_map_data.data.iter().flatten().all(|&v| v == 10));
+        assert!(map.landscapes[&(1, 0)]
+            .world_map_data
+            .data
+            .iter()
+            .flatten()
+            .all(|&v| v == -10));
+    }
+}
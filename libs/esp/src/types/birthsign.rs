@@ -9,6 +9,7 @@ pub struct Birthsign {
     pub name: String,
     pub texture: String,
     pub description: String,
+    #[merge(keyed)]
     pub spells: Vec<String>,
 }
 
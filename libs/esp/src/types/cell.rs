@@ -15,6 +15,13 @@ pub struct Cell {
     pub references: HashMap<(u32, u32), Reference>,
 }
 
+/// Identifies a cell record: interiors by name, exteriors by grid coordinates.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CellId {
+    Interior(String),
+    Exterior(i32, i32),
+}
+
 #[esp_meta]
 #[derive(LoadSave, Clone, Debug, Default, Eq, PartialEq)]
 pub struct CellData {
@@ -233,6 +240,20 @@ impl Cell {
         }
     }
 
+    pub fn cell_id(&self) -> CellId {
+        match self.exterior_coords() {
+            Some((x, y)) => CellId::Exterior(x, y),
+            None => CellId::Interior(self.name.clone()),
+        }
+    }
+
+    pub fn matches_id(&self, id: &CellId) -> bool {
+        match id {
+            CellId::Interior(name) => self.is_interior() && self.name.eq_ignore_ascii_case(name),
+            CellId::Exterior(x, y) => self.exterior_coords() == Some((*x, *y)),
+        }
+    }
+
     pub fn get_region(&self) -> &str {
         self.region.as_deref().unwrap_or("Wilderness")
     }
@@ -11,6 +11,7 @@ pub struct Container {
     pub mesh: String,
     pub encumbrance: f32,
     pub container_flags: ContainerFlags,
+    #[merge(keyed)]
     pub inventory: Vec<(i32, FixedString<32>)>,
 }
 
@@ -8,6 +8,7 @@ pub struct Faction {
     pub id: String,
     pub name: String,
     pub rank_names: Vec<String>,
+    #[merge(keyed)]
     pub reactions: Vec<FactionReaction>,
     pub data: FactionData,
 }
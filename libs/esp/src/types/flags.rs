@@ -6,6 +6,7 @@ use bitflags::bitflags;
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct ObjectFlags: u32 {
@@ -19,6 +20,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct LandscapeFlags: u32 {
@@ -30,6 +32,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct CellFlags: u32 {
@@ -42,6 +45,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct LightFlags: u32 {
@@ -59,6 +63,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct MiscItemFlags: u32 {
@@ -68,6 +73,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct AlchemyFlags: u32 {
@@ -77,6 +83,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct EnchantingFlags: u32 {
@@ -86,6 +93,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct SpellFlags: u32 {
@@ -97,6 +105,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct WeaponFlags: u32 {
@@ -107,6 +116,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct ClassFlags: u32 {
@@ -116,6 +126,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct FactionFlags: u32 {
@@ -125,6 +136,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct RaceFlags: u32 {
@@ -135,6 +147,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct MagicEffectFlags: u32 {
@@ -161,6 +174,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct ContainerFlags: u32 {
@@ -172,6 +186,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct CreatureFlags: u8 {
@@ -188,6 +203,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct NpcFlags: u8 {
@@ -201,6 +217,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct ServiceFlags: u32 {
@@ -227,6 +244,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct LeveledItemFlags: u32 {
@@ -237,6 +255,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct LeveledCreatureFlags: u32 {
@@ -246,6 +265,7 @@ bitflags! {
 
 bitflags! {
     #[esp_meta]
+    #[merge(flags)]
     #[repr(transparent)]
     #[derive(LoadSave, Clone, Copy, Debug, Default, Eq, PartialEq)]
     pub struct BodypartFlags: u8 {
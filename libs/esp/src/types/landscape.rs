@@ -230,6 +230,183 @@ impl Landscape {
         heights
     }
 
+    /// Encode absolute vertex heights into the delta compressed `VertexHeights` format.
+    ///
+    /// This is the inverse of [`Landscape::decode_vertex_heights`]. Heights are stored in
+    /// steps of 8 units, with each vertex relative to its left neighbor (or the first vertex
+    /// of the previous row). Slopes too steep to be represented by an `i8` delta are clamped,
+    /// and the remaining difference is carried over into the following vertices.
+    ///
+    /// The offset is taken from the first vertex as is, so heights are stored relative to it
+    /// rather than rounded to absolute multiples of 8.
+    ///
+    /// The vertex normals and world map data are recalculated from the encoded heights.
+    ///
+    /// Returns the largest deviation between the given and the encoded heights.
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn encode_vertex_heights(&mut self, heights: &[[f32; 65]; 65]) -> f32 {
+        let offset = heights[0][0] / 8.0;
+
+        let mut max_error = 0.0f32;
+        let mut row_start = 0;
+
+        for (row, deltas) in heights.iter().zip(self.vertex_heights.data.iter_mut()) {
+            let mut previous = row_start;
+            for (x, (&height, delta)) in row.iter().zip(deltas).enumerate() {
+                let target = (height / 8.0 - offset).round() as i32;
+                let clamped = (target - previous).clamp(i8::MIN.into(), i8::MAX.into());
+                *delta = clamped as i8;
+                previous += clamped;
+                if x == 0 {
+                    row_start = previous;
+                }
+                let encoded = (offset + previous as f32) * 8.0;
+                max_error = max_error.max((encoded - height).abs());
+            }
+        }
+
+        self.vertex_heights.offset = offset;
+        self.landscape_flags.insert(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS);
+
+        self.recalculate_vertex_normals();
+        self.recalculate_world_map_data();
+
+        max_error
+    }
+
+    /// Recalculate the vertex normals from the (decoded) vertex heights.
+    ///
+    /// Normals of edge vertices only consider the vertices of this landscape.
+    ///
+    pub fn recalculate_vertex_normals(&mut self) {
+        self.recalculate_vertex_normals_with_neighbors(|_, _| None);
+    }
+
+    /// Recalculate the vertex normals from the (decoded) vertex heights.
+    ///
+    /// The `neighbor_height` function is queried for vertices just outside this landscape, using
+    /// local coordinates of `-1` or `65`. Returning `None` falls back to one-sided differences.
+    ///
+    pub fn recalculate_vertex_normals_with_neighbors(&mut self, neighbor_height: impl Fn(i32, i32) -> Option<f32>) {
+        let heights = self.decode_vertex_heights();
+        let height_at = |x: i32, y: i32| {
+            if (0..65).contains(&x) && (0..65).contains(&y) {
+                #[allow(clippy::cast_sign_loss)]
+                Some(heights[y as usize][x as usize])
+            } else {
+                neighbor_height(x, y)
+            }
+        };
+
+        for (y, row) in (0..).zip(self.vertex_normals.data.iter_mut()) {
+            for (x, normal) in (0..).zip(row) {
+                *normal = calculate_vertex_normal(height_at, x, y);
+            }
+        }
+    }
+
+    /// Recalculate the world map data from the (decoded) vertex heights.
+    ///
+    /// Each of the 9x9 values samples a vertex height, scaled down by a factor of 128 above sea
+    /// level and by a factor of 16 below sea level.
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn recalculate_world_map_data(&mut self) {
+        const STEP: f32 = 64.0 / 9.0;
+
+        let heights = self.decode_vertex_heights();
+
+        for row in 0..9 {
+            for col in 0..9 {
+                let height = heights[(row as f32 * STEP) as usize][(col as f32 * STEP) as usize];
+                let scaled = height / if height > 0.0 { 128.0 } else { 16.0 };
+                self.world_map_data.data[row][col] = scaled.clamp(i8::MIN.into(), i8::MAX.into()) as i8;
+            }
+        }
+    }
+
+    /// Decode the texture indices into row-major order.
+    ///
+    /// Texture indices are stored in blocks of 4x4 texels, with the blocks themselves stored in
+    /// row-major order. A value of zero refers to the default texture, otherwise the value minus
+    /// one is the index of a [`LandscapeTexture`] defined in the same plugin.
+    ///
+    pub fn decode_texture_indices(&self) -> Box<[[u16; 16]; 16]> {
+        let mut indices: Box<[[u16; 16]; 16]> = zeroed_box();
+
+        for (i, &value) in self.texture_indices.data.as_flattened().iter().enumerate() {
+            let (x, y) = texel_coords(i);
+            indices[y][x] = value;
+        }
+
+        indices
+    }
+
+    /// Encode row-major texture indices into the swizzled `TextureIndices` format.
+    ///
+    /// This is the inverse of [`Landscape::decode_texture_indices`].
+    ///
+    pub fn encode_texture_indices(&mut self, indices: &[[u16; 16]; 16]) {
+        for (i, value) in self.texture_indices.data.as_flattened_mut().iter_mut().enumerate() {
+            let (x, y) = texel_coords(i);
+            *value = indices[y][x];
+        }
+        self.landscape_flags.insert(LandscapeFlags::USES_TEXTURES);
+    }
+
+    /// Get the texture index of the texel at (`x`, `y`), in row-major coordinates.
+    ///
+    /// Returns `None` if the coordinates are outside of the 16x16 texels.
+    ///
+    pub fn texture_index(&self, x: usize, y: usize) -> Option<u16> {
+        Some(self.texture_indices.data.as_flattened()[texel_position(x, y)?])
+    }
+
+    /// Set the texture index of the texel at (`x`, `y`), in row-major coordinates.
+    ///
+    /// Returns an error if the coordinates are outside of the 16x16 texels.
+    ///
+    pub fn set_texture_index(&mut self, x: usize, y: usize, value: u16) -> io::Result<()> {
+        let position = texel_position(x, y)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Texel is out of bounds: ({x}, {y})")))?;
+        self.texture_indices.data.as_flattened_mut()[position] = value;
+        self.landscape_flags.insert(LandscapeFlags::USES_TEXTURES);
+        Ok(())
+    }
+
+    /// Sample the terrain height at a position relative to the south-west corner of the
+    /// landscape, by bilinear interpolation of the surrounding vertex heights.
+    ///
+    /// Returns `None` if the position is outside of the landscape. Decodes the vertex heights on
+    /// every call, so prefer [`Landscape::sample_height`] when sampling many positions.
+    ///
+    pub fn height_at(&self, x: f32, y: f32) -> Option<f32> {
+        Self::sample_height(&self.decode_vertex_heights(), x, y)
+    }
+
+    /// Sample the terrain height like [`Landscape::height_at`], using heights returned by
+    /// [`Landscape::decode_vertex_heights`].
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn sample_height(heights: &[[f32; 65]; 65], x: f32, y: f32) -> Option<f32> {
+        let (fx, fy) = (x / 128.0, y / 128.0);
+        if !(0.0..=64.0).contains(&fx) || !(0.0..=64.0).contains(&fy) {
+            return None;
+        }
+
+        let (x0, y0) = ((fx as usize).min(63), (fy as usize).min(63));
+        #[allow(clippy::cast_precision_loss)]
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+        let lerp = |a: f32, b: f32, t: f32| (b - a).mul_add(t, a);
+
+        let south = lerp(heights[y0][x0], heights[y0][x0 + 1], tx);
+        let north = lerp(heights[y0 + 1][x0], heights[y0 + 1][x0 + 1], tx);
+
+        Some(lerp(south, north, ty))
+    }
+
     #[allow(clippy::cast_precision_loss)]
     pub fn calculate_world_vertices(&self) -> Vec<Vec3> {
         const CELL_SIZE: f32 = 8192.0;
@@ -291,3 +468,113 @@ impl Landscape {
         triangles
     }
 }
+
+/// Convert a position in the swizzled `TextureIndices` data into row-major texel coordinates.
+const fn texel_coords(position: usize) -> (usize, usize) {
+    let (block, texel) = (position / 16, position % 16);
+    let x = (block % 4) * 4 + texel % 4;
+    let y = (block / 4) * 4 + texel / 4;
+    (x, y)
+}
+
+/// Convert row-major texel coordinates into a position in the swizzled `TextureIndices` data.
+///
+/// Returns `None` if the coordinates are outside of the 16x16 texels.
+///
+const fn texel_position(x: usize, y: usize) -> Option<usize> {
+    if x >= 16 || y >= 16 {
+        return None;
+    }
+    let block = (y / 4) * 4 + x / 4;
+    let texel = (y % 4) * 4 + x % 4;
+    Some(block * 16 + texel)
+}
+
+/// Calculate the encoded normal of the vertex at (`x`, `y`) using central differences.
+///
+/// The `height_at` function returns `None` for vertices that are unavailable, which allows
+/// including vertices of neighboring landscapes when they exist. Otherwise the calculation
+/// falls back to one-sided differences.
+///
+#[allow(clippy::cast_possible_truncation)]
+fn calculate_vertex_normal(height_at: impl Fn(i32, i32) -> Option<f32>, x: i32, y: i32) -> [i8; 3] {
+    const SPACING: f32 = 128.0;
+
+    let center = height_at(x, y).unwrap_or_default();
+
+    let slope = |prev: Option<f32>, next: Option<f32>| match (prev, next) {
+        (Some(prev), Some(next)) => (next - prev) / (2.0 * SPACING),
+        (Some(prev), None) => (center - prev) / SPACING,
+        (None, Some(next)) => (next - center) / SPACING,
+        (None, None) => 0.0,
+    };
+
+    let dx = slope(height_at(x - 1, y), height_at(x + 1, y));
+    let dy = slope(height_at(x, y - 1), height_at(x, y + 1));
+
+    let normal = Vec3::new(-dx, -dy, 1.0).normalize() * 127.0;
+
+    [normal.x.round() as i8, normal.y.round() as i8, normal.z.round() as i8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    fn encode_decode_heights() {
+        let mut heights: Box<[[f32; 65]; 65]> = zeroed_box();
+        for (y, row) in heights.iter_mut().enumerate() {
+            for (x, height) in row.iter_mut().enumerate() {
+                *height = -256.0 + (x * 16 + y * 40) as f32;
+            }
+        }
+
+        let mut landscape = Landscape::default();
+        let max_error = landscape.encode_vertex_heights(&heights);
+        assert_eq!(max_error, 0.0);
+        assert_eq!(landscape.decode_vertex_heights(), heights);
+
+        // heights between vertices are interpolated
+        assert_eq!(landscape.height_at(64.0, 0.0), Some(-248.0));
+        assert_eq!(Landscape::sample_height(&heights, 128.0, 192.0), Some(-180.0));
+        assert_eq!(Landscape::sample_height(&heights, -1.0, 0.0), None);
+
+        // a cliff too steep for a single delta is spread across the following vertices
+        heights[10][10] += 8.0 * 200.0;
+        let max_error = landscape.encode_vertex_heights(&heights);
+        assert_eq!(max_error, 8.0 * 75.0);
+        assert!(landscape.vertex_normals.data[0][0][2] > 0);
+
+        // heights between multiples of 8 keep the offset of the first vertex
+        heights.iter_mut().flatten().for_each(|height| *height = 3.0);
+        assert_eq!(landscape.encode_vertex_heights(&heights), 0.0);
+        assert_eq!(landscape.vertex_heights.offset, 0.375);
+        assert_eq!(landscape.decode_vertex_heights()[32][32], 3.0);
+    }
+
+    #[test]
+    fn texture_indices_swizzle() {
+        for i in 0..256 {
+            let (x, y) = texel_coords(i);
+            assert_eq!(texel_position(x, y), Some(i));
+        }
+
+        // the second stored value belongs to the first block of 4x4 texels
+        assert_eq!(texel_coords(1), (1, 0));
+        assert_eq!(texel_coords(4), (0, 1));
+        assert_eq!(texel_coords(16), (4, 0));
+
+        let mut landscape = Landscape::default();
+        landscape.set_texture_index(1, 5, 3).unwrap();
+        assert_eq!(landscape.texture_indices.data[4][5], 3);
+        assert_eq!(landscape.decode_texture_indices()[5][1], 3);
+        assert_eq!(landscape.texture_index(1, 5), Some(3));
+
+        // coordinates outside of the texels are rejected
+        assert!(landscape.set_texture_index(16, 0, 3).is_err());
+        assert_eq!(landscape.texture_index(0, 16), None);
+        assert_eq!(landscape.decode_texture_indices()[0][0], 0);
+    }
+}
@@ -8,6 +8,7 @@ pub struct LeveledCreature {
     pub id: String,
     pub leveled_creature_flags: LeveledCreatureFlags,
     pub chance_none: u8,
+    #[merge(keyed)]
     pub creatures: Vec<(String, u16)>,
 }
 
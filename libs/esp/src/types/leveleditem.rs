@@ -8,6 +8,7 @@ pub struct LeveledItem {
     pub id: String,
     pub leveled_item_flags: LeveledItemFlags,
     pub chance_none: u8,
+    #[merge(keyed)]
     pub items: Vec<(String, u16)>,
 }
 
@@ -9,10 +9,14 @@ pub struct Npc {
     pub name: String,
     pub script: String,
     pub mesh: String,
+    #[merge(keyed)]
     pub inventory: Vec<(i32, FixedString<32>)>,
+    #[merge(keyed)]
     pub spells: Vec<String>,
     pub ai_data: AiData,
+    #[merge(list)]
     pub ai_packages: Vec<AiPackage>,
+    #[merge(list)]
     pub travel_destinations: Vec<TravelDestination>,
     pub race: String,
     pub class: String,
@@ -1,3 +1,6 @@
+// external imports
+use glam::Vec3;
+
 // internal imports
 use crate::prelude::*;
 
@@ -27,6 +30,23 @@ pub struct PathGridPoint {
     pub connection_count: u8,
 }
 
+/// A problem found by [`PathGrid::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathGridIssue {
+    /// The `point_count` does not match the number of points.
+    PointCountMismatch { point_count: u16, points: usize },
+    /// The sum of all `connection_count` values does not match the number of connections.
+    ConnectionCountMismatch { connection_count: usize, connections: usize },
+    /// A point is connected to an index that does not exist.
+    InvalidConnection { point: usize, neighbor: u32 },
+    /// A point is connected to a neighbor which is not connected back.
+    AsymmetricConnection { point: usize, neighbor: usize },
+    /// A point has no connections at all.
+    IsolatedPoint { point: usize },
+    /// A point lies below the terrain.
+    BelowTerrain { point: usize, terrain_height: f32 },
+}
+
 impl Load for PathGrid {
     fn load(stream: &mut Reader<'_>) -> io::Result<Self> {
         let mut this: Self = default();
@@ -124,3 +144,531 @@ impl Save for PathGridPoint {
         Ok(())
     }
 }
+
+impl PathGrid {
+    /// Build the adjacency list of each point from the flat `connections` list.
+    ///
+    /// The connections of each point are stored consecutively, in the order of the points.
+    /// Connections beyond the end of the list are ignored.
+    ///
+    pub fn adjacency_list(&self) -> Vec<Vec<u32>> {
+        let mut connections = self.connections.iter().copied();
+        self.points
+            .iter()
+            .map(|point| connections.by_ref().take(point.connection_count.into()).collect())
+            .collect()
+    }
+
+    /// Replace all connections with the given adjacency list, keeping `connection_count` and
+    /// `point_count` in sync.
+    ///
+    /// Returns an error, leaving the path grid unchanged, if the adjacency list length does not
+    /// match the number of points, a neighbor is out of range, or a point has too many
+    /// connections.
+    ///
+    pub fn set_adjacency_list(&mut self, adjacency_list: &[Vec<u32>]) -> io::Result<()> {
+        if adjacency_list.len() != self.points.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "adjacency list length does not match the number of points",
+            ));
+        }
+
+        if adjacency_list
+            .iter()
+            .flatten()
+            .any(|&neighbor| neighbor as usize >= self.points.len())
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "neighbor index out of range"));
+        }
+
+        let connection_counts = adjacency_list
+            .iter()
+            .map(|neighbors| neighbors.len().try_into())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "too many connections"))?;
+
+        self.update_point_count()?;
+
+        for (point, connection_count) in self.points.iter_mut().zip(connection_counts) {
+            point.connection_count = connection_count;
+        }
+        self.connections = adjacency_list.concat();
+
+        Ok(())
+    }
+
+    /// Add a new point and return its index.
+    ///
+    /// Returns an error, leaving the path grid unchanged, if the point count would overflow.
+    ///
+    pub fn add_point(&mut self, location: [i32; 3]) -> io::Result<usize> {
+        self.data.point_count = (self.points.len() + 1)
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "too many points"))?;
+        self.points.push(PathGridPoint { location, ..default() });
+        Ok(self.points.len() - 1)
+    }
+
+    /// Remove a point along with all of its connections.
+    ///
+    /// Indices of the following points are shifted down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn remove_point(&mut self, index: usize) -> io::Result<PathGridPoint> {
+        let mut adjacency_list = self.adjacency_list();
+
+        adjacency_list.remove(index);
+        let point = self.points.remove(index);
+
+        let index = index as u32;
+        for neighbors in &mut adjacency_list {
+            neighbors.retain(|&neighbor| neighbor != index);
+            for neighbor in neighbors.iter_mut().filter(|neighbor| **neighbor > index) {
+                *neighbor -= 1;
+            }
+        }
+
+        self.set_adjacency_list(&adjacency_list)?;
+        Ok(point)
+    }
+
+    /// Connect two points in both directions, unless they are connected already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    ///
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn add_edge(&mut self, a: usize, b: usize) -> io::Result<()> {
+        assert!(a < self.points.len() && b < self.points.len());
+
+        let mut adjacency_list = self.adjacency_list();
+
+        for (from, to) in [(a, b), (b, a)] {
+            if from != to && !adjacency_list[from].contains(&(to as u32)) {
+                adjacency_list[from].push(to as u32);
+            }
+        }
+
+        self.set_adjacency_list(&adjacency_list)
+    }
+
+    /// Disconnect two points in both directions.
+    ///
+    /// Returns whether any connection was removed.
+    ///
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn remove_edge(&mut self, a: usize, b: usize) -> io::Result<bool> {
+        let mut adjacency_list = self.adjacency_list();
+
+        let mut removed = false;
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(neighbors) = adjacency_list.get_mut(from) {
+                let len = neighbors.len();
+                neighbors.retain(|&neighbor| neighbor != to as u32);
+                removed |= neighbors.len() != len;
+            }
+        }
+
+        self.set_adjacency_list(&adjacency_list)?;
+        Ok(removed)
+    }
+
+    /// Find the shortest path between two points using A* search.
+    ///
+    /// Returns the indices of all points along the path, including `start` and `goal`, or
+    /// `None` if the points are not connected. Connections to invalid indices are ignored.
+    ///
+    pub fn shortest_path(&self, start: usize, goal: usize) -> Option<Vec<usize>> {
+        use std::collections::BinaryHeap;
+
+        let adjacency_list = self.adjacency_list();
+        let distance = |a: usize, b: usize| self.point_distance(a, b);
+
+        if start >= self.points.len() || goal >= self.points.len() {
+            return None;
+        }
+
+        let mut costs = vec![f32::INFINITY; self.points.len()];
+        let mut previous = vec![usize::MAX; self.points.len()];
+        let mut open = BinaryHeap::new();
+
+        costs[start] = 0.0;
+        open.push(SearchNode {
+            estimate: distance(start, goal),
+            index: start,
+        });
+
+        while let Some(SearchNode { index, .. }) = open.pop() {
+            if index == goal {
+                let mut path = vec![goal];
+                while let Some(&last) = path.last() {
+                    if last == start {
+                        break;
+                    }
+                    path.push(previous[last]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for &neighbor in &adjacency_list[index] {
+                let neighbor = neighbor as usize;
+                if neighbor >= self.points.len() {
+                    continue;
+                }
+                let cost = costs[index] + distance(index, neighbor);
+                if cost < costs[neighbor] {
+                    costs[neighbor] = cost;
+                    previous[neighbor] = index;
+                    open.push(SearchNode {
+                        estimate: cost + distance(neighbor, goal),
+                        index: neighbor,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Group the points into connected components, treating connections as undirected.
+    ///
+    /// Each component lists its point indices in ascending order.
+    ///
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        let adjacency_list = self.adjacency_list();
+
+        // connections may be one-sided, so treat them as undirected
+        let mut undirected = vec![vec![]; self.points.len()];
+        for (index, neighbors) in adjacency_list.iter().enumerate() {
+            for &neighbor in neighbors {
+                let neighbor = neighbor as usize;
+                if neighbor < self.points.len() {
+                    undirected[index].push(neighbor);
+                    undirected[neighbor].push(index);
+                }
+            }
+        }
+
+        let mut visited = vec![false; self.points.len()];
+        let mut components = vec![];
+
+        for start in 0..self.points.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            let mut component = vec![];
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                component.push(index);
+                for &neighbor in &undirected[index] {
+                    if !visited[neighbor] {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            component.sort_unstable();
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Convert a point location into world space.
+    ///
+    /// Exterior point locations are relative to the origin of their cell. Interior path grids
+    /// use a grid of `(0, 0)`, so their locations are returned unchanged.
+    ///
+    pub const fn world_location(&self, location: [i32; 3]) -> [i32; 3] {
+        const CELL_SIZE: i32 = 8192;
+        let (x, y) = self.data.grid;
+        [location[0] + x * CELL_SIZE, location[1] + y * CELL_SIZE, location[2]]
+    }
+
+    /// Check the path grid for common problems.
+    ///
+    /// When the `landscape` of an exterior path grid is provided, points are also checked for
+    /// lying below the terrain.
+    ///
+    #[allow(clippy::cast_precision_loss)]
+    pub fn validate(&self, landscape: Option<&Landscape>) -> Vec<PathGridIssue> {
+        /// Points this far below the terrain are still considered to be on the surface.
+        const TERRAIN_TOLERANCE: f32 = 16.0;
+
+        let mut issues = vec![];
+
+        if usize::from(self.data.point_count) != self.points.len() {
+            issues.push(PathGridIssue::PointCountMismatch {
+                point_count: self.data.point_count,
+                points: self.points.len(),
+            });
+        }
+
+        let connection_count = self.points.iter().map(|point| usize::from(point.connection_count)).sum();
+        if connection_count != self.connections.len() {
+            issues.push(PathGridIssue::ConnectionCountMismatch {
+                connection_count,
+                connections: self.connections.len(),
+            });
+        }
+
+        let adjacency_list = self.adjacency_list();
+        let has_incoming: HashSet<u32> = self.connections.iter().copied().collect();
+
+        for (point, neighbors) in adjacency_list.iter().enumerate() {
+            for &neighbor in neighbors {
+                match adjacency_list.get(neighbor as usize) {
+                    None => issues.push(PathGridIssue::InvalidConnection { point, neighbor }),
+                    Some(reverse) =>
+                    {
+                        #[allow(clippy::cast_possible_truncation)]
+                        if !reverse.contains(&(point as u32)) {
+                            issues.push(PathGridIssue::AsymmetricConnection {
+                                point,
+                                neighbor: neighbor as usize,
+                            });
+                        }
+                    }
+                }
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            if neighbors.is_empty() && !has_incoming.contains(&(point as u32)) {
+                issues.push(PathGridIssue::IsolatedPoint { point });
+            }
+        }
+
+        if let Some(landscape) = landscape {
+            let heights = landscape.decode_vertex_heights();
+            for (point, PathGridPoint { location: [x, y, z], .. }) in self.points.iter().enumerate() {
+                if let Some(terrain_height) = Landscape::sample_height(&heights, *x as f32, *y as f32) {
+                    if (*z as f32) < terrain_height - TERRAIN_TOLERANCE {
+                        issues.push(PathGridIssue::BelowTerrain { point, terrain_height });
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Generate a path grid for the exterior cell of the given landscape.
+    ///
+    /// Points are placed on a regular grid with a spacing of `granularity` units, at the height
+    /// of the terrain. Points below sea level are skipped. Neighboring points, including
+    /// diagonals, are connected when the slope between them does not exceed `max_slope` (the
+    /// ratio of height difference to horizontal distance).
+    ///
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn generate(landscape: &Landscape, granularity: u16, max_slope: f32) -> io::Result<Self> {
+        const CELL_SIZE: usize = 8192;
+
+        if granularity == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "granularity must not be zero"));
+        }
+
+        let mut this = Self {
+            data: PathGridData {
+                grid: landscape.grid,
+                granularity,
+                point_count: 0,
+            },
+            ..default()
+        };
+
+        let steps = CELL_SIZE / usize::from(granularity) + 1;
+        let heights = landscape.decode_vertex_heights();
+
+        // the point index at each grid position, if any
+        let mut indices = vec![vec![None; steps]; steps];
+
+        for (j, row) in indices.iter_mut().enumerate() {
+            for (i, index) in row.iter_mut().enumerate() {
+                let x = (i * usize::from(granularity)).min(CELL_SIZE) as f32;
+                let y = (j * usize::from(granularity)).min(CELL_SIZE) as f32;
+                let Some(height) = Landscape::sample_height(&heights, x, y) else {
+                    continue;
+                };
+                if height < 0.0 {
+                    continue;
+                }
+                let point = this.add_point([x as i32, y as i32, height.round() as i32])?;
+                this.points[point].auto_generated = 1;
+                *index = Some(point);
+            }
+        }
+
+        let mut adjacency_list = vec![vec![]; this.points.len()];
+
+        for j in 0..steps {
+            for i in 0..steps {
+                let Some(a) = indices[j][i] else {
+                    continue;
+                };
+                for (di, dj) in [(1, 0), (0, 1), (1, 1), (-1, 1)] {
+                    let (Some(ni), Some(nj)) = (i.checked_add_signed(di), j.checked_add_signed(dj)) else {
+                        continue;
+                    };
+                    let Some(&Some(b)) = indices.get(nj).and_then(|row| row.get(ni)) else {
+                        continue;
+                    };
+                    let [ax, ay, az] = this.points[a].location.map(|v| v as f32);
+                    let [bx, by, bz] = this.points[b].location.map(|v| v as f32);
+                    let run = (bx - ax).hypot(by - ay);
+                    if (bz - az).abs() <= run * max_slope {
+                        adjacency_list[a].push(b as u32);
+                        adjacency_list[b].push(a as u32);
+                    }
+                }
+            }
+        }
+
+        this.set_adjacency_list(&adjacency_list)?;
+
+        Ok(this)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn point_distance(&self, a: usize, b: usize) -> f32 {
+        let [ax, ay, az] = self.points[a].location.map(|v| v as f32);
+        let [bx, by, bz] = self.points[b].location.map(|v| v as f32);
+        Vec3::new(ax - bx, ay - by, az - bz).length()
+    }
+
+    fn update_point_count(&mut self) -> io::Result<()> {
+        self.data.point_count = self
+            .points
+            .len()
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "too many points"))?;
+        Ok(())
+    }
+}
+
+/// A point queued for visiting by [`PathGrid::shortest_path`], ordered by lowest estimate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SearchNode {
+    estimate: f32,
+    index: usize,
+}
+
+impl Eq for SearchNode {}
+
+impl Ord for SearchNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for SearchNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_and_search() -> io::Result<()> {
+        let mut pathgrid = PathGrid::default();
+        for location in [[0, 0, 0], [100, 0, 0], [200, 0, 0], [100, 500, 0], [0, 900, 0]] {
+            pathgrid.add_point(location)?;
+        }
+        pathgrid.add_edge(0, 1)?;
+        pathgrid.add_edge(1, 2)?;
+        pathgrid.add_edge(0, 3)?;
+        pathgrid.add_edge(3, 2)?;
+
+        assert_eq!(pathgrid.data.point_count, 5);
+        assert_eq!(pathgrid.connections.len(), 8);
+        assert_eq!(pathgrid.shortest_path(0, 2), Some(vec![0, 1, 2]));
+        assert_eq!(pathgrid.shortest_path(0, 4), None);
+        assert_eq!(pathgrid.connected_components(), [vec![0, 1, 2, 3], vec![4]]);
+
+        pathgrid.remove_point(1)?;
+        assert_eq!(pathgrid.data.point_count, 4);
+        assert_eq!(pathgrid.shortest_path(0, 1), Some(vec![0, 2, 1]));
+        assert_eq!(pathgrid.adjacency_list(), [vec![2], vec![2], vec![0, 1], vec![]]);
+
+        assert!(pathgrid.remove_edge(2, 1)?);
+        assert_eq!(pathgrid.connections, [2, 0]);
+
+        // invalid adjacency lists are rejected without modifying the path grid
+        let before = pathgrid.clone();
+        assert!(pathgrid.set_adjacency_list(&[vec![]]).is_err());
+        assert!(pathgrid.set_adjacency_list(&[vec![], vec![], vec![0; 256], vec![]]).is_err());
+        assert!(pathgrid.set_adjacency_list(&[vec![4], vec![], vec![], vec![]]).is_err());
+        assert_eq!(pathgrid, before);
+
+        // points beyond the point count limit are rejected without modifying the path grid
+        pathgrid.points.resize(u16::MAX as usize, default());
+        pathgrid.data.point_count = u16::MAX;
+        let before = pathgrid.clone();
+        assert!(pathgrid.add_point([0, 0, 0]).is_err());
+        assert_eq!(pathgrid, before);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_and_generate() -> io::Result<()> {
+        let mut landscape = Landscape::default();
+        let mut heights: Box<[[f32; 65]; 65]> = bytemuck::zeroed_box();
+        for (y, row) in heights.iter_mut().enumerate() {
+            row.fill(if y < 32 { 64.0 } else { 4096.0 });
+        }
+        landscape.encode_vertex_heights(&heights);
+
+        let pathgrid = PathGrid::generate(&landscape, 1024, 1.0)?;
+        assert_eq!(pathgrid.points.len(), 81);
+        assert!(pathgrid.validate(Some(&landscape)).is_empty());
+        // the cliff splits the grid in two
+        assert_eq!(pathgrid.connected_components().len(), 2);
+
+        let mut pathgrid = PathGrid {
+            points: vec![
+                PathGridPoint {
+                    location: [0, 0, 0],
+                    connection_count: 2,
+                    ..default()
+                },
+                PathGridPoint {
+                    location: [100, 0, 64],
+                    ..default()
+                },
+                PathGridPoint::default(),
+            ],
+            connections: vec![1, 7],
+            ..default()
+        };
+        pathgrid.data.point_count = 3;
+        assert_eq!(
+            pathgrid.validate(Some(&landscape)),
+            [
+                PathGridIssue::AsymmetricConnection { point: 0, neighbor: 1 },
+                PathGridIssue::InvalidConnection { point: 0, neighbor: 7 },
+                PathGridIssue::IsolatedPoint { point: 2 },
+                PathGridIssue::BelowTerrain {
+                    point: 0,
+                    terrain_height: 64.0
+                },
+                PathGridIssue::BelowTerrain {
+                    point: 2,
+                    terrain_height: 64.0
+                },
+            ]
+        );
+        Ok(())
+    }
+}
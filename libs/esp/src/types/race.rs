@@ -7,6 +7,7 @@ pub struct Race {
     pub flags: ObjectFlags,
     pub id: String,
     pub name: String,
+    #[merge(keyed)]
     pub spells: Vec<String>,
     pub description: String,
     pub data: RaceData,
@@ -10,6 +10,7 @@ pub struct Region {
     pub weather_chances: WeatherChances,
     pub sleep_creature: String,
     pub map_color: [u8; 4],
+    #[merge(keyed)]
     pub sounds: Vec<(FixedString<32>, u8)>,
 }
 
@@ -6,8 +6,10 @@ use crate::prelude::*;
 pub struct Script {
     pub flags: ObjectFlags,
     pub id: String,
+    /// The header loaded with the script. Saving always writes a header recalculated from the
+    /// variables and bytecode, see [`Script::calculate_header`].
     pub header: ScriptHeader,
-    pub variables: Vec<u8>,
+    pub variables: ScriptVariables,
     pub bytecode: Vec<u8>,
     pub text: String,
 }
@@ -22,6 +24,60 @@ pub struct ScriptHeader {
     pub variables_length: u32,
 }
 
+/// The type of a script variable.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VariableKind {
+    Short,
+    Long,
+    Float,
+}
+
+impl VariableKind {
+    pub const ALL: [Self; 3] = [Self::Short, Self::Long, Self::Float];
+
+    /// The keyword used to declare variables of this type.
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Short => "short",
+            Self::Long => "long",
+            Self::Float => "float",
+        }
+    }
+
+    /// The prefix of variables of this type in bytecode.
+    pub const fn prefix(self) -> u8 {
+        match self {
+            Self::Short => b's',
+            Self::Long => b'l',
+            Self::Float => b'f',
+        }
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.keyword().eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn from_prefix(prefix: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.prefix() == prefix)
+    }
+}
+
+/// The local variables of a script, grouped by type in the order of the `SCVR` table.
+///
+/// Compiled scripts refer to variables by their 1-based index among variables of the same type.
+/// Removing or renaming variables does not update the script text or bytecode, so the script
+/// should be recompiled afterwards.
+///
+#[esp_meta]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScriptVariables {
+    pub shorts: Vec<String>,
+    pub longs: Vec<String>,
+    pub floats: Vec<String>,
+}
+
 impl Load for Script {
     fn load(stream: &mut Reader<'_>) -> io::Result<Self> {
         let mut this: Self = default();
@@ -36,7 +92,8 @@ impl Load for Script {
                     this.header = stream.load()?;
                 }
                 b"SCVR" => {
-                    this.variables = stream.load()?;
+                    let bytes: Vec<u8> = stream.load()?;
+                    this.variables = ScriptVariables::from_bytes(&bytes, &this.header);
                 }
                 b"SCDT" => {
                     this.bytecode = stream.load()?;
@@ -66,11 +123,11 @@ impl Save for Script {
         stream.save(b"SCHD")?;
         stream.save(&52u32)?;
         stream.save::<FixedString<32>>(self.id.as_ref())?;
-        stream.save(&self.header)?;
+        stream.save(&self.calculate_header())?;
         // SCVR
         if !self.variables.is_empty() {
             stream.save(b"SCVR")?;
-            stream.save(&self.variables)?;
+            stream.save(&self.variables.to_bytes())?;
         }
         // SCDT
         if !self.bytecode.is_empty() {
@@ -91,3 +148,200 @@ impl Save for Script {
         Ok(())
     }
 }
+
+impl Script {
+    /// Update the header counts and lengths to match the variables and bytecode.
+    pub fn recalculate_header(&mut self) {
+        self.header = self.calculate_header();
+    }
+
+    /// The header matching the variables and bytecode.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn calculate_header(&self) -> ScriptHeader {
+        ScriptHeader {
+            num_shorts: self.variables.shorts.len() as u32,
+            num_longs: self.variables.longs.len() as u32,
+            num_floats: self.variables.floats.len() as u32,
+            bytecode_length: self.bytecode.len() as u32,
+            variables_length: self.variables.to_bytes().len() as u32,
+        }
+    }
+}
+
+impl ScriptVariables {
+    /// Parse an `SCVR` table, assigning types using the counts of the script header.
+    ///
+    /// Headers with mismatched counts are tolerated: names beyond the header counts are kept as
+    /// floats rather than dropped, and missing names are ignored.
+    ///
+    pub fn from_bytes(bytes: &[u8], header: &ScriptHeader) -> Self {
+        let mut names = bytes
+            .strip_suffix(b"\0")
+            .unwrap_or(bytes)
+            .split(|&b| b == 0)
+            .map(|name| name.to_str_lossy().into_owned());
+        let mut take = |count: u32| names.by_ref().take(count as usize).collect();
+        let mut this = Self {
+            shorts: take(header.num_shorts),
+            longs: take(header.num_longs),
+            floats: take(header.num_floats),
+        };
+        this.floats.extend(names.filter(|name| !name.is_empty()));
+        this
+    }
+
+    /// Encode as an `SCVR` table of null terminated names.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for (name, _) in self.iter() {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.shorts.len() + self.longs.len() + self.floats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The names of all variables of the given type.
+    pub fn names(&self, kind: VariableKind) -> &[String] {
+        match kind {
+            VariableKind::Short => &self.shorts,
+            VariableKind::Long => &self.longs,
+            VariableKind::Float => &self.floats,
+        }
+    }
+
+    fn names_mut(&mut self, kind: VariableKind) -> &mut Vec<String> {
+        match kind {
+            VariableKind::Short => &mut self.shorts,
+            VariableKind::Long => &mut self.longs,
+            VariableKind::Float => &mut self.floats,
+        }
+    }
+
+    /// Iterate over all variables with their types, in the order of the `SCVR` table.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, VariableKind)> {
+        VariableKind::ALL
+            .into_iter()
+            .flat_map(move |kind| self.names(kind).iter().map(move |name| (name.as_str(), kind)))
+    }
+
+    /// Find a variable by name ignoring case, returning its type and its 1-based index among
+    /// variables of that type.
+    pub fn get(&self, name: &str) -> Option<(VariableKind, u16)> {
+        VariableKind::ALL.into_iter().find_map(|kind| {
+            let index = self.names(kind).iter().position(|n| n.eq_ignore_ascii_case(name))?;
+            Some((kind, u16::try_from(index + 1).ok()?))
+        })
+    }
+
+    /// Find the name of a variable by its type and 1-based index among variables of that type.
+    pub fn name(&self, kind: VariableKind, index: u16) -> Option<&str> {
+        let index = usize::from(index).checked_sub(1)?;
+        self.names(kind).get(index).map(String::as_str)
+    }
+
+    /// Add a variable, returning its 1-based index among variables of that type.
+    pub fn add(&mut self, kind: VariableKind, name: &str) -> io::Result<u16> {
+        self.validate_name(name)?;
+        let names = self.names_mut(kind);
+        let Ok(index) = u16::try_from(names.len() + 1) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Too many variables"));
+        };
+        names.push(name.into());
+        Ok(index)
+    }
+
+    /// Remove a variable by name ignoring case, returning its type.
+    ///
+    /// Later variables of the same type move down by one index.
+    ///
+    pub fn remove(&mut self, name: &str) -> Option<VariableKind> {
+        let (kind, index) = self.get(name)?;
+        self.names_mut(kind).remove(usize::from(index) - 1);
+        Some(kind)
+    }
+
+    /// Rename a variable, keeping its type and index.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> io::Result<()> {
+        let Some((kind, index)) = self.get(name) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Variable is not declared: {name}"),
+            ));
+        };
+        if !name.eq_ignore_ascii_case(new_name) {
+            self.validate_name(new_name)?;
+        }
+        self.names_mut(kind)[usize::from(index) - 1] = new_name.into();
+        Ok(())
+    }
+
+    fn validate_name(&self, name: &str) -> io::Result<()> {
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid variable name: {name:?}"),
+            ));
+        }
+        if self.get(name).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Variable is already declared: {name}"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_variables() -> io::Result<()> {
+        let mut script = Script::default();
+        assert_eq!(script.variables.add(VariableKind::Float, "timer")?, 1);
+        assert_eq!(script.variables.add(VariableKind::Short, "state")?, 1);
+        assert_eq!(script.variables.add(VariableKind::Short, "count")?, 2);
+        assert!(script.variables.add(VariableKind::Long, "STATE").is_err());
+        assert!(script.variables.add(VariableKind::Long, "bad name").is_err());
+
+        script.variables.rename("Count", "counter")?;
+        assert_eq!(script.variables.remove("state"), Some(VariableKind::Short));
+        assert_eq!(script.variables.get("COUNTER"), Some((VariableKind::Short, 1)));
+        assert_eq!(script.variables.name(VariableKind::Float, 1), Some("timer"));
+        assert_eq!(script.variables.to_bytes(), b"counter\0timer\0");
+
+        let mut plugin = Plugin::new();
+        plugin.objects.push(script.clone().into());
+        let mut loaded = Plugin::new();
+        loaded.load_bytes(&plugin.save_bytes()?)?;
+        let loaded = loaded.objects_of_type::<Script>().next().unwrap();
+        assert_eq!((loaded.header.num_shorts, loaded.header.num_floats), (1, 1));
+        assert_eq!(loaded.header.variables_length, 14);
+        assert_eq!(loaded.variables, script.variables);
+
+        // names beyond the header counts are kept rather than dropped
+        let header = ScriptHeader {
+            num_shorts: 1,
+            ..default()
+        };
+        assert_eq!(ScriptVariables::from_bytes(b"counter\0timer\0", &header), script.variables);
+        let header = ScriptHeader {
+            num_shorts: 3,
+            num_floats: 1,
+            ..default()
+        };
+        let variables = ScriptVariables::from_bytes(b"counter\0timer\0", &header);
+        assert_eq!((variables.shorts.len(), variables.floats.len()), (2, 0));
+
+        Ok(())
+    }
+}
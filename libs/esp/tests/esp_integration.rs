@@ -44,3 +44,55 @@ fn load_save_json() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "png")]
+fn terrain_png_round_trip() -> std::io::Result<()> {
+    use esp::{Landscape, LandscapeFlags, Terrain, TerrainImage};
+
+    let landscape = |grid, height: f32| {
+        let mut heights: Box<[[f32; 65]; 65]> = bytemuck::zeroed_box();
+        for (row, y) in heights.iter_mut().zip(0..65u8) {
+            for (z, x) in row.iter_mut().zip(0..65u8) {
+                *z = 8.0f32.mul_add(f32::from(x + y), height);
+            }
+        }
+        let mut landscape = Landscape {
+            grid,
+            ..Default::default()
+        };
+        landscape.encode_vertex_heights(&heights);
+        landscape
+            .vertex_colors
+            .data
+            .iter_mut()
+            .flatten()
+            .for_each(|color| *color = [10, 20, 30]);
+        landscape.landscape_flags.insert(LandscapeFlags::USES_VERTEX_COLORS);
+        landscape.into()
+    };
+
+    let mut plugin = Plugin::new();
+    plugin.objects = vec![landscape((-1, 0), -512.0), landscape((0, 0), 0.0)];
+    let mut terrain = Terrain::from_plugin(&mut plugin);
+
+    let dir = TempDir::new()?;
+
+    let heightmap = terrain.export_heightmap().unwrap();
+    heightmap.save_png(dir.path().join("heightmap.png"))?;
+    let loaded = TerrainImage::<u16>::load_png(dir.path().join("heightmap.png"))?;
+    assert_eq!(loaded, heightmap);
+
+    let colors = terrain.export_vertex_colors().unwrap();
+    colors.save_png(dir.path().join("colors.png"))?;
+    let loaded_colors = TerrainImage::<[u8; 3]>::load_png(dir.path().join("colors.png"))?;
+    assert_eq!(loaded_colors, colors);
+    assert_eq!(colors.pixels[0], [10, 20, 30]);
+
+    assert_eq!(terrain.import_heightmap(&loaded)?, 2);
+    assert_eq!(terrain.import_vertex_colors(&loaded_colors)?, 2);
+    assert_eq!(terrain.export_heightmap(), Some(heightmap));
+    assert_eq!(terrain.export_vertex_colors(), Some(colors));
+
+    Ok(())
+}
@@ -2,13 +2,15 @@ use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 
 mod features;
+mod merge;
 
 #[doc(hidden)]
 #[proc_macro_attribute]
 pub fn esp_meta(_args: TokenStream, input: TokenStream) -> TokenStream {
-    #[allow(unused_mut)]
     let mut input = syn::parse_macro_input!(input as syn::DeriveInput);
 
+    let impl_merge = merge::impl_merge(&mut input);
+
     #[cfg(feature = "serde")]
     {
         features::serde::impl_serialize_deserialize(&mut input);
@@ -16,6 +18,7 @@ pub fn esp_meta(_args: TokenStream, input: TokenStream) -> TokenStream {
 
     let output = quote! {
         #input
+        #impl_merge
     };
 
     output.into()
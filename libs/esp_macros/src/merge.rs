@@ -0,0 +1,110 @@
+use quote::{quote, ToTokens};
+
+/// Implement the `Merge` trait for input, consuming any `#[merge(...)]` field attributes.
+///
+/// Structs with named fields are merged field by field, types marked `#[merge(flags)]` are merged
+/// bit by bit, and everything else is merged as a single value. Fields marked `#[merge(keyed)]`
+/// are merged as collections whose elements are identified by their `MergeKey`, and fields marked
+/// `#[merge(list)]` are merged as ordered lists.
+///
+pub fn impl_merge(input: &mut syn::DeriveInput) -> impl ToTokens {
+    let is_flags = take_merge_attr(&mut input.attrs, &["flags"]).is_some();
+    let strategies = take_field_strategies(&mut input.data);
+
+    // TES3Object is merged by variant, which is implemented by hand.
+    if input.ident == "TES3Object" || !derives_partial_eq(&input.attrs) {
+        return quote! {};
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => {
+            let fields = fields.named.iter().zip(strategies).map(|(field, strategy)| {
+                let ident = field.ident.as_ref().unwrap();
+                let name = syn::LitStr::new(&ident.to_string(), ident.span());
+                let base = quote! { &base.#ident };
+                let versions = quote! { &versions.iter().map(|version| &version.#ident).collect::<Vec<_>>() };
+                let path = quote! { &crate::prelude::merge_field_path(path, #name) };
+                match strategy.as_deref() {
+                    Some("keyed") => quote! {
+                        #ident: crate::prelude::merge_keyed(#base, #versions, #path, conflicts, crate::prelude::MergeKey::merge_key)
+                    },
+                    Some("list") => quote! {
+                        #ident: crate::prelude::merge_list(#base, #versions, #path, conflicts)
+                    },
+                    _ => quote! {
+                        #ident: crate::prelude::Merge::merge(#base, #versions, #path, conflicts)
+                    },
+                }
+            });
+            quote! {
+                Self { #(#fields,)* }
+            }
+        }
+        syn::Data::Struct(_) if is_flags => quote! {
+            let mut bits = base.bits();
+            for version in versions {
+                let changed = version.bits() ^ base.bits();
+                bits = (bits & !changed) | (version.bits() & changed);
+            }
+            Self::from_bits_retain(bits)
+        },
+        _ => quote! {
+            crate::prelude::merge_atomic(base, versions, path, conflicts)
+        },
+    };
+
+    quote! {
+        impl #impl_generics crate::prelude::Merge for #ident #ty_generics #where_clause {
+            fn merge(
+                base: &Self,
+                versions: &[&Self],
+                path: &str,
+                conflicts: &mut Vec<crate::prelude::MergeConflict>,
+            ) -> Self {
+                #body
+            }
+        }
+    }
+}
+
+/// Remove the `#[merge(...)]` attributes of each field, returning the strategy of each field.
+fn take_field_strategies(data: &mut syn::Data) -> Vec<Option<String>> {
+    let syn::Data::Struct(data) = data else {
+        return vec![];
+    };
+
+    data.fields
+        .iter_mut()
+        .map(|field| take_merge_attr(&mut field.attrs, &["keyed", "list"]))
+        .collect()
+}
+
+/// Remove any `#[merge(...)]` attribute, returning its argument, which must be one of `allowed`.
+fn take_merge_attr(attrs: &mut Vec<syn::Attribute>, allowed: &[&str]) -> Option<String> {
+    let mut strategy = None;
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("merge") {
+            return true;
+        }
+        let ident: syn::Ident = attr.parse_args().expect("expected #[merge(...)]");
+        assert!(allowed.iter().any(|allowed| ident == allowed), "unexpected #[merge({ident})]");
+        strategy = Some(ident.to_string());
+        false
+    });
+    strategy
+}
+
+fn derives_partial_eq(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr
+                .parse_args_with(syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+                .is_ok_and(|paths| paths.iter().any(|path| path.is_ident("PartialEq")))
+    })
+}